@@ -19,7 +19,9 @@ use std::{
 };
 
 #[cfg(feature = "vapoursynth")]
-use helpers::vapoursynth::{import_lsmash, resize_node};
+use av_decoders::source::import_lsmash;
+#[cfg(feature = "vapoursynth")]
+use helpers::vapoursynth::resize_node;
 
 const TEST_FILE: &str = "./test_files/tt_sif.y4m";
 const HBD_TEST_FILE: &str = "./test_files/tt_sif_10b.y4m";
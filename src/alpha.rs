@@ -0,0 +1,123 @@
+use v_frame::{frame::Frame, pixel::Pixel, plane::Plane};
+
+use crate::{Decoder, DecoderError};
+#[cfg(any(feature = "ffmpeg", feature = "vapoursynth"))]
+use crate::{
+    DecoderConfig, DecoderImpl,
+    helpers::frame_builder::{check_pixel_type, new_padded_frame},
+};
+
+/// The alpha plane of a frame, returned by
+/// [`Decoder::read_video_frame_with_alpha`].
+#[derive(Debug, Clone)]
+pub struct AlphaPlane<T: Pixel> {
+    /// The alpha samples, with the size, bit depth, and padding of the luma plane.
+    pub plane: Plane<T>,
+    /// Whether the color planes are premultiplied by alpha. When `false`, alpha is
+    /// straight and the color planes must be multiplied by it when compositing.
+    pub premultiplied: bool,
+}
+
+impl Decoder {
+    /// Decodes the next video frame along with its alpha plane, for sources with
+    /// transparency such as ProRes 4444 or VP8 with alpha.
+    ///
+    /// Alpha is read from YUVA sources on the FFmpeg backend, unless an output format was
+    /// set on the backend, and from the `_Alpha` frame property on the VapourSynth backend.
+    /// For other sources, or frames without alpha, this returns the frame from
+    /// [`read_video_frame`](Self::read_video_frame) with no alpha.
+    ///
+    /// Alpha is straight unless the source says otherwise: VapourSynth frames are
+    /// premultiplied if their `_AlphaIsPremultiplied` property is set, while FFmpeg does
+    /// not signal premultiplication for YUVA formats, so its alpha is always reported as
+    /// straight.
+    ///
+    /// RGB and RGBA VapourSynth nodes are not supported, like everywhere else in the
+    /// crate, and fail with [`DecoderError::UnsupportedFormat`]. Convert them to YUV in
+    /// the script and keep the alpha clip attached as `_Alpha` to read it here.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecoderError::UnsupportedDecoder`] for a source with alpha, or any
+    /// VapourSynth source, when a crop, normalized output, auto-rotation, or lookahead is
+    /// enabled, since these are not applied to alpha, [`DecoderError::VariableResolution`] or
+    /// [`DecoderError::PixelTypeMismatch`] if a VapourSynth alpha clip does not match the
+    /// frame it is attached to, and otherwise the same errors as
+    /// [`read_video_frame`](Self::read_video_frame).
+    #[inline]
+    pub fn read_video_frame_with_alpha<T: Pixel>(
+        &mut self,
+    ) -> Result<(Frame<T>, Option<AlphaPlane<T>>), DecoderError> {
+        self.check_end_of_stream()?;
+        #[cfg(feature = "ffmpeg")]
+        if matches!(&self.decoder, DecoderImpl::Ffmpeg(dec) if dec.has_alpha()) {
+            let (mut frame, mut alpha) = self.new_frame_with_alpha()?;
+            if let DecoderImpl::Ffmpeg(dec) = &mut self.decoder {
+                let result = dec.read_video_frame_with_alpha(&mut frame, &mut alpha);
+                self.track_end_of_stream(result)?;
+                self.frames_read += 1;
+                let alpha = AlphaPlane {
+                    plane: alpha,
+                    premultiplied: false,
+                };
+                return Ok((frame, Some(alpha)));
+            }
+        }
+        #[cfg(feature = "vapoursynth")]
+        if matches!(&self.decoder, DecoderImpl::Vapoursynth(_)) {
+            let (mut frame, mut alpha) = self.new_frame_with_alpha()?;
+            if let DecoderImpl::Vapoursynth(dec) = &mut self.decoder {
+                let result =
+                    dec.read_video_frame_with_alpha(self.frames_read, &mut frame, &mut alpha);
+                let premultiplied = self.track_end_of_stream(result)?;
+                self.frames_read += 1;
+                let alpha = premultiplied.map(|premultiplied| AlphaPlane {
+                    plane: alpha,
+                    premultiplied,
+                });
+                return Ok((frame, alpha));
+            }
+        }
+        self.read_video_frame().map(|frame| (frame, None))
+    }
+
+    /// Allocates a frame and an alpha plane for a backend that reads alpha, after checking
+    /// that no processing is enabled that would not be applied to alpha.
+    #[cfg(any(feature = "ffmpeg", feature = "vapoursynth"))]
+    fn new_frame_with_alpha<T: Pixel>(&self) -> Result<(Frame<T>, Plane<T>), DecoderError> {
+        if self.crop.is_some()
+            || self.normalized.is_some()
+            || self.auto_rotation.is_some()
+            || self.lookahead.is_some()
+        {
+            return Err(DecoderError::UnsupportedDecoder);
+        }
+        check_pixel_type::<T>(&self.video_details)?;
+        let frame = new_padded_frame(&self.video_details, self.config)?;
+        let alpha_config = DecoderConfig {
+            luma_only: true,
+            ..self.config
+        };
+        let alpha = new_padded_frame::<T>(&self.video_details, alpha_config)?.y_plane;
+        Ok((frame, alpha))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::y4m_fixture;
+
+    #[test]
+    fn opaque_sources_have_no_alpha() {
+        let data = y4m_fixture(&[16]);
+        let mut decoder = Decoder::from_bytes(data).expect("valid Y4M header");
+
+        let (frame, alpha) = decoder
+            .read_video_frame_with_alpha::<u8>()
+            .expect("first frame");
+        assert!(alpha.is_none());
+        assert!(frame.y_plane.rows().flatten().all(|&sample| sample == 16));
+        assert_eq!(decoder.current_frame_index(), 1);
+    }
+}
@@ -0,0 +1,82 @@
+use std::{
+    sync::mpsc::{self, Receiver},
+    thread::{self, JoinHandle},
+};
+
+use v_frame::{frame::Frame, pixel::Pixel};
+
+use crate::worker::decode_all;
+use crate::{Decoder, DecoderError};
+
+impl Decoder {
+    /// Decodes frames on a background thread, so that the next frames are decoded while
+    /// the caller processes the current one.
+    ///
+    /// `open` creates the decoder on the new thread, see
+    /// [decoding on other threads](crate#decoding-on-other-threads); this works with every
+    /// backend, including VapourSynth. Up to `channel_depth` decoded frames are buffered;
+    /// decoding pauses while the channel is full and stops once the receiver is dropped.
+    /// A depth of 0 hands each frame over directly, without decoding ahead.
+    ///
+    /// The channel closes after the last frame. Any other error, including one returned
+    /// by `open`, is sent once and closes the channel. The thread exits once the channel
+    /// is closed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the thread cannot be spawned.
+    #[inline]
+    #[must_use]
+    pub fn spawn_background<T, F>(
+        open: F,
+        channel_depth: usize,
+    ) -> (JoinHandle<()>, Receiver<Result<Frame<T>, DecoderError>>)
+    where
+        T: Pixel + Send + 'static,
+        F: FnOnce() -> Result<Decoder, DecoderError> + Send + 'static,
+    {
+        let (tx, rx) = mpsc::sync_channel(channel_depth);
+        let handle = thread::spawn(move || {
+            decode_all(open, |frame| tx.send(frame).is_ok());
+        });
+
+        (handle, rx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::y4m_fixture;
+
+    #[test]
+    fn background_decoder_sends_all_frames() {
+        let data = y4m_fixture(&[16, 32, 48]);
+
+        let (handle, frames) = Decoder::spawn_background::<u8, _>(|| Decoder::from_bytes(data), 1);
+        let values: Vec<_> = frames
+            .iter()
+            .map(|frame| {
+                frame
+                    .expect("decoded frame")
+                    .y_plane
+                    .rows()
+                    .flatten()
+                    .copied()
+                    .next()
+            })
+            .collect();
+        handle.join().expect("decode thread");
+
+        assert_eq!(values, [Some(16), Some(32), Some(48)]);
+    }
+
+    #[test]
+    fn background_decoder_reports_open_errors() {
+        let (handle, frames) =
+            Decoder::spawn_background::<u8, _>(|| Decoder::from_bytes(b"garbage".to_vec()), 1);
+        assert!(frames.recv().expect("open error").is_err());
+        assert!(frames.recv().is_err());
+        handle.join().expect("decode thread");
+    }
+}
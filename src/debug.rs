@@ -0,0 +1,77 @@
+use std::{fs::File, io::Write, mem::size_of, path::Path};
+
+use v_frame::{frame::Frame, pixel::Pixel};
+
+use crate::{DecoderError, encoder::write_plane};
+
+/// Writes one frame to `path` as headerless planar YUV, for comparing decoder output
+/// against reference dumps.
+///
+/// Planes are written Y, U, V without padding, in the layout
+/// [`Decoder::from_raw_yuv`](crate::Decoder::from_raw_yuv) reads, so a dump can be loaded
+/// back or inspected with tools like `ffplay -f rawvideo`. `u16` samples are written
+/// little-endian regardless of the platform or the source's byte order. Missing chroma
+/// planes, e.g. from luma-only decoding, are omitted.
+///
+/// # Errors
+///
+/// Returns [`DecoderError::FileWriteError`] if the file cannot be created or written.
+#[inline]
+pub fn dump_frame_raw<T: Pixel + Into<u32>, P: AsRef<Path>>(
+    path: P,
+    frame: &Frame<T>,
+) -> Result<(), DecoderError> {
+    let mut file = File::create(path).map_err(|e| DecoderError::FileWriteError {
+        cause: e.to_string(),
+    })?;
+    let mut buffer = Vec::new();
+    for plane in [
+        Some(&frame.y_plane),
+        frame.u_plane.as_ref(),
+        frame.v_plane.as_ref(),
+    ]
+    .into_iter()
+    .flatten()
+    {
+        write_plane(plane, size_of::<T>(), &mut buffer);
+        file.write_all(&buffer)
+            .map_err(|e| DecoderError::FileWriteError {
+                cause: e.to_string(),
+            })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::temp_path;
+    use crate::{Decoder, VideoDetails};
+
+    #[test]
+    fn dump_round_trips_through_raw_yuv() {
+        let details = VideoDetails {
+            width: 4,
+            height: 2,
+            bit_depth: 10,
+            ..VideoDetails::default()
+        };
+        let mut data = Vec::new();
+        for sample in (0..4 * 2).map(|i| 0x0100_u16 + i).chain([0x0200; 2 * 2]) {
+            data.extend_from_slice(&sample.to_le_bytes());
+        }
+        let source = temp_path("dump-source.yuv");
+        let dump = temp_path("dump.yuv");
+        std::fs::write(&source, &data).expect("write test file");
+
+        let frame = Decoder::from_raw_yuv(&source, details)
+            .and_then(|mut decoder| decoder.read_video_frame::<u16>())
+            .expect("10-bit frame");
+        dump_frame_raw(&dump, &frame).expect("dump frame");
+        let dumped = std::fs::read(&dump).expect("read dump");
+        std::fs::remove_file(&source).expect("remove test file");
+        std::fs::remove_file(&dump).expect("remove dump");
+
+        assert_eq!(dumped, data);
+    }
+}
@@ -0,0 +1,270 @@
+use std::{io::Write, mem::size_of};
+
+use v_frame::{chroma::ChromaSubsampling, frame::Frame, pixel::Pixel, plane::Plane};
+
+use crate::helpers::convert::push_sample;
+use crate::{ChromaLocation, ColorRange, DecoderError, FieldOrder, VideoDetails};
+
+/// Writes frames as a Y4M stream, e.g. to pipe processed video into an encoder.
+///
+/// The stream header is derived from the [`VideoDetails`] given to [`new`](Self::new),
+/// so frames decoded by a [`Decoder`](crate::Decoder) can be written back out unchanged.
+pub struct Y4mEncoder<W: Write> {
+    writer: W,
+    video_details: VideoDetails,
+    buffers: [Vec<u8>; 3],
+}
+
+impl<W: Write> Y4mEncoder<W> {
+    /// Writes the Y4M stream header to `writer`.
+    ///
+    /// The colorspace tag is chosen from `chroma_sampling`, `bit_depth`, and, for 8-bit
+    /// 4:2:0, `chroma_location`. The field order, sample aspect ratio, and color range
+    /// (as the `XCOLORRANGE` extension) are written as well.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecoderError::UnsupportedFormat`] if the format has no Y4M colorspace,
+    /// [`DecoderError::FileWriteError`] if the header cannot be written.
+    #[inline]
+    pub fn new(mut writer: W, video_details: VideoDetails) -> Result<Self, DecoderError> {
+        let colorspace = y4m_colorspace(&video_details)?;
+        let frame_rate = video_details.frame_rate.reduced();
+        let aspect = video_details.sample_aspect_ratio.reduced();
+        let interlacing = match video_details.field_order {
+            FieldOrder::Progressive => 'p',
+            FieldOrder::TopFieldFirst => 't',
+            FieldOrder::BottomFieldFirst => 'b',
+            FieldOrder::Mixed => 'm',
+        };
+        let color_range = match video_details.color_range {
+            ColorRange::Limited => "LIMITED",
+            ColorRange::Full => "FULL",
+        };
+        writeln!(
+            writer,
+            "YUV4MPEG2 W{} H{} F{}:{} I{interlacing} A{}:{} {colorspace:?} XCOLORRANGE={color_range}",
+            video_details.width,
+            video_details.height,
+            frame_rate.numer(),
+            frame_rate.denom(),
+            aspect.numer(),
+            aspect.denom(),
+        )
+        .map_err(|e| DecoderError::FileWriteError {
+            cause: e.to_string(),
+        })?;
+
+        Ok(Self {
+            writer,
+            video_details,
+            buffers: Default::default(),
+        })
+    }
+
+    /// Writes one frame.
+    ///
+    /// Frames without chroma planes (e.g. decoded with `set_luma_only`) are written with
+    /// neutral chroma if the stream is not monochrome.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecoderError::GenericDecodeError`] if `T` does not match the stream's
+    /// bit depth, [`DecoderError::VariableResolution`] if the frame's dimensions do not
+    /// match the stream header, [`DecoderError::FileWriteError`] if the frame cannot be
+    /// written.
+    #[inline]
+    pub fn write_frame<T: Pixel + Into<u32>>(
+        &mut self,
+        frame: &Frame<T>,
+    ) -> Result<(), DecoderError> {
+        let bytes_per_sample = self.video_details.bytes_per_sample();
+        if size_of::<T>() != bytes_per_sample {
+            return Err(DecoderError::GenericDecodeError {
+                cause: format!(
+                    "cannot write {}-byte pixels to a {}-bit stream",
+                    size_of::<T>(),
+                    self.video_details.bit_depth
+                ),
+                source: None,
+            });
+        }
+        if plane_dimensions(&frame.y_plane) != self.video_details.plane_dimensions(0) {
+            return Err(DecoderError::VariableResolution);
+        }
+
+        let [y_buffer, u_buffer, v_buffer] = &mut self.buffers;
+        write_plane(&frame.y_plane, bytes_per_sample, y_buffer);
+        let neutral = 1_u16 << (self.video_details.bit_depth - 1);
+        let (chroma_width, chroma_height) = self.video_details.plane_dimensions(1);
+        for (plane, buffer) in [(&frame.u_plane, u_buffer), (&frame.v_plane, v_buffer)] {
+            match plane {
+                Some(plane) if plane_dimensions(plane) != (chroma_width, chroma_height) => {
+                    return Err(DecoderError::VariableResolution);
+                }
+                Some(plane) => write_plane(plane, bytes_per_sample, buffer),
+                None => {
+                    buffer.clear();
+                    for _ in 0..chroma_width * chroma_height {
+                        push_sample(buffer, neutral, bytes_per_sample);
+                    }
+                }
+            }
+        }
+
+        self.writer
+            .write_all(b"FRAME\n")
+            .and_then(|()| {
+                self.buffers
+                    .iter()
+                    .try_for_each(|buffer| self.writer.write_all(buffer))
+            })
+            .map_err(|e| DecoderError::FileWriteError {
+                cause: e.to_string(),
+            })
+    }
+}
+
+/// Returns the visible `(width, height)` of `plane`.
+fn plane_dimensions<T: Pixel>(plane: &Plane<T>) -> (usize, usize) {
+    (
+        plane.rows().next().map_or(0, <[T]>::len),
+        plane.rows().count(),
+    )
+}
+
+/// Serializes the visible samples of `plane` into `buffer`, little-endian above 8 bits.
+pub(crate) fn write_plane<T: Pixel + Into<u32>>(
+    plane: &Plane<T>,
+    bytes_per_sample: usize,
+    buffer: &mut Vec<u8>,
+) {
+    buffer.clear();
+    for row in plane.rows() {
+        for &sample in row {
+            push_sample(buffer, sample.into() as u16, bytes_per_sample);
+        }
+    }
+}
+
+/// Picks the Y4M colorspace tag for a chroma subsampling and bit depth.
+fn y4m_colorspace(details: &VideoDetails) -> Result<y4m::Colorspace, DecoderError> {
+    use y4m::Colorspace::{
+        C420, C420jpeg, C420mpeg2, C420p10, C420p12, C420paldv, C422, C422p10, C422p12, C444,
+        C444p10, C444p12, Cmono, Cmono12,
+    };
+    Ok(match (details.chroma_sampling, details.bit_depth) {
+        (ChromaSubsampling::Yuv420, 8) => match details.chroma_location {
+            ChromaLocation::Center => C420jpeg,
+            ChromaLocation::TopLeft => C420paldv,
+            ChromaLocation::Left => C420mpeg2,
+            _ => C420,
+        },
+        (ChromaSubsampling::Yuv420, 10) => C420p10,
+        (ChromaSubsampling::Yuv420, 12) => C420p12,
+        (ChromaSubsampling::Yuv422, 8) => C422,
+        (ChromaSubsampling::Yuv422, 10) => C422p10,
+        (ChromaSubsampling::Yuv422, 12) => C422p12,
+        (ChromaSubsampling::Yuv444, 8) => C444,
+        (ChromaSubsampling::Yuv444, 10) => C444p10,
+        (ChromaSubsampling::Yuv444, 12) => C444p12,
+        (ChromaSubsampling::Monochrome, 8) => Cmono,
+        (ChromaSubsampling::Monochrome, 12) => Cmono12,
+        (chroma_sampling, bit_depth) => {
+            return Err(DecoderError::UnsupportedFormat {
+                fmt: format!(
+                    "{bit_depth}-bit {} in Y4M",
+                    crate::chroma_label(chroma_sampling)
+                ),
+            });
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Decoder;
+    use crate::test_util::y4m_fixture;
+
+    #[test]
+    fn round_trips_through_decoder() {
+        let mut source = y4m_fixture(&[0]);
+        let frame_start = source.len() - (4 * 2 + 2 * 2);
+        source[frame_start..].copy_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12]);
+        let mut decoder =
+            Decoder::from_reader(std::io::Cursor::new(source)).expect("valid Y4M header");
+        let details = VideoDetails {
+            field_order: FieldOrder::TopFieldFirst,
+            color_range: ColorRange::Full,
+            ..*decoder.get_video_details()
+        };
+        let frame = decoder.read_video_frame::<u8>().expect("one frame");
+
+        let mut output = Vec::new();
+        let mut encoder = Y4mEncoder::new(&mut output, details).expect("supported format");
+        encoder.write_frame(&frame).expect("write to Vec");
+        drop(encoder);
+
+        let mut decoder =
+            Decoder::from_reader(std::io::Cursor::new(output)).expect("written Y4M header");
+        assert_eq!(*decoder.get_video_details(), details);
+        let written = decoder.read_video_frame::<u8>().expect("one frame");
+        for (original, written) in [
+            (&frame.y_plane, &written.y_plane),
+            (
+                frame.u_plane.as_ref().expect("U"),
+                written.u_plane.as_ref().expect("U"),
+            ),
+            (
+                frame.v_plane.as_ref().expect("V"),
+                written.v_plane.as_ref().expect("V"),
+            ),
+        ] {
+            assert!(original.rows().eq(written.rows()));
+        }
+    }
+
+    #[test]
+    fn rejects_frames_that_do_not_match_the_header() {
+        let mut decoder = Decoder::from_reader(std::io::Cursor::new(y4m_fixture(&[16])))
+            .expect("valid Y4M header");
+        let frame = decoder.read_video_frame::<u8>().expect("one frame");
+        let details = VideoDetails {
+            width: 8,
+            ..*decoder.get_video_details()
+        };
+
+        let mut output = Vec::new();
+        let mut encoder = Y4mEncoder::new(&mut output, details).expect("supported format");
+        assert!(matches!(
+            encoder.write_frame(&frame),
+            Err(DecoderError::VariableResolution)
+        ));
+        drop(encoder);
+        assert!(!output.windows(5).any(|window| window == b"FRAME"));
+    }
+
+    #[test]
+    fn maps_colorspace_from_chroma_and_bit_depth() {
+        let details = VideoDetails {
+            bit_depth: 10,
+            chroma_sampling: ChromaSubsampling::Yuv422,
+            ..VideoDetails::default()
+        };
+        assert!(matches!(
+            y4m_colorspace(&details),
+            Ok(y4m::Colorspace::C422p10)
+        ));
+
+        let details = VideoDetails {
+            bit_depth: 10,
+            chroma_sampling: ChromaSubsampling::Monochrome,
+            ..VideoDetails::default()
+        };
+        assert!(matches!(
+            y4m_colorspace(&details),
+            Err(DecoderError::UnsupportedFormat { .. })
+        ));
+    }
+}
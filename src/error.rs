@@ -1,5 +1,12 @@
+use std::error::Error as StdError;
+use std::io;
+use std::sync::Arc;
+
 use thiserror::Error;
 
+/// The original error behind a [`DecoderError`], shared so that errors stay cloneable.
+pub type ErrorSource = Arc<dyn StdError + Send + Sync>;
+
 /// Errors that can occur during video decoding operations.
 #[derive(Debug, Clone, Error)]
 pub enum DecoderError {
@@ -12,6 +19,31 @@ pub enum DecoderError {
     FileReadError {
         /// The underlying cause of the error.
         cause: String,
+        /// The original error, if the cause came from one.
+        #[source]
+        source: Option<ErrorSource>,
+    },
+
+    /// An I/O operation failed, e.g. opening or reading the input file.
+    ///
+    /// `kind` distinguishes causes such as [`io::ErrorKind::NotFound`] and
+    /// [`io::ErrorKind::PermissionDenied`].
+    #[error("failed to read input ({cause})")]
+    Io {
+        /// The kind of the underlying I/O error.
+        kind: io::ErrorKind,
+        /// The underlying cause of the error.
+        cause: String,
+        /// The original I/O error.
+        #[source]
+        source: Option<ErrorSource>,
+    },
+
+    /// Failed to write output, e.g. from [`Y4mEncoder`](crate::Y4mEncoder).
+    #[error("failed to write output ({cause})")]
+    FileWriteError {
+        /// The underlying cause of the error.
+        cause: String,
     },
 
     /// VapourSynth script execution error (requires `vapoursynth` feature).
@@ -38,12 +70,23 @@ pub enum DecoderError {
         cause: String,
     },
 
+    /// A VapourSynth plugin is not installed (requires `vapoursynth` feature).
+    #[cfg(feature = "vapoursynth")]
+    #[error("Vapoursynth plugin {plugin} is not installed")]
+    MissingVapoursynthPlugin {
+        /// The identifier of the missing plugin, e.g. `com.vapoursynth.ffms2`.
+        plugin: String,
+    },
+
     /// FFmpeg internal error (requires `ffmpeg` feature).
     #[cfg(feature = "ffmpeg")]
     #[error("FFMpeg internal error ({cause})")]
     FfmpegInternalError {
         /// The underlying cause of the error.
         cause: String,
+        /// The original error, if the cause came from one.
+        #[source]
+        source: Option<ErrorSource>,
     },
 
     /// FFMS2 internal error (requires `ffms2` feature).
@@ -59,6 +102,16 @@ pub enum DecoderError {
     GenericDecodeError {
         /// The underlying cause of the error.
         cause: String,
+        /// The original error, if the cause came from one.
+        #[source]
+        source: Option<ErrorSource>,
+    },
+
+    /// The stream header is malformed, e.g. a Y4M header with a zero frame size.
+    #[error("invalid stream header ({cause})")]
+    InvalidHeader {
+        /// The underlying cause of the error.
+        cause: String,
     },
 
     /// No decodeable video stream found in the input file.
@@ -96,6 +149,16 @@ pub enum DecoderError {
         y: usize,
     },
 
+    /// The requested pixel type does not match the video's bit depth, e.g. reading `u8`
+    /// frames from a 10-bit source.
+    #[error("requested {got}-byte pixels from a video with {expected}-byte samples")]
+    PixelTypeMismatch {
+        /// Bytes per sample of the video.
+        expected: usize,
+        /// Size in bytes of the requested pixel type.
+        got: usize,
+    },
+
     /// Unsupported pixel format or codec.
     #[error("unsupported video format {fmt}")]
     UnsupportedFormat {
@@ -103,3 +166,93 @@ pub enum DecoderError {
         fmt: String,
     },
 }
+
+impl DecoderError {
+    /// Returns `true` if this is [`DecoderError::EndOfFile`], i.e. the stream ended normally.
+    #[inline]
+    #[must_use]
+    pub const fn is_eof(&self) -> bool {
+        matches!(self, Self::EndOfFile)
+    }
+
+    /// Wraps `error` as [`DecoderError::FileReadError`], keeping it as the source.
+    pub(crate) fn file_read(error: impl StdError + Send + Sync + 'static) -> Self {
+        Self::FileReadError {
+            cause: error.to_string(),
+            source: Some(Arc::new(error)),
+        }
+    }
+
+    /// Wraps `error` as [`DecoderError::FfmpegInternalError`], keeping it as the source.
+    #[cfg(feature = "ffmpeg")]
+    pub(crate) fn ffmpeg(error: impl StdError + Send + Sync + 'static) -> Self {
+        Self::FfmpegInternalError {
+            cause: error.to_string(),
+            source: Some(Arc::new(error)),
+        }
+    }
+
+    /// Wraps `error` as [`DecoderError::GenericDecodeError`], keeping it as the source.
+    pub(crate) fn generic(error: impl StdError + Send + Sync + 'static) -> Self {
+        Self::GenericDecodeError {
+            cause: error.to_string(),
+            source: Some(Arc::new(error)),
+        }
+    }
+}
+
+impl From<io::Error> for DecoderError {
+    /// Wraps an I/O error as [`DecoderError::Io`], keeping its kind and the error itself as
+    /// the source.
+    #[inline]
+    fn from(error: io::Error) -> Self {
+        Self::Io {
+            kind: error.kind(),
+            cause: error.to_string(),
+            source: Some(Arc::new(error)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_underlying_error_as_source() {
+        let error = DecoderError::generic(io::Error::other("bad packet"));
+        assert!(error.clone().source().is_some());
+
+        assert_eq!(error.to_string(), "internal decoder error (bad packet)");
+        let source = error.source().expect("source is kept");
+        assert!(source.downcast_ref::<io::Error>().is_some());
+    }
+
+    #[test]
+    fn io_errors_keep_their_source() {
+        let error = DecoderError::from(io::Error::new(io::ErrorKind::NotFound, "no such file"));
+
+        assert!(matches!(
+            error,
+            DecoderError::Io {
+                kind: io::ErrorKind::NotFound,
+                ..
+            }
+        ));
+        let source = error.source().expect("source is kept");
+        assert_eq!(
+            source.downcast_ref::<io::Error>().map(io::Error::kind),
+            Some(io::ErrorKind::NotFound)
+        );
+    }
+
+    #[test]
+    fn message_only_errors_have_no_source() {
+        let error = DecoderError::GenericDecodeError {
+            cause: "frame request timed out".to_string(),
+            source: None,
+        };
+
+        assert!(error.source().is_none());
+    }
+}
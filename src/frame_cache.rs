@@ -0,0 +1,98 @@
+use std::any::Any;
+use std::collections::VecDeque;
+
+use v_frame::{frame::Frame, pixel::Pixel};
+
+use crate::FrameMeta;
+use crate::error::DecoderError;
+
+/// Least recently used cache of decoded frames, keyed by frame index, so that seeking
+/// back to the same frames does not decode them again.
+///
+/// Frames are stored type-erased, since the pixel type is only chosen per read.
+pub(crate) struct FrameCache {
+    capacity: usize,
+    /// Cached frames, least recently used first
+    frames: VecDeque<(usize, Box<dyn Any>, FrameMeta)>,
+}
+
+impl FrameCache {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            frames: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub(crate) const fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Drops all cached frames, e.g. because the decoder's output format changed.
+    pub(crate) fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    /// Returns a copy of the cached frame at `index` and what the backend reported about
+    /// it, if there is one, and marks it as the most recently used.
+    pub(crate) fn get<T: Pixel>(
+        &mut self,
+        index: usize,
+    ) -> Result<Option<(Frame<T>, FrameMeta)>, DecoderError> {
+        let Some(entry) = self
+            .frames
+            .iter()
+            .position(|&(i, ..)| i == index)
+            .and_then(|position| self.frames.remove(position))
+        else {
+            return Ok(None);
+        };
+        let frame = entry
+            .1
+            .downcast_ref::<Frame<T>>()
+            .cloned()
+            .map(|frame| (frame, entry.2));
+        self.frames.push_back(entry);
+        frame
+            .ok_or_else(|| DecoderError::GenericDecodeError {
+                cause: "cached frame was decoded with a different pixel type".to_string(),
+                source: None,
+            })
+            .map(Some)
+    }
+
+    /// Caches a freshly decoded frame, evicting the least recently used one if the cache
+    /// is full.
+    pub(crate) fn insert<T: Pixel>(&mut self, index: usize, frame: Frame<T>, meta: FrameMeta) {
+        self.frames.retain(|&(i, ..)| i != index);
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+        self.frames.push_back((index, Box::new(frame), meta));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DecoderConfig, VideoDetails, helpers::frame_builder::new_padded_frame};
+
+    fn frame() -> Frame<u8> {
+        new_padded_frame(&VideoDetails::default(), DecoderConfig::default()).expect("640x480")
+    }
+
+    #[test]
+    fn evicts_least_recently_used_frame() {
+        let mut cache = FrameCache::new(2);
+        cache.insert(0, frame(), FrameMeta::default());
+        cache.insert(1, frame(), FrameMeta::default());
+        // Touching frame 0 makes frame 1 the least recently used
+        assert!(cache.get::<u8>(0).expect("same pixel type").is_some());
+        cache.insert(2, frame(), FrameMeta::default());
+
+        assert!(cache.get::<u8>(1).expect("same pixel type").is_none());
+        assert!(cache.get::<u8>(0).expect("same pixel type").is_some());
+        assert!(cache.get::<u8>(2).expect("same pixel type").is_some());
+        assert!(cache.get::<u16>(2).is_err());
+    }
+}
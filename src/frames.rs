@@ -0,0 +1,223 @@
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+
+use v_frame::{frame::Frame, pixel::Pixel};
+
+use crate::{Decoder, DecoderError};
+
+impl Decoder {
+    /// Returns an iterator over the remaining frames, decoded with
+    /// [`read_video_frame`](Self::read_video_frame).
+    ///
+    /// The iterator ends at the end of the stream. Any other error is yielded once, after
+    /// which the iterator ends too.
+    #[inline]
+    #[must_use]
+    pub fn frames<T: Pixel>(&mut self) -> Frames<'_, T> {
+        Frames {
+            decoder: self,
+            done: false,
+            pixel: PhantomData,
+        }
+    }
+
+    /// Consumes the decoder, returning an iterator over its remaining frames.
+    ///
+    /// This is the generic form of the [`IntoIterator`] implementation, which always
+    /// yields `u8` frames; use it with `u16` for high bit depth sources. The iterator ends
+    /// like the one returned by [`frames`](Self::frames).
+    #[inline]
+    #[must_use]
+    pub fn into_frames<T: Pixel>(self) -> IntoFrames<T> {
+        IntoFrames {
+            decoder: self,
+            done: false,
+            pixel: PhantomData,
+        }
+    }
+
+    /// Consumes the decoder, returning an iterator that applies `f` to each remaining
+    /// frame as it is decoded, e.g. to compute a per-frame metric.
+    ///
+    /// Frames are decoded lazily, one per call to [`next`](Iterator::next), and errors
+    /// are passed through without calling `f`. The iterator ends like the one returned by
+    /// [`frames`](Self::frames).
+    #[inline]
+    #[must_use]
+    pub fn map_frames<T: Pixel, U, F: FnMut(Frame<T>) -> U>(self, f: F) -> MapFrames<T, F> {
+        MapFrames {
+            frames: self.into_frames(),
+            f,
+        }
+    }
+}
+
+/// Decodes the next frame for an iterator, marking it `done` at the end of the stream or
+/// after an error.
+fn next_frame<T: Pixel>(
+    decoder: &mut Decoder,
+    done: &mut bool,
+) -> Option<Result<Frame<T>, DecoderError>> {
+    if *done {
+        return None;
+    }
+    match decoder.read_video_frame() {
+        Ok(frame) => Some(Ok(frame)),
+        Err(DecoderError::EndOfFile) => {
+            *done = true;
+            None
+        }
+        Err(e) => {
+            *done = true;
+            Some(Err(e))
+        }
+    }
+}
+
+/// Iterator over the frames of a borrowed decoder, created by [`Decoder::frames`].
+pub struct Frames<'a, T> {
+    decoder: &'a mut Decoder,
+    done: bool,
+    pixel: PhantomData<T>,
+}
+
+impl<T: Pixel> Iterator for Frames<'_, T> {
+    type Item = Result<Frame<T>, DecoderError>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        next_frame(self.decoder, &mut self.done)
+    }
+}
+
+impl<T: Pixel> FusedIterator for Frames<'_, T> {}
+
+/// Iterator over the frames of an owned decoder, created by [`Decoder::into_frames`] or
+/// [`Decoder::into_iter`](IntoIterator::into_iter).
+pub struct IntoFrames<T> {
+    decoder: Decoder,
+    done: bool,
+    pixel: PhantomData<T>,
+}
+
+impl<T> IntoFrames<T> {
+    /// Returns the underlying decoder, positioned after the last frame yielded.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> Decoder {
+        self.decoder
+    }
+}
+
+impl<T: Pixel> Iterator for IntoFrames<T> {
+    type Item = Result<Frame<T>, DecoderError>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        next_frame(&mut self.decoder, &mut self.done)
+    }
+}
+
+impl<T: Pixel> FusedIterator for IntoFrames<T> {}
+
+/// Iterator over the results of a closure applied to each frame of an owned decoder,
+/// created by [`Decoder::map_frames`].
+pub struct MapFrames<T, F> {
+    frames: IntoFrames<T>,
+    f: F,
+}
+
+impl<T, F> MapFrames<T, F> {
+    /// Returns the underlying decoder, positioned after the last frame mapped.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> Decoder {
+        self.frames.into_inner()
+    }
+}
+
+impl<T: Pixel, U, F: FnMut(Frame<T>) -> U> Iterator for MapFrames<T, F> {
+    type Item = Result<U, DecoderError>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.frames.next().map(|frame| frame.map(&mut self.f))
+    }
+}
+
+impl<T: Pixel, U, F: FnMut(Frame<T>) -> U> FusedIterator for MapFrames<T, F> {}
+
+/// Iterates over 8-bit frames; use [`Decoder::into_frames`] for other pixel types.
+impl IntoIterator for Decoder {
+    type Item = Result<Frame<u8>, DecoderError>;
+    type IntoIter = IntoFrames<u8>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.into_frames()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::y4m_fixture;
+
+    #[test]
+    fn owned_decoder_iterates_until_end_of_stream() {
+        let decoder = Decoder::from_bytes(y4m_fixture(&[16, 32, 48])).expect("valid Y4M header");
+
+        let mut first_luma = Vec::new();
+        for frame in decoder {
+            let frame = frame.expect("frame in stream");
+            first_luma.extend(frame.y_plane.rows().flatten().next().copied());
+        }
+        assert_eq!(first_luma, [16, 32, 48]);
+    }
+
+    #[test]
+    fn borrowed_iterator_leaves_decoder_usable() {
+        let mut decoder =
+            Decoder::from_bytes(y4m_fixture(&[16, 32, 48])).expect("valid Y4M header");
+
+        assert_eq!(decoder.frames::<u8>().take(2).count(), 2);
+        assert_eq!(decoder.current_frame_index(), 2);
+        assert_eq!(decoder.frames::<u8>().count(), 1);
+    }
+
+    #[test]
+    fn maps_frames_lazily() {
+        let decoder = Decoder::from_bytes(y4m_fixture(&[16, 32, 48])).expect("valid Y4M header");
+
+        let mut calls = 0;
+        let mut first_luma = decoder.map_frames(|frame: Frame<u8>| {
+            calls += 1;
+            frame.y_plane.rows().flatten().next().copied()
+        });
+        assert_eq!(
+            first_luma.next().expect("frame 0").expect("decoded"),
+            Some(16)
+        );
+        let rest = first_luma
+            .by_ref()
+            .collect::<Result<Vec<_>, _>>()
+            .expect("remaining frames");
+        assert_eq!(rest, [Some(32), Some(48)]);
+        assert!(first_luma.next().is_none());
+        drop(first_luma);
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn error_ends_iteration() {
+        let mut decoder =
+            Decoder::from_bytes(y4m_fixture(&[16, 32, 48])).expect("valid Y4M header");
+
+        let mut frames = decoder.frames::<u16>();
+        assert!(matches!(
+            frames.next(),
+            Some(Err(DecoderError::PixelTypeMismatch { .. }))
+        ));
+        assert!(frames.next().is_none());
+    }
+}
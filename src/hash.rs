@@ -0,0 +1,107 @@
+use std::mem::size_of;
+
+use v_frame::{frame::Frame, pixel::Pixel, plane::Plane};
+
+use crate::{Decoder, DecoderError};
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+impl Decoder {
+    /// Decodes the next video frame along with a hash of its content, e.g. to detect
+    /// repeated frames in static scenes or to key a cache of decoded frames.
+    ///
+    /// See [`hash_frame`] for what the hash covers.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`read_video_frame`](Self::read_video_frame).
+    #[inline]
+    pub fn read_video_frame_hashed<T: Pixel + Into<u32>>(
+        &mut self,
+    ) -> Result<(Frame<T>, u64), DecoderError> {
+        let frame = self.read_video_frame()?;
+        let hash = hash_frame(&frame);
+        Ok((frame, hash))
+    }
+}
+
+/// Computes a 64-bit FNV-1a hash of the visible samples of `frame`.
+///
+/// Padding is skipped, so equal pictures hash equal regardless of how their frames were
+/// allocated. Plane dimensions are part of the hash, and samples are hashed in
+/// little-endian order, so hashes are stable across platforms. This is not a
+/// cryptographic hash: it is meant for deduplication and caching, where rare collisions
+/// are acceptable.
+#[inline]
+#[must_use]
+pub fn hash_frame<T: Pixel + Into<u32>>(frame: &Frame<T>) -> u64 {
+    let mut hash = FNV_OFFSET_BASIS;
+    for plane in [
+        Some(&frame.y_plane),
+        frame.u_plane.as_ref(),
+        frame.v_plane.as_ref(),
+    ] {
+        match plane {
+            Some(plane) => hash = hash_plane(hash, plane),
+            // Distinguishes a missing plane from an empty one
+            None => hash = fnv1a(hash, &[0xff]),
+        }
+    }
+    hash
+}
+
+fn hash_plane<T: Pixel + Into<u32>>(mut hash: u64, plane: &Plane<T>) -> u64 {
+    let width = plane.rows().next().map_or(0, <[T]>::len);
+    let height = plane.rows().count();
+    hash = fnv1a(hash, &(width as u64).to_le_bytes());
+    hash = fnv1a(hash, &(height as u64).to_le_bytes());
+    for &sample in plane.rows().flatten() {
+        let bytes = Into::<u32>::into(sample).to_le_bytes();
+        hash = fnv1a(hash, &bytes[..size_of::<T>()]);
+    }
+    hash
+}
+
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VideoDetails;
+    use crate::test_util::y4m_fixture;
+
+    #[test]
+    fn ignores_padding() {
+        let data = y4m_fixture(&[16, 16, 48]);
+        let mut decoder = Decoder::from_bytes(data).expect("valid Y4M header");
+
+        let (_, first) = decoder.read_video_frame_hashed::<u8>().expect("frame 0");
+        decoder.set_padding(0, 0, 0, 0).expect("no padding");
+        let (unpadded, second) = decoder.read_video_frame_hashed::<u8>().expect("frame 1");
+        let (_, third) = decoder.read_video_frame_hashed::<u8>().expect("frame 2");
+
+        assert_eq!(first, second);
+        assert_eq!(hash_frame(&unpadded), second);
+        assert_ne!(second, third);
+    }
+
+    #[test]
+    fn distinguishes_missing_chroma() {
+        let details = VideoDetails {
+            width: 4,
+            height: 2,
+            ..VideoDetails::default()
+        };
+        let frame = details.new_black_frame::<u8>().expect("black frame");
+        let mut luma_only = frame.clone();
+        luma_only.u_plane = None;
+        luma_only.v_plane = None;
+
+        assert_ne!(hash_frame(&frame), hash_frame(&luma_only));
+    }
+}
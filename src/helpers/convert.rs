@@ -0,0 +1,246 @@
+use v_frame::{frame::Frame, pixel::Pixel, plane::Plane};
+
+use super::frame_builder::new_padded_frame;
+use crate::{DecoderConfig, DecoderError, VideoDetails};
+
+/// Converts `frame` from `source` to `target`'s resolution, bit depth, and chroma
+/// subsampling.
+///
+/// Every plane is resized bilinearly to its target size, which also resamples chroma, and
+/// samples are rescaled to the target bit depth with rounding. Chroma missing from the
+/// source, such as for monochrome video, is filled with the neutral value.
+pub(crate) fn convert_frame<S: Pixel + Into<u32>, T: Pixel>(
+    frame: &Frame<S>,
+    source: &VideoDetails,
+    target: &VideoDetails,
+    config: DecoderConfig,
+) -> Result<Frame<T>, DecoderError> {
+    let mut output: Frame<T> = new_padded_frame(target, config)?;
+    let sources = [
+        Some(&frame.y_plane),
+        frame.u_plane.as_ref(),
+        frame.v_plane.as_ref(),
+    ];
+    let targets = [
+        Some(&mut output.y_plane),
+        output.u_plane.as_mut(),
+        output.v_plane.as_mut(),
+    ];
+    let mut buffer = Vec::new();
+    for (index, (src, dst)) in sources.into_iter().zip(targets).enumerate() {
+        let Some(dst) = dst else {
+            continue;
+        };
+        let size = target.plane_dimensions(index);
+        buffer.clear();
+        match src {
+            Some(src) => resample_plane(
+                src,
+                source.plane_dimensions(index),
+                source.bit_depth,
+                size,
+                target,
+                &mut buffer,
+            ),
+            None => {
+                let neutral = 1_u16 << (target.bit_depth - 1);
+                for _ in 0..size.0 * size.1 {
+                    push_sample(&mut buffer, neutral, target.bytes_per_sample());
+                }
+            }
+        }
+        dst.copy_from_u8_slice(&buffer)
+            .map_err(DecoderError::generic)?;
+    }
+    Ok(output)
+}
+
+/// Reduces a high bit depth `frame` of `source` to 8 bits, right-shifting each sample by
+/// `bit_depth - 8` with rounding. Planes missing from `frame` stay missing.
+pub(crate) fn reduce_to_8bit(
+    frame: &Frame<u16>,
+    source: &VideoDetails,
+    config: DecoderConfig,
+) -> Result<Frame<u8>, DecoderError> {
+    let target = VideoDetails {
+        bit_depth: 8,
+        ..*source
+    };
+    let mut output: Frame<u8> = new_padded_frame(
+        &target,
+        DecoderConfig {
+            luma_only: frame.u_plane.is_none(),
+            ..config
+        },
+    )?;
+    let shift = source.bit_depth - 8;
+    let half = (1_u32 << shift) >> 1;
+    let planes = [
+        (Some(&frame.y_plane), Some(&mut output.y_plane)),
+        (frame.u_plane.as_ref(), output.u_plane.as_mut()),
+        (frame.v_plane.as_ref(), output.v_plane.as_mut()),
+    ];
+    let mut buffer = Vec::new();
+    for (src, dst) in planes {
+        let (Some(src), Some(dst)) = (src, dst) else {
+            continue;
+        };
+        buffer.clear();
+        buffer.extend(
+            src.rows()
+                .flatten()
+                .map(|&sample| ((u32::from(sample) + half) >> shift).min(255) as u8),
+        );
+        dst.copy_from_u8_slice(&buffer)
+            .map_err(DecoderError::generic)?;
+    }
+    Ok(output)
+}
+
+/// Bilinearly resizes the visible `(width, height)` area of `plane` to `size` and writes
+/// the samples, rescaled from `bit_depth` to the target bit depth, into `buffer`.
+fn resample_plane<S: Pixel + Into<u32>>(
+    plane: &Plane<S>,
+    (width, height): (usize, usize),
+    bit_depth: usize,
+    size: (usize, usize),
+    target: &VideoDetails,
+    buffer: &mut Vec<u8>,
+) {
+    let rows: Vec<&[S]> = plane.rows().take(height).collect();
+    let scale = f32::from(1_u16 << (target.bit_depth - 1)) / f32::from(1_u16 << (bit_depth - 1));
+    let max = ((1_u32 << target.bit_depth) - 1) as f32;
+    let sample = |x: usize, y: usize| -> f32 { Into::<u32>::into(rows[y][x]) as f32 };
+    let xs: Vec<_> = (0..size.0)
+        .map(|x| source_position(x, width, size.0))
+        .collect();
+    for y in 0..size.1 {
+        let (y0, y1, wy) = source_position(y, height, size.1);
+        for &(x0, x1, wx) in &xs {
+            let top = sample(x0, y0).mul_add(1.0 - wx, sample(x1, y0) * wx);
+            let bottom = sample(x0, y1).mul_add(1.0 - wx, sample(x1, y1) * wx);
+            let value = top.mul_add(1.0 - wy, bottom * wy) * scale;
+            push_sample(
+                buffer,
+                value.round().clamp(0.0, max) as u16,
+                target.bytes_per_sample(),
+            );
+        }
+    }
+}
+
+/// Maps output sample `index` of `output_len` onto the two nearest of `input_len` input
+/// samples, aligning sample centers, and returns them with the weight of the second one.
+fn source_position(index: usize, input_len: usize, output_len: usize) -> (usize, usize, f32) {
+    let position = ((index as f32 + 0.5) * input_len as f32 / output_len as f32 - 0.5).max(0.0);
+    let first = (position as usize).min(input_len - 1);
+    let second = (first + 1).min(input_len - 1);
+    (first, second, position - first as f32)
+}
+
+/// Appends `sample` to `buffer` as one byte, or as two little-endian bytes above 8 bits.
+pub(crate) fn push_sample(buffer: &mut Vec<u8>, sample: u16, bytes_per_sample: usize) {
+    if bytes_per_sample == 1 {
+        buffer.push(sample as u8);
+    } else {
+        buffer.extend_from_slice(&sample.to_le_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use v_frame::chroma::ChromaSubsampling;
+
+    use super::*;
+
+    #[test]
+    fn converts_bit_depth_and_subsampling() {
+        let source = VideoDetails {
+            width: 4,
+            height: 2,
+            chroma_sampling: ChromaSubsampling::Yuv444,
+            ..VideoDetails::default()
+        };
+        let target = VideoDetails {
+            width: 2,
+            height: 2,
+            bit_depth: 10,
+            chroma_sampling: ChromaSubsampling::Yuv420,
+            ..source
+        };
+        let mut frame: Frame<u8> =
+            new_padded_frame(&source, DecoderConfig::default()).expect("source frame");
+        frame
+            .y_plane
+            .copy_from_u8_slice(&[16, 48, 100, 100, 16, 48, 100, 100])
+            .expect("luma");
+        for plane in [frame.u_plane.as_mut(), frame.v_plane.as_mut()]
+            .into_iter()
+            .flatten()
+        {
+            plane.copy_from_u8_slice(&[128; 8]).expect("chroma");
+        }
+
+        let converted: Frame<u16> =
+            convert_frame(&frame, &source, &target, DecoderConfig::default())
+                .expect("converted frame");
+        for row in converted.y_plane.rows() {
+            assert_eq!(row, [128, 400]);
+        }
+        let u_plane = converted.u_plane.as_ref().expect("4:2:0 has chroma");
+        assert_eq!(u_plane.rows().flatten().copied().collect::<Vec<_>>(), [512]);
+    }
+
+    #[test]
+    fn reduces_to_8bit_with_rounding() {
+        let source = VideoDetails {
+            width: 4,
+            height: 2,
+            bit_depth: 10,
+            chroma_sampling: ChromaSubsampling::Monochrome,
+            ..VideoDetails::default()
+        };
+        let mut frame: Frame<u16> =
+            new_padded_frame(&source, DecoderConfig::default()).expect("source frame");
+        let samples: Vec<u8> = [0_u16, 1, 2, 64, 513, 1021, 1022, 1023]
+            .into_iter()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        frame.y_plane.copy_from_u8_slice(&samples).expect("luma");
+
+        let reduced =
+            reduce_to_8bit(&frame, &source, DecoderConfig::default()).expect("8-bit frame");
+        assert_eq!(
+            reduced
+                .y_plane
+                .rows()
+                .flatten()
+                .copied()
+                .collect::<Vec<_>>(),
+            [0, 0, 1, 16, 128, 255, 255, 255]
+        );
+        assert!(reduced.u_plane.is_none());
+    }
+
+    #[test]
+    fn fills_missing_chroma_with_neutral() {
+        let source = VideoDetails {
+            width: 2,
+            height: 2,
+            chroma_sampling: ChromaSubsampling::Monochrome,
+            ..VideoDetails::default()
+        };
+        let target = VideoDetails {
+            chroma_sampling: ChromaSubsampling::Yuv420,
+            ..source
+        };
+        let frame: Frame<u8> =
+            new_padded_frame(&source, DecoderConfig::default()).expect("source frame");
+
+        let converted: Frame<u8> =
+            convert_frame(&frame, &source, &target, DecoderConfig::default())
+                .expect("converted frame");
+        let v_plane = converted.v_plane.as_ref().expect("4:2:0 has chroma");
+        assert!(v_plane.rows().flatten().all(|&sample| sample == 128));
+    }
+}
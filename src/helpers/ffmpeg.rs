@@ -1,6 +1,8 @@
 extern crate ffmpeg_the_third as ffmpeg;
 
 use std::path::Path;
+use std::thread;
+use std::time::Duration;
 
 use ffmpeg::{
     codec::{decoder, packet},
@@ -8,14 +10,21 @@ use ffmpeg::{
     format::context,
     frame,
     media::Type,
+    software::scaling,
+    util::error::{EAGAIN, ETIMEDOUT},
 };
 use ffmpeg_the_third::threading;
 use num_rational::Rational32;
-use v_frame::{chroma::ChromaSubsampling, frame::Frame, pixel::Pixel};
+use v_frame::{chroma::ChromaSubsampling, frame::Frame, pixel::Pixel, plane::Plane};
 
-use crate::{VideoDetails, error::DecoderError};
-
-use super::frame_builder::new_padded_frame;
+use super::frame_builder::check_sample_storage;
+use crate::{
+    Backend, ChromaLocation, ColorPrimaries, ColorRange, ContentLightLevel, FieldOrder, FrameFlags,
+    FrameTimestamp, HdrMetadata, MasteringDisplay, MatrixCoefficients, Resizer, SourceInfo,
+    TransferCharacteristics, VideoDetails,
+    error::DecoderError,
+    util::{sample_aspect_ratio, ticks_to_duration},
+};
 
 /// An interface that is used for decoding a video stream using ffmpeg
 ///
@@ -29,8 +38,28 @@ pub struct FfmpegDecoder {
     #[allow(missing_docs)]
     pub video_details: VideoDetails,
     stream_index: usize,
+    time_base: Rational32,
+    last_pts: Option<i64>,
+    last_keyframe: bool,
     end_of_stream: bool,
     eof_sent: bool,
+    frames_decoded: usize,
+    frame_count_tolerance: Option<usize>,
+    variable_frame_rate: bool,
+    strict_frame_rate: bool,
+    /// Whether packets keep their container timestamps instead of being stamped with the
+    /// index of the frame being read, set with
+    /// [`set_sequential_decoding`](Self::set_sequential_decoding)
+    sequential: bool,
+    scaler: Option<scaling::Context>,
+    error_resilience: ErrorResilience,
+    /// The codec's own `err_recognition`, `flags`, and `flags2`
+    default_error_flags: (i32, i32, i32),
+    /// Frame decoded by [`seek_to_keyframe`](Self::seek_to_keyframe), returned by the next read
+    pending: Option<frame::Video>,
+    /// Whether the input was opened with [`new_url`](Self::new_url)
+    network: bool,
+    retry_policy: RetryPolicy,
 }
 
 impl FfmpegDecoder {
@@ -40,7 +69,11 @@ impl FfmpegDecoder {
     ///
     /// # Supported pixel formats
     ///
-    /// YUV 4:2:0/4:2:2/4:4:4 at 8, 10, or 12-bit (including JPEG colorspace variants).
+    /// YUV 4:2:0/4:2:2/4:4:4 at 8, 10, or 12-bit (including JPEG colorspace variants),
+    /// 8-bit semi-planar NV12/NV21, and 8-bit packed 4:2:2 YUYV/UYVY. YUVA formats, as
+    /// decoded from e.g. ProRes 4444 or VP8 with alpha, are read as their YUV planes; use
+    /// [`Decoder::read_video_frame_with_alpha`](crate::Decoder::read_video_frame_with_alpha)
+    /// to get the alpha plane too.
     ///
     /// # Errors
     ///
@@ -53,124 +86,648 @@ impl FfmpegDecoder {
     /// Desync issues have been reported with this decoder on some files. Use at your own risk.
     #[inline]
     pub fn new<P: AsRef<Path>>(input: P) -> Result<Self, DecoderError> {
-        ffmpeg::init().map_err(|e| DecoderError::FfmpegInternalError {
-            cause: e.to_string(),
-        })?;
+        let mut decoder = Self::open(input.as_ref(), ffmpeg::Dictionary::new(), true)?;
+        decoder.read_first_frame_metadata();
+        Ok(decoder)
+    }
+
+    /// Creates a new FFmpeg decoder for a network stream, such as an HTTP(S) or RTSP URL.
+    ///
+    /// `url` is passed to FFmpeg as is. Reads that stall for longer than `timeout` fail
+    /// instead of blocking forever.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecoderError::FileReadError`] if the stream cannot be opened or times out,
+    /// otherwise the same errors as [`new`](Self::new).
+    #[inline]
+    pub fn new_url(url: &str, timeout: Duration) -> Result<Self, DecoderError> {
+        let mut options = ffmpeg::Dictionary::new();
+        options.set("rw_timeout", &timeout.as_micros().to_string());
+        let mut decoder = Self::open(Path::new(url), options, true)?;
+        decoder.network = true;
+        decoder.read_first_frame_metadata();
+        Ok(decoder)
+    }
 
-        let input_ctx = format::input(input.as_ref()).map_err(|e| DecoderError::FileReadError {
-            cause: e.to_string(),
-        })?;
+    /// Reads the video details from the stream parameters, without starting decoder
+    /// threads or decoding any frames.
+    pub(crate) fn probe(input: &Path) -> Result<VideoDetails, DecoderError> {
+        Self::open(input, ffmpeg::Dictionary::new(), false).map(|decoder| decoder.video_details)
+    }
+
+    fn open(
+        input: &Path,
+        options: ffmpeg::Dictionary<'_>,
+        threaded: bool,
+    ) -> Result<Self, DecoderError> {
+        ffmpeg::init().map_err(DecoderError::ffmpeg)?;
+
+        let input_ctx =
+            format::input_with_dictionary(input, options).map_err(DecoderError::file_read)?;
         let input = input_ctx
             .streams()
             .best(Type::Video)
             .ok_or(DecoderError::NoVideoStream)?;
         let stream_index = input.index();
+        let time_base = input.time_base();
         let mut context = ffmpeg::codec::context::Context::from_parameters(input.parameters())
-            .map_err(|e| DecoderError::FfmpegInternalError {
-                cause: e.to_string(),
-            })?;
-        context.set_threading(threading::Config::kind(threading::Type::Frame));
+            .map_err(DecoderError::ffmpeg)?;
+        if threaded {
+            context.set_threading(threading::Config::kind(threading::Type::Frame));
+        }
         let mut decoder = context
             .decoder()
             .video()
             .map_err(|_| DecoderError::NoVideoStream)?;
-        decoder.set_parameters(input.parameters()).map_err(|e| {
-            DecoderError::FfmpegInternalError {
-                cause: e.to_string(),
-            }
-        })?;
+        decoder
+            .set_parameters(input.parameters())
+            .map_err(DecoderError::ffmpeg)?;
 
         let total_frames = input.frames();
         let frame_rate = input.rate();
+        let average_frame_rate = input.avg_frame_rate();
+        let variable_frame_rate = frame_rates_differ(
+            Rational32::new_raw(frame_rate.numerator(), frame_rate.denominator()),
+            Rational32::new_raw(
+                average_frame_rate.numerator(),
+                average_frame_rate.denominator(),
+            ),
+        );
+        // SAFETY: the codec context is owned by `decoder` and valid for its lifetime
+        let field_order = map_field_order(unsafe { (*decoder.as_ptr()).field_order });
+        // SAFETY: as above
+        let (color_primaries, transfer_characteristics, matrix_coefficients) = unsafe {
+            let context = &*decoder.as_ptr();
+            (
+                ColorPrimaries::from_h273(context.color_primaries as i64),
+                TransferCharacteristics::from_h273(context.color_trc as i64),
+                MatrixCoefficients::from_h273(context.colorspace as i64),
+            )
+        };
+        // SAFETY: the codec parameters are owned by `input_ctx` and valid for its lifetime
+        let hdr_metadata = unsafe { read_hdr_metadata(input.parameters().as_ptr()) };
+        // SAFETY: as above
+        let rotation = unsafe { read_display_matrix_rotation(input.parameters().as_ptr()) }
+            .or_else(|| {
+                input
+                    .metadata()
+                    .get("rotate")
+                    .and_then(|rotate| rotate.parse::<f64>().ok())
+            })
+            .map_or(0, snap_rotation);
+        // SAFETY: the codec context is owned by `decoder` and valid for its lifetime
+        let default_error_flags = unsafe {
+            let context = &*decoder.as_ptr();
+            (context.err_recognition, context.flags, context.flags2)
+        };
+        // Checked before the bit depth, so that layouts such as 4:4:0 report their
+        // subsampling instead of a bare format name
+        let pixel_format = non_jpeg_format(decoder.format());
+        let chroma_sampling = map_chroma_sampling(pixel_format)?;
+        let bit_depth = match pixel_format {
+            format::pixel::Pixel::YUV420P
+            | format::pixel::Pixel::YUV422P
+            | format::pixel::Pixel::YUV444P
+            | format::pixel::Pixel::NV12
+            | format::pixel::Pixel::NV21
+            | format::pixel::Pixel::YUYV422
+            | format::pixel::Pixel::UYVY422
+            | format::pixel::Pixel::YUVA420P
+            | format::pixel::Pixel::YUVA422P
+            | format::pixel::Pixel::YUVA444P => 8,
+            format::pixel::Pixel::YUV420P10LE
+            | format::pixel::Pixel::YUV422P10LE
+            | format::pixel::Pixel::YUV444P10LE
+            | format::pixel::Pixel::YUVA420P10LE
+            | format::pixel::Pixel::YUVA422P10LE
+            | format::pixel::Pixel::YUVA444P10LE => 10,
+            format::pixel::Pixel::YUV420P12LE
+            | format::pixel::Pixel::YUV422P12LE
+            | format::pixel::Pixel::YUV444P12LE
+            | format::pixel::Pixel::YUVA422P12LE
+            | format::pixel::Pixel::YUVA444P12LE => 12,
+            format::pixel::Pixel::YUV420P14LE
+            | format::pixel::Pixel::YUV422P14LE
+            | format::pixel::Pixel::YUV444P14LE => 14,
+            format::pixel::Pixel::YUV420P16LE
+            | format::pixel::Pixel::YUV422P16LE
+            | format::pixel::Pixel::YUV444P16LE
+            | format::pixel::Pixel::YUVA420P16LE
+            | format::pixel::Pixel::YUVA422P16LE
+            | format::pixel::Pixel::YUVA444P16LE => 16,
+            fmt => {
+                return Err(DecoderError::UnsupportedFormat {
+                    fmt: format!("{fmt:?}"),
+                });
+            }
+        };
+        check_luma_storage(pixel_format, bit_depth)?;
         Ok(Self {
             video_details: VideoDetails {
                 width: decoder.width() as usize,
                 height: decoder.height() as usize,
-                bit_depth: match decoder.format() {
-                    format::pixel::Pixel::YUV420P
-                    | format::pixel::Pixel::YUV422P
-                    | format::pixel::Pixel::YUV444P
-                    | format::pixel::Pixel::YUVJ420P
-                    | format::pixel::Pixel::YUVJ422P
-                    | format::pixel::Pixel::YUVJ444P => 8,
-                    format::pixel::Pixel::YUV420P10LE
-                    | format::pixel::Pixel::YUV422P10LE
-                    | format::pixel::Pixel::YUV444P10LE => 10,
-                    format::pixel::Pixel::YUV420P12LE
-                    | format::pixel::Pixel::YUV422P12LE
-                    | format::pixel::Pixel::YUV444P12LE => 12,
-                    fmt => {
-                        return Err(DecoderError::UnsupportedFormat {
-                            fmt: format!("{fmt:?}"),
-                        });
-                    }
-                },
-                chroma_sampling: match decoder.format() {
-                    format::pixel::Pixel::YUV420P
-                    | format::pixel::Pixel::YUVJ420P
-                    | format::pixel::Pixel::YUV420P10LE
-                    | format::pixel::Pixel::YUV420P12LE => ChromaSubsampling::Yuv420,
-                    format::pixel::Pixel::YUV422P
-                    | format::pixel::Pixel::YUVJ422P
-                    | format::pixel::Pixel::YUV422P10LE
-                    | format::pixel::Pixel::YUV422P12LE => ChromaSubsampling::Yuv422,
-                    format::pixel::Pixel::YUV444P
-                    | format::pixel::Pixel::YUVJ444P
-                    | format::pixel::Pixel::YUV444P10LE
-                    | format::pixel::Pixel::YUV444P12LE => ChromaSubsampling::Yuv444,
-                    fmt => {
-                        return Err(DecoderError::UnsupportedFormat {
-                            fmt: format!("{fmt:?}"),
-                        });
-                    }
-                },
+                bit_depth,
+                chroma_sampling,
                 frame_rate: Rational32::new(frame_rate.numerator(), frame_rate.denominator()),
                 total_frames: total_frames.try_into().ok(),
+                field_order,
+                chroma_location: map_chroma_location(decoder.chroma_location()),
+                color_range: map_color_range(decoder.format(), decoder.color_range()),
+                sample_aspect_ratio: sample_aspect_ratio(
+                    decoder.aspect_ratio().numerator().into(),
+                    decoder.aspect_ratio().denominator().into(),
+                ),
+                hdr_metadata,
+                rotation,
+                color_primaries,
+                transfer_characteristics,
+                matrix_coefficients,
             },
             decoder,
             input_ctx,
             stream_index,
+            time_base: Rational32::new(time_base.numerator(), time_base.denominator()),
+            last_pts: None,
+            last_keyframe: false,
             end_of_stream: false,
             eof_sent: false,
+            frames_decoded: 0,
+            frame_count_tolerance: None,
+            variable_frame_rate,
+            strict_frame_rate: false,
+            sequential: false,
+            scaler: None,
+            error_resilience: ErrorResilience::Default,
+            default_error_flags,
+            pending: None,
+            network: false,
+            retry_policy: RetryPolicy::default(),
         })
     }
 
+    /// Decodes the first frame to read the HDR metadata in its side data, which also
+    /// covers metadata carried only in the bitstream, and keeps the frame for the first
+    /// read. The container's metadata remains for anything the frame does not carry, or
+    /// if it cannot be decoded.
+    ///
+    /// Packets keep their timestamps here, so that enabling
+    /// [`set_sequential_decoding`](Self::set_sequential_decoding) after opening still
+    /// decodes the first frame as it would any other.
+    fn read_first_frame_metadata(&mut self) {
+        let Ok(decoded) = self.receive_frame_with(true) else {
+            return;
+        };
+        // SAFETY: the frame is owned by `decoded` and valid for its lifetime
+        let frame_metadata = unsafe { read_frame_hdr_metadata(decoded.as_ptr()) };
+        self.video_details.hdr_metadata =
+            merge_hdr_metadata(frame_metadata, self.video_details.hdr_metadata);
+        self.frames_decoded = 0;
+        self.pending = Some(decoded);
+    }
+
+    /// Checks the number of decoded frames against the container's frame count when the
+    /// end of the stream is reached.
+    ///
+    /// With `Some(tolerance)`, reaching the end of the stream after decoding more or fewer
+    /// than `total_frames` frames, give or take `tolerance`, returns
+    /// [`DecoderError::GenericDecodeError`] instead of [`DecoderError::EndOfFile`]. This
+    /// surfaces dropped or duplicated frames that would otherwise desync downstream
+    /// consumers. Streams whose container does not report a frame count are not checked.
+    ///
+    /// Disabled (`None`) by default.
+    #[inline]
+    pub fn set_frame_count_validation(&mut self, tolerance: Option<usize>) {
+        self.frame_count_tolerance = tolerance;
+    }
+
+    /// Decodes strictly sequentially, sending packets to the codec with their container
+    /// timestamps.
+    ///
+    /// By default, each packet is stamped with the index of the frame being read, which
+    /// works around desync on some files with broken timestamps, but leaves decoded frames
+    /// without their real timestamps. With sequential decoding, packets are sent as
+    /// demuxed, frames are counted as the codec outputs them, and
+    /// [`Decoder::read_video_frame_with_pts`](crate::Decoder::read_video_frame_with_pts)
+    /// reports the container timestamps. Combine it with
+    /// [`set_frame_count_validation`](Self::set_frame_count_validation) to detect frames
+    /// that were dropped or duplicated anyway.
+    ///
+    /// Disabled by default.
+    #[inline]
+    pub fn set_sequential_decoding(&mut self, enabled: bool) {
+        self.sequential = enabled;
+    }
+
+    /// Returns `true` if the container reports an average frame rate that differs from the
+    /// nominal one by more than 1%, which usually indicates variable frame rate content.
+    ///
+    /// This is a heuristic based on the stream headers, not on the timestamps of each
+    /// frame. Telecined content, and interlaced content whose nominal rate is the field
+    /// rate, also have differing rates and are reported as well, even though their frames
+    /// are evenly spaced. [`VideoDetails::frame_rate`] is the nominal rate, so frame
+    /// indices of such streams may not map to timestamps; use the presentation
+    /// timestamps instead.
+    #[inline]
+    #[must_use]
+    pub const fn is_likely_variable_frame_rate(&self) -> bool {
+        self.variable_frame_rate
+    }
+
+    /// Rejects variable frame rate content instead of treating it as constant.
+    ///
+    /// When enabled, this fails immediately if [`is_likely_variable_frame_rate`] is `true`, and
+    /// later reads fail with [`DecoderError::VariableFramerate`] once two consecutive
+    /// frames are further apart than the nominal frame duration, give or take half a
+    /// frame. This matches the VapourSynth backend, which rejects variable frame rate
+    /// clips. Frame durations are read from the container timestamps, so packets are no
+    /// longer stamped with the index of the frame being read while this is enabled.
+    /// Disabled by default.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecoderError::VariableFramerate`] if enabling it on a stream detected as
+    /// variable frame rate.
+    ///
+    /// [`is_likely_variable_frame_rate`]: Self::is_likely_variable_frame_rate
+    #[inline]
+    pub fn set_strict_frame_rate(&mut self, enabled: bool) -> Result<(), DecoderError> {
+        if enabled && self.variable_frame_rate {
+            return Err(DecoderError::VariableFramerate);
+        }
+        self.strict_frame_rate = enabled;
+        Ok(())
+    }
+
+    /// Sets how the codec handles bitstream errors, e.g. to recover what is left of a
+    /// partially corrupt file.
+    ///
+    /// See [`ErrorResilience`] for the tradeoffs of each level. Takes effect from the next
+    /// packet; frames already buffered in the codec are not affected.
+    #[inline]
+    pub fn set_error_resilience(&mut self, level: ErrorResilience) {
+        use ffmpeg::ffi::{
+            AV_CODEC_FLAG_OUTPUT_CORRUPT, AV_CODEC_FLAG2_SHOW_ALL, AV_EF_BITSTREAM, AV_EF_BUFFER,
+            AV_EF_CRCCHECK, AV_EF_EXPLODE, AV_EF_IGNORE_ERR,
+        };
+
+        let (err_recognition, flags, flags2) = self.default_error_flags;
+        // SAFETY: the codec context is owned by `decoder` and valid for its lifetime
+        let context = unsafe { &mut *self.decoder.as_mut_ptr() };
+        let output_corrupt = AV_CODEC_FLAG_OUTPUT_CORRUPT as i32;
+        let show_all = AV_CODEC_FLAG2_SHOW_ALL as i32;
+        (context.err_recognition, context.flags, context.flags2) = match level {
+            ErrorResilience::Strict => (
+                err_recognition
+                    | (AV_EF_CRCCHECK | AV_EF_BITSTREAM | AV_EF_BUFFER | AV_EF_EXPLODE) as i32,
+                flags & !output_corrupt,
+                flags2 & !show_all,
+            ),
+            ErrorResilience::Default => (err_recognition, flags, flags2),
+            ErrorResilience::Tolerant => (
+                AV_EF_IGNORE_ERR as i32,
+                flags | output_corrupt,
+                flags2 | show_all,
+            ),
+        };
+        self.error_resilience = level;
+    }
+
+    /// Sets how reads from a network stream opened with [`new_url`](Self::new_url) retry
+    /// transient failures, i.e. `EAGAIN` and read timeouts.
+    ///
+    /// Once the retries are exhausted, or on any other read error, reads fail with
+    /// [`DecoderError::FfmpegInternalError`] rather than ending the stream, so that a
+    /// dropped connection is not mistaken for the end of the video. Has no effect on files.
+    #[inline]
+    pub const fn set_retry_policy(&mut self, policy: RetryPolicy) {
+        self.retry_policy = policy;
+    }
+
+    /// Converts decoded frames to the given resolution, bit depth, and chroma layout with
+    /// `swscale`, mirroring `Ffms2Decoder::set_output_format`.
+    ///
+    /// Supports YUV 4:2:0, 4:2:2, or 4:4:4 and grayscale, in 8-bit, 10-bit, or 12-bit.
+    /// Any input format the decoder supports can be converted. Frames are scaled with
+    /// `resizer`; when the output matches the decoded format and resolution, no
+    /// conversion is performed. Full-range `YUVJ*` input stays full range.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecoderError::UnsupportedFormat`] if the bit depth / chroma combination is
+    /// not supported, [`DecoderError::FfmpegInternalError`] if `swscale` rejects the
+    /// conversion.
+    #[inline]
+    pub fn set_output_format(
+        &mut self,
+        width: usize,
+        height: usize,
+        bit_depth: u8,
+        chroma_subsampling: ChromaSubsampling,
+        resizer: Resizer,
+    ) -> Result<(), DecoderError> {
+        let output_format = output_pixel_format(bit_depth, chroma_subsampling)?;
+        // swscale would convert the implied full range of `YUVJ*` formats to limited range
+        let input_format = non_jpeg_format(self.decoder.format());
+        self.scaler = if output_format == input_format
+            && width == self.decoder.width() as usize
+            && height == self.decoder.height() as usize
+        {
+            None
+        } else {
+            Some(
+                scaling::Context::get(
+                    input_format,
+                    self.decoder.width(),
+                    self.decoder.height(),
+                    output_format,
+                    width as u32,
+                    height as u32,
+                    match resizer {
+                        Resizer::Point => scaling::Flags::POINT,
+                        Resizer::Bilinear => scaling::Flags::BILINEAR,
+                        Resizer::Bicubic => scaling::Flags::BICUBIC,
+                        Resizer::Lanczos => scaling::Flags::LANCZOS,
+                        Resizer::Spline => scaling::Flags::SPLINE,
+                    },
+                )
+                .map_err(DecoderError::ffmpeg)?,
+            )
+        };
+
+        self.video_details.width = width;
+        self.video_details.height = height;
+        self.video_details.bit_depth = bit_depth as usize;
+        self.video_details.chroma_sampling = chroma_subsampling;
+        Ok(())
+    }
+
     fn decode_frame<T: Pixel>(
         &self,
         decoded: &frame::Video,
-        luma_only: bool,
-    ) -> Result<Frame<T>, DecoderError> {
-        let mut frame: Frame<T> = new_padded_frame(&self.video_details, luma_only)?;
+        frame: &mut Frame<T>,
+    ) -> Result<(), DecoderError> {
+        let packed_luma_first = match decoded.format() {
+            format::pixel::Pixel::YUYV422 => Some(true),
+            format::pixel::Pixel::UYVY422 => Some(false),
+            _ => None,
+        };
+        if let Some(luma_first) = packed_luma_first {
+            let planes = unpack_422(
+                decoded.data(0),
+                decoded.stride(0),
+                self.video_details.width,
+                self.video_details.height,
+                luma_first,
+            )?;
+            let targets = [
+                Some(&mut frame.y_plane),
+                frame.u_plane.as_mut(),
+                frame.v_plane.as_mut(),
+            ];
+            for (plane, data) in targets.into_iter().zip(&planes) {
+                if let Some(plane) = plane {
+                    plane
+                        .copy_from_u8_slice(data)
+                        .map_err(DecoderError::generic)?;
+                }
+            }
+            return Ok(());
+        }
 
+        // FFmpeg pads each row to its alignment, so rows must be copied by stride; for odd
+        // widths the padding starts mid-row of what a packed copy would assume
         frame
             .y_plane
-            .copy_from_u8_slice(decoded.data(0))
-            .map_err(|e| DecoderError::GenericDecodeError {
-                cause: e.to_string(),
-            })?;
+            .copy_from_u8_slice_with_stride(decoded.data(0), decoded.stride(0))
+            .map_err(DecoderError::generic)?;
+        let semi_planar = match decoded.format() {
+            format::pixel::Pixel::NV12 => Some(false),
+            format::pixel::Pixel::NV21 => Some(true),
+            _ => None,
+        };
+        if let (Some(swap_uv), Some(u_plane), Some(v_plane)) =
+            (semi_planar, frame.u_plane.as_mut(), frame.v_plane.as_mut())
+        {
+            let (u_data, v_data) = deinterleave_chroma(
+                decoded.data(1),
+                decoded.stride(1),
+                self.video_details.width.div_ceil(2),
+                self.video_details.height.div_ceil(2),
+            )?;
+            let (u_data, v_data) = if swap_uv {
+                (v_data, u_data)
+            } else {
+                (u_data, v_data)
+            };
+            u_plane
+                .copy_from_u8_slice(&u_data)
+                .map_err(DecoderError::generic)?;
+            v_plane
+                .copy_from_u8_slice(&v_data)
+                .map_err(DecoderError::generic)?;
+            return Ok(());
+        }
+
         if let Some(u_plane) = frame.u_plane.as_mut() {
-            u_plane.copy_from_u8_slice(decoded.data(1)).map_err(|e| {
-                DecoderError::GenericDecodeError {
-                    cause: e.to_string(),
-                }
-            })?;
+            u_plane
+                .copy_from_u8_slice_with_stride(decoded.data(1), decoded.stride(1))
+                .map_err(DecoderError::generic)?;
         }
         if let Some(v_plane) = frame.v_plane.as_mut() {
-            v_plane.copy_from_u8_slice(decoded.data(2)).map_err(|e| {
-                DecoderError::GenericDecodeError {
-                    cause: e.to_string(),
-                }
-            })?;
+            v_plane
+                .copy_from_u8_slice_with_stride(decoded.data(2), decoded.stride(2))
+                .map_err(DecoderError::generic)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the container format and codec names as reported by FFmpeg.
+    pub(crate) fn source_info(&self) -> SourceInfo {
+        SourceInfo {
+            container: Some(self.input_ctx.format().name().to_string()),
+            codec: self.decoder.codec().map(|codec| codec.name().to_string()),
+            backend: Backend::Ffmpeg,
         }
+    }
+
+    /// Returns `true` if the container has an audio stream.
+    pub(crate) fn has_audio(&self) -> bool {
+        self.input_ctx.streams().best(Type::Audio).is_some()
+    }
 
-        Ok(frame)
+    /// Returns the duration reported by the container, preferring the duration of the
+    /// video stream over that of the whole file.
+    pub(crate) fn container_duration(&self) -> Option<Duration> {
+        let known = |duration: i64| u64::try_from(duration).ok().filter(|&ticks| ticks > 0);
+        self.input_ctx
+            .stream(self.stream_index)
+            .and_then(|stream| known(stream.duration()))
+            .and_then(|ticks| ticks_to_duration(ticks, self.time_base))
+            .or_else(|| {
+                let ticks = known(self.input_ctx.duration())?;
+                ticks_to_duration(ticks, Rational32::new_raw(1, ffmpeg::ffi::AV_TIME_BASE))
+            })
+    }
+
+    /// Returns the presentation timestamp of the most recently decoded frame, if the
+    /// container provided one and packets keep their timestamps.
+    pub(crate) fn last_timestamp(&self) -> Option<FrameTimestamp> {
+        self.last_pts.map(|pts| FrameTimestamp {
+            pts,
+            time_base: self.time_base,
+        })
+    }
+
+    /// Returns the flags of the most recently decoded frame.
+    ///
+    /// FFmpeg does not detect scene changes, so only the keyframe flag is set.
+    pub(crate) const fn last_frame_flags(&self) -> FrameFlags {
+        FrameFlags {
+            is_keyframe: self.last_keyframe,
+            is_scene_change: false,
+        }
     }
 
     pub(crate) fn read_video_frame<T: Pixel>(
         &mut self,
-        frame_index: usize,
-        luma_only: bool,
-    ) -> Result<Frame<T>, DecoderError> {
+        frame: &mut Frame<T>,
+    ) -> Result<(), DecoderError> {
+        let mut decoded = self.receive_frame()?;
+        if let Some(scaler) = self.scaler.as_mut() {
+            decoded.set_format(non_jpeg_format(decoded.format()));
+            let mut scaled = frame::Video::empty();
+            scaler
+                .run(&decoded, &mut scaled)
+                .map_err(DecoderError::ffmpeg)?;
+            decoded = scaled;
+        }
+        self.decode_frame(&decoded, frame)
+    }
+
+    /// Returns `true` if decoded frames carry an alpha plane, i.e. the source is YUVA and
+    /// no output format was set with [`set_output_format`](Self::set_output_format).
+    pub(crate) fn has_alpha(&self) -> bool {
+        self.scaler.is_none() && is_yuva(self.decoder.format())
+    }
+
+    /// Reads the next frame into `frame` and its alpha plane into `alpha`.
+    pub(crate) fn read_video_frame_with_alpha<T: Pixel>(
+        &mut self,
+        frame: &mut Frame<T>,
+        alpha: &mut Plane<T>,
+    ) -> Result<(), DecoderError> {
+        let decoded = self.receive_frame()?;
+        if decoded.planes() < 4 {
+            return Err(DecoderError::GenericDecodeError {
+                cause: format!("decoded {:?} frame has no alpha plane", decoded.format()),
+                source: None,
+            });
+        }
+        self.decode_frame(&decoded, frame)?;
+        alpha
+            .copy_from_u8_slice_with_stride(decoded.data(3), decoded.stride(3))
+            .map_err(DecoderError::generic)
+    }
+
+    /// Seeks to the keyframe at or before `frame_index` and returns the index of the
+    /// keyframe, which the next read returns.
+    ///
+    /// The index is derived from the keyframe's timestamp and the nominal frame rate, so it
+    /// is only exact for constant frame rate streams.
+    pub(crate) fn seek_to_keyframe(&mut self, frame_index: usize) -> Result<usize, DecoderError> {
+        let frame_rate = self.video_details.frame_rate;
+        let ticks_per_frame = f64::from(*frame_rate.denom()) * f64::from(*self.time_base.denom())
+            / (f64::from(*frame_rate.numer()) * f64::from(*self.time_base.numer()));
+        let start_time = self
+            .input_ctx
+            .stream(self.stream_index)
+            .map(|stream| stream.start_time())
+            .filter(|&start_time| start_time != ffmpeg::ffi::AV_NOPTS_VALUE)
+            .unwrap_or(0);
+        let timestamp = start_time + (frame_index as f64 * ticks_per_frame).round() as i64;
+
+        // SAFETY: the format context is owned by `input_ctx` and valid for its lifetime
+        let result = unsafe {
+            ffmpeg::ffi::av_seek_frame(
+                self.input_ctx.as_mut_ptr(),
+                self.stream_index as i32,
+                timestamp,
+                ffmpeg::ffi::AVSEEK_FLAG_BACKWARD as i32,
+            )
+        };
+        if result < 0 {
+            return Err(DecoderError::ffmpeg(ffmpeg::Error::from(result)));
+        }
+        self.decoder.flush();
+        self.end_of_stream = false;
+        self.eof_sent = false;
+        self.last_pts = None;
+        self.pending = None;
+
+        // The keyframe index is derived from its real timestamp
+        let decoded = self.receive_frame_with(true)?;
+        let pts = decoded
+            .timestamp()
+            .ok_or_else(|| DecoderError::GenericDecodeError {
+                cause: "keyframe has no timestamp".to_string(),
+                source: None,
+            })?;
+        let keyframe_index = ((pts - start_time) as f64 / ticks_per_frame)
+            .round()
+            .max(0.0) as usize;
+        self.frames_decoded = keyframe_index;
+        self.pending = Some(decoded);
+        Ok(keyframe_index)
+    }
+
+    /// Advances past the next frame without copying it into a [`Frame`].
+    pub(crate) fn skip_frame(&mut self) -> Result<(), DecoderError> {
+        self.receive_frame().map(|_| ())
+    }
+
+    /// Reads the next packet of any stream, or `None` at the end of the input.
+    ///
+    /// Read errors end the input for files. For network streams, transient errors are
+    /// retried according to the [`RetryPolicy`], and other errors are returned.
+    fn read_packet(&mut self) -> Result<Option<packet::Packet>, DecoderError> {
+        let mut retries = 0;
+        let mut backoff = self.retry_policy.initial_backoff;
+        loop {
+            match self.input_ctx.packets().next() {
+                None | Some(Err(ffmpeg::Error::Eof)) => return Ok(None),
+                Some(Ok((_, packet))) => return Ok(Some(packet)),
+                Some(Err(_)) if !self.network => return Ok(None),
+                Some(Err(e)) if is_transient(e) && retries < self.retry_policy.max_retries => {
+                    thread::sleep(backoff);
+                    backoff = backoff.saturating_mul(2).min(self.retry_policy.max_backoff);
+                    retries += 1;
+                }
+                Some(Err(e)) => return Err(DecoderError::ffmpeg(e)),
+            }
+        }
+    }
+
+    /// Feeds packets to the codec until it produces the next frame.
+    fn receive_frame(&mut self) -> Result<frame::Video, DecoderError> {
+        self.receive_frame_with(self.sequential || self.strict_frame_rate)
+    }
+
+    /// Feeds packets to the codec until it produces the next frame, keeping the container
+    /// timestamps of the packets if `keep_timestamps` is set.
+    ///
+    /// Otherwise each packet is stamped with the index of the frame being read, which works
+    /// around codecs that decode out of sync with broken container timestamps, but leaves
+    /// decoded frames without meaningful timestamps.
+    fn receive_frame_with(&mut self, keep_timestamps: bool) -> Result<frame::Video, DecoderError> {
+        if let Some(decoded) = self.pending.take() {
+            self.last_pts = decoded.timestamp().filter(|_| keep_timestamps);
+            self.last_keyframe = decoded.is_key();
+            self.frames_decoded += 1;
+            return Ok(decoded);
+        }
+
         // For some reason there's a crap ton of work needed to get ffmpeg to do
         // something simple, because each codec has it's own stupid way of doing
         // things and they don't all decode the same way.
@@ -182,14 +739,7 @@ impl FfmpegDecoder {
         loop {
             // This iterator is actually really stupid... it doesn't reset itself after each
             // `new`. But that solves our lifetime hell issues, ironically.
-            let packet = self
-                .input_ctx
-                .packets()
-                .next()
-                .and_then(Result::ok)
-                .map(|(_, packet)| packet);
-
-            let mut packet = if let Some(packet) = packet {
+            let mut packet = if let Some(packet) = self.read_packet()? {
                 packet
             } else {
                 self.end_of_stream = true;
@@ -204,23 +754,980 @@ impl FfmpegDecoder {
             if self.end_of_stream || packet.stream() == self.stream_index {
                 let mut decoded = frame::Video::new(
                     self.decoder.format(),
-                    self.video_details.width as u32,
-                    self.video_details.height as u32,
+                    self.decoder.width(),
+                    self.decoder.height(),
                 );
-                packet.set_pts(Some(frame_index as i64));
-                packet.set_dts(Some(frame_index as i64));
-
+                if !keep_timestamps {
+                    packet.set_pts(Some(self.frames_decoded as i64));
+                    packet.set_dts(Some(self.frames_decoded as i64));
+                }
                 if !self.end_of_stream {
                     let _ = self.decoder.send_packet(&packet);
                 }
 
                 if self.decoder.receive_frame(&mut decoded).is_ok() {
-                    let f = self.decode_frame(&decoded, luma_only);
-                    return f;
+                    if self.error_resilience == ErrorResilience::Strict && decoded.is_corrupt() {
+                        return Err(DecoderError::GenericDecodeError {
+                            cause: "decoded frame is corrupt".to_string(),
+                            source: None,
+                        });
+                    }
+                    if self.strict_frame_rate
+                        && let (Some(previous), Some(pts)) = (self.last_pts, decoded.timestamp())
+                    {
+                        check_frame_duration(
+                            pts - previous,
+                            self.time_base,
+                            self.video_details.frame_rate,
+                        )?;
+                    }
+                    self.last_pts = decoded.timestamp().filter(|_| keep_timestamps);
+                    self.last_keyframe = decoded.is_key();
+                    self.frames_decoded += 1;
+                    return Ok(decoded);
                 } else if self.end_of_stream {
+                    if let Some(tolerance) = self.frame_count_tolerance {
+                        check_frame_count(
+                            self.frames_decoded,
+                            self.video_details.total_frames,
+                            tolerance,
+                        )?;
+                    }
                     return Err(DecoderError::EndOfFile);
                 }
             }
         }
     }
 }
+
+/// How the FFmpeg decoder retries transient read failures of network streams, set with
+/// [`FfmpegDecoder::set_retry_policy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RetryPolicy {
+    /// Number of retries after a failed read, 0 to fail immediately.
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for each further retry.
+    pub initial_backoff: Duration,
+    /// Upper bound for the delay between retries.
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    /// Retries up to 5 times, waiting from 250 ms up to 4 seconds.
+    #[inline]
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            initial_backoff: Duration::from_millis(250),
+            max_backoff: Duration::from_secs(4),
+        }
+    }
+}
+
+/// Returns `true` for read errors that may succeed when retried.
+fn is_transient(error: ffmpeg::Error) -> bool {
+    matches!(error, ffmpeg::Error::Other { errno } if errno == EAGAIN || errno == ETIMEDOUT)
+}
+
+/// How the FFmpeg decoder reacts to errors in the bitstream, set with
+/// [`FfmpegDecoder::set_error_resilience`].
+///
+/// More tolerant levels recover more frames from damaged files, at the cost of returning
+/// frames with visible artifacts, such as gray or smeared blocks, instead of failing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ErrorResilience {
+    /// Checks CRCs and bitstream conformance, and fails on the first error or corrupt
+    /// frame. Use this when a damaged file must not go unnoticed.
+    Strict,
+    /// The codec's own defaults: errors are concealed where possible, but frames the codec
+    /// cannot reconstruct, such as those missing their reference frames, are dropped.
+    #[default]
+    Default,
+    /// Ignores errors and outputs every frame the codec can produce, including frames
+    /// decoded without their reference frames. Use this for recovery and forensics.
+    Tolerant,
+}
+
+/// Returns `true` for planar YUV formats with a fourth, alpha plane.
+const fn is_yuva(format: format::Pixel) -> bool {
+    matches!(
+        format,
+        format::Pixel::YUVA420P
+            | format::Pixel::YUVA422P
+            | format::Pixel::YUVA444P
+            | format::Pixel::YUVA420P10LE
+            | format::Pixel::YUVA422P10LE
+            | format::Pixel::YUVA444P10LE
+            | format::Pixel::YUVA422P12LE
+            | format::Pixel::YUVA444P12LE
+            | format::Pixel::YUVA420P16LE
+            | format::Pixel::YUVA422P16LE
+            | format::Pixel::YUVA444P16LE
+    )
+}
+
+/// Checks the bit depth assumed for `format` against the luma sample layout FFmpeg
+/// describes for it, so that a mismatch fails instead of producing corrupted frames.
+fn check_luma_storage(format: format::Pixel, bit_depth: usize) -> Result<(), DecoderError> {
+    // SAFETY: FFmpeg returns either null or a pointer to a static descriptor
+    let Some(descriptor) = (unsafe { ffmpeg::ffi::av_pix_fmt_desc_get(format.into()).as_ref() })
+    else {
+        return Ok(());
+    };
+    let luma = descriptor.comp[0];
+    let depth = luma.depth as usize;
+    if depth != bit_depth {
+        return Err(DecoderError::UnsupportedFormat {
+            fmt: format!("{format:?} has {depth}-bit samples, expected {bit_depth}-bit"),
+        });
+    }
+    // Samples may be stored shifted up within a wider word, as in P010
+    check_sample_storage(depth, (depth + luma.shift as usize).div_ceil(8))
+}
+
+/// Compares the nominal and average frame rates of a stream, allowing for the rounding
+/// of averages computed from millisecond timestamps. Unknown rates are not compared.
+fn frame_rates_differ(nominal: Rational32, average: Rational32) -> bool {
+    if *nominal.numer() <= 0
+        || *nominal.denom() <= 0
+        || *average.numer() <= 0
+        || *average.denom() <= 0
+    {
+        return false;
+    }
+    let nominal = f64::from(*nominal.numer()) / f64::from(*nominal.denom());
+    let average = f64::from(*average.numer()) / f64::from(*average.denom());
+    (nominal - average).abs() > nominal * 0.01
+}
+
+/// Checks that `delta` timestamp ticks between two frames is the nominal frame duration,
+/// give or take half a frame.
+fn check_frame_duration(
+    delta: i64,
+    time_base: Rational32,
+    frame_rate: Rational32,
+) -> Result<(), DecoderError> {
+    let tick = f64::from(*time_base.numer()) / f64::from(*time_base.denom());
+    let duration = f64::from(*frame_rate.denom()) / f64::from(*frame_rate.numer()) / tick;
+    if (delta as f64 - duration).abs() > duration / 2.0 {
+        return Err(DecoderError::VariableFramerate);
+    }
+    Ok(())
+}
+
+/// Verifies that the number of decoded frames is within `tolerance` of the frame count
+/// reported by the container.
+fn check_frame_count(
+    frames_decoded: usize,
+    total_frames: Option<usize>,
+    tolerance: usize,
+) -> Result<(), DecoderError> {
+    match total_frames {
+        Some(total_frames) if frames_decoded.abs_diff(total_frames) > tolerance => {
+            Err(DecoderError::GenericDecodeError {
+                cause: format!(
+                    "decoded {frames_decoded} frames, but the container reports {total_frames}"
+                ),
+                source: None,
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Reads HDR metadata from the container-level side data of a stream.
+///
+/// Metadata carried only in the bitstream (e.g. HEVC SEI messages) is not visible here;
+/// it is read from the first decoded frame by [`read_frame_hdr_metadata`] instead.
+///
+/// # Safety
+///
+/// `parameters` must point to valid codec parameters.
+unsafe fn read_hdr_metadata(
+    parameters: *const ffmpeg::ffi::AVCodecParameters,
+) -> Option<HdrMetadata> {
+    use ffmpeg::ffi::{
+        AVContentLightMetadata, AVMasteringDisplayMetadata,
+        AVPacketSideDataType::{
+            AV_PKT_DATA_CONTENT_LIGHT_LEVEL, AV_PKT_DATA_MASTERING_DISPLAY_METADATA,
+        },
+        av_packet_side_data_get,
+    };
+
+    // SAFETY: the caller guarantees `parameters` is valid, and FFmpeg guarantees side data
+    // of these types holds the matching struct
+    unsafe {
+        let side_data = (*parameters).coded_side_data;
+        let count = (*parameters).nb_coded_side_data;
+
+        let mastering_display =
+            av_packet_side_data_get(side_data, count, AV_PKT_DATA_MASTERING_DISPLAY_METADATA)
+                .as_ref()
+                .and_then(|data| {
+                    map_mastering_display(&*data.data.cast::<AVMasteringDisplayMetadata>())
+                });
+        let content_light_level =
+            av_packet_side_data_get(side_data, count, AV_PKT_DATA_CONTENT_LIGHT_LEVEL)
+                .as_ref()
+                .map(|data| map_content_light_level(&*data.data.cast::<AVContentLightMetadata>()));
+
+        (mastering_display.is_some() || content_light_level.is_some()).then_some(HdrMetadata {
+            mastering_display,
+            content_light_level,
+        })
+    }
+}
+
+/// Reads HDR metadata from the side data of a decoded frame, which includes metadata
+/// carried in the bitstream.
+///
+/// # Safety
+///
+/// `frame` must point to a valid frame.
+unsafe fn read_frame_hdr_metadata(frame: *const ffmpeg::ffi::AVFrame) -> Option<HdrMetadata> {
+    use ffmpeg::ffi::{
+        AVContentLightMetadata,
+        AVFrameSideDataType::{
+            AV_FRAME_DATA_CONTENT_LIGHT_LEVEL, AV_FRAME_DATA_MASTERING_DISPLAY_METADATA,
+        },
+        AVMasteringDisplayMetadata, av_frame_get_side_data,
+    };
+
+    // SAFETY: the caller guarantees `frame` is valid, and FFmpeg guarantees side data of
+    // these types holds the matching struct
+    unsafe {
+        let mastering_display =
+            av_frame_get_side_data(frame, AV_FRAME_DATA_MASTERING_DISPLAY_METADATA)
+                .as_ref()
+                .and_then(|data| {
+                    map_mastering_display(&*data.data.cast::<AVMasteringDisplayMetadata>())
+                });
+        let content_light_level = av_frame_get_side_data(frame, AV_FRAME_DATA_CONTENT_LIGHT_LEVEL)
+            .as_ref()
+            .map(|data| map_content_light_level(&*data.data.cast::<AVContentLightMetadata>()));
+
+        (mastering_display.is_some() || content_light_level.is_some()).then_some(HdrMetadata {
+            mastering_display,
+            content_light_level,
+        })
+    }
+}
+
+/// Converts mastering display side data, if it has both primaries and luminance.
+fn map_mastering_display(
+    metadata: &ffmpeg::ffi::AVMasteringDisplayMetadata,
+) -> Option<MasteringDisplay> {
+    (metadata.has_primaries != 0 && metadata.has_luminance != 0).then(|| MasteringDisplay {
+        primaries: metadata
+            .display_primaries
+            .map(|[x, y]| (map_rational(x), map_rational(y))),
+        white_point: (
+            map_rational(metadata.white_point[0]),
+            map_rational(metadata.white_point[1]),
+        ),
+        min_luminance: map_rational(metadata.min_luminance),
+        max_luminance: map_rational(metadata.max_luminance),
+    })
+}
+
+/// Converts content light level side data.
+const fn map_content_light_level(
+    metadata: &ffmpeg::ffi::AVContentLightMetadata,
+) -> ContentLightLevel {
+    ContentLightLevel {
+        max_content_light_level: metadata.MaxCLL,
+        max_frame_average_light_level: metadata.MaxFALL,
+    }
+}
+
+/// Prefers each part of the HDR metadata of the first frame over the container's.
+fn merge_hdr_metadata(
+    frame: Option<HdrMetadata>,
+    container: Option<HdrMetadata>,
+) -> Option<HdrMetadata> {
+    match (frame, container) {
+        (Some(frame), Some(container)) => Some(HdrMetadata {
+            mastering_display: frame.mastering_display.or(container.mastering_display),
+            content_light_level: frame.content_light_level.or(container.content_light_level),
+        }),
+        (frame, container) => frame.or(container),
+    }
+}
+
+/// Reads the clockwise rotation in degrees from the display matrix side data of a stream.
+///
+/// # Safety
+///
+/// `parameters` must point to valid codec parameters.
+unsafe fn read_display_matrix_rotation(
+    parameters: *const ffmpeg::ffi::AVCodecParameters,
+) -> Option<f64> {
+    use ffmpeg::ffi::{
+        AVPacketSideDataType::AV_PKT_DATA_DISPLAYMATRIX, av_display_rotation_get,
+        av_packet_side_data_get,
+    };
+
+    // SAFETY: the caller guarantees `parameters` is valid, and FFmpeg guarantees display
+    // matrix side data holds nine 32-bit integers
+    unsafe {
+        let side_data = av_packet_side_data_get(
+            (*parameters).coded_side_data,
+            (*parameters).nb_coded_side_data,
+            AV_PKT_DATA_DISPLAYMATRIX,
+        )
+        .as_ref()?;
+        // The display matrix rotates counter-clockwise
+        let degrees = -av_display_rotation_get(side_data.data.cast::<i32>());
+        degrees.is_finite().then_some(degrees)
+    }
+}
+
+/// Rounds a rotation in degrees to the nearest multiple of 90 in `0..360`.
+fn snap_rotation(degrees: f64) -> i32 {
+    ((degrees / 90.0).round() as i32 * 90).rem_euclid(360)
+}
+
+/// Converts an FFmpeg rational, treating an unset (zero) denominator as zero.
+fn map_rational(value: ffmpeg::ffi::AVRational) -> Rational32 {
+    if value.den == 0 {
+        Rational32::from_integer(0)
+    } else {
+        Rational32::new(value.num, value.den)
+    }
+}
+
+/// Splits an interleaved semi-planar chroma plane (as in NV12) into separate planes.
+///
+/// Returns tightly packed planes of `width * height` samples each, in the order the
+/// samples appear in each pair. Row padding beyond `2 * width` bytes is skipped, and the
+/// last row need not be padded.
+fn deinterleave_chroma(
+    data: &[u8],
+    stride: usize,
+    width: usize,
+    height: usize,
+) -> Result<(Vec<u8>, Vec<u8>), DecoderError> {
+    let mut first = Vec::with_capacity(width * height);
+    let mut second = Vec::with_capacity(width * height);
+    for y in 0..height {
+        let row = data
+            .get(y * stride..y * stride + 2 * width)
+            .ok_or_else(|| DecoderError::GenericDecodeError {
+                cause: format!(
+                    "semi-planar chroma has {} bytes, too few for {height} rows of {} bytes",
+                    data.len(),
+                    2 * width
+                ),
+                source: None,
+            })?;
+        for pair in row.chunks_exact(2) {
+            first.push(pair[0]);
+            second.push(pair[1]);
+        }
+    }
+    Ok((first, second))
+}
+
+/// Maps a bit depth and chroma layout onto the planar FFmpeg format used for conversion.
+fn output_pixel_format(
+    bit_depth: u8,
+    chroma_subsampling: ChromaSubsampling,
+) -> Result<format::Pixel, DecoderError> {
+    use format::Pixel;
+    Ok(match (bit_depth, chroma_subsampling) {
+        (8, ChromaSubsampling::Yuv420) => Pixel::YUV420P,
+        (8, ChromaSubsampling::Yuv422) => Pixel::YUV422P,
+        (8, ChromaSubsampling::Yuv444) => Pixel::YUV444P,
+        (8, ChromaSubsampling::Monochrome) => Pixel::GRAY8,
+        (10, ChromaSubsampling::Yuv420) => Pixel::YUV420P10LE,
+        (10, ChromaSubsampling::Yuv422) => Pixel::YUV422P10LE,
+        (10, ChromaSubsampling::Yuv444) => Pixel::YUV444P10LE,
+        (10, ChromaSubsampling::Monochrome) => Pixel::GRAY10LE,
+        (12, ChromaSubsampling::Yuv420) => Pixel::YUV420P12LE,
+        (12, ChromaSubsampling::Yuv422) => Pixel::YUV422P12LE,
+        (12, ChromaSubsampling::Yuv444) => Pixel::YUV444P12LE,
+        (12, ChromaSubsampling::Monochrome) => Pixel::GRAY12LE,
+        _ => {
+            return Err(DecoderError::UnsupportedFormat {
+                fmt: "Unsupported bit depth and subsampling combination".to_string(),
+            });
+        }
+    })
+}
+
+/// Splits a packed 4:2:2 plane into separate Y, U, and V planes.
+///
+/// Each group of 4 bytes holds two pixels: `Y0 U Y1 V` for YUYV (`luma_first`), or
+/// `U Y0 V Y1` for UYVY. For odd widths the last group carries one padding luma sample.
+/// The last row need not be padded beyond its samples.
+fn unpack_422(
+    data: &[u8],
+    stride: usize,
+    width: usize,
+    height: usize,
+    luma_first: bool,
+) -> Result<[Vec<u8>; 3], DecoderError> {
+    let chroma_width = width.div_ceil(2);
+    let (luma_offset, chroma_offset) = if luma_first { (0, 1) } else { (1, 0) };
+    let mut y_data = Vec::with_capacity(width * height);
+    let mut u_data = Vec::with_capacity(chroma_width * height);
+    let mut v_data = Vec::with_capacity(chroma_width * height);
+    for y in 0..height {
+        let row = data
+            .get(y * stride..y * stride + 4 * chroma_width)
+            .ok_or_else(|| DecoderError::GenericDecodeError {
+                cause: format!(
+                    "packed 4:2:2 plane has {} bytes, too few for {height} rows of {} bytes",
+                    data.len(),
+                    4 * chroma_width
+                ),
+                source: None,
+            })?;
+        for group in row.chunks_exact(4) {
+            y_data.push(group[luma_offset]);
+            y_data.push(group[luma_offset + 2]);
+            u_data.push(group[chroma_offset]);
+            v_data.push(group[chroma_offset + 2]);
+        }
+        y_data.truncate(y_data.len() - (2 * chroma_width - width));
+    }
+    Ok([y_data, u_data, v_data])
+}
+
+/// Maps FFmpeg's coded field order onto a [`FieldOrder`].
+///
+/// `TB`/`BT` describe the coded order versus display order; only the display order
+/// matters to consumers, so they collapse onto the corresponding field-first variant.
+const fn map_field_order(field_order: ffmpeg::ffi::AVFieldOrder) -> FieldOrder {
+    use ffmpeg::ffi::AVFieldOrder::{AV_FIELD_BB, AV_FIELD_BT, AV_FIELD_TB, AV_FIELD_TT};
+    match field_order {
+        AV_FIELD_TT | AV_FIELD_BT => FieldOrder::TopFieldFirst,
+        AV_FIELD_BB | AV_FIELD_TB => FieldOrder::BottomFieldFirst,
+        _ => FieldOrder::Progressive,
+    }
+}
+
+/// Maps FFmpeg's color range onto a [`ColorRange`].
+///
+/// `YUVJ*` formats imply full range, so they are full range even if the decoder leaves
+/// the range unspecified.
+const fn map_color_range(format: format::Pixel, range: ffmpeg::color::Range) -> ColorRange {
+    if is_jpeg_format(format) || matches!(range, ffmpeg::color::Range::JPEG) {
+        ColorRange::Full
+    } else {
+        ColorRange::Limited
+    }
+}
+
+/// Returns `true` for the deprecated `YUVJ*` formats, which imply full range.
+const fn is_jpeg_format(format: format::Pixel) -> bool {
+    matches!(
+        format,
+        format::Pixel::YUVJ420P
+            | format::Pixel::YUVJ422P
+            | format::Pixel::YUVJ444P
+            | format::Pixel::YUVJ440P
+            | format::Pixel::YUVJ411P
+    )
+}
+
+/// Maps a `YUVJ*` format onto the format with the same layout, leaving others as is.
+///
+/// The range is tracked in [`VideoDetails::color_range`] instead.
+const fn non_jpeg_format(format: format::Pixel) -> format::Pixel {
+    match format {
+        format::Pixel::YUVJ420P => format::Pixel::YUV420P,
+        format::Pixel::YUVJ422P => format::Pixel::YUV422P,
+        format::Pixel::YUVJ444P => format::Pixel::YUV444P,
+        format::Pixel::YUVJ440P => format::Pixel::YUV440P,
+        format::Pixel::YUVJ411P => format::Pixel::YUV411P,
+        fmt => fmt,
+    }
+}
+
+/// Maps FFmpeg's chroma location onto a [`ChromaLocation`], defaulting to `Left` when unspecified.
+const fn map_chroma_location(location: ffmpeg::chroma::Location) -> ChromaLocation {
+    match location {
+        ffmpeg::chroma::Location::Center => ChromaLocation::Center,
+        ffmpeg::chroma::Location::TopLeft => ChromaLocation::TopLeft,
+        ffmpeg::chroma::Location::Top => ChromaLocation::Top,
+        ffmpeg::chroma::Location::BottomLeft => ChromaLocation::BottomLeft,
+        ffmpeg::chroma::Location::Bottom => ChromaLocation::Bottom,
+        ffmpeg::chroma::Location::Unspecified | ffmpeg::chroma::Location::Left => {
+            ChromaLocation::Left
+        }
+    }
+}
+
+/// Maps an FFmpeg pixel format onto the subsampling modes supported by `v_frame`.
+///
+/// 4:4:0 and 4:1:1 have no `v_frame` equivalent and are reported with their subsampling
+/// factors, as log2 shifts like VapourSynth's.
+fn map_chroma_sampling(fmt: format::Pixel) -> Result<ChromaSubsampling, DecoderError> {
+    Ok(match fmt {
+        format::Pixel::YUV420P
+        | format::Pixel::YUVJ420P
+        | format::Pixel::YUV420P10LE
+        | format::Pixel::YUV420P12LE
+        | format::Pixel::YUV420P14LE
+        | format::Pixel::YUV420P16LE
+        | format::Pixel::NV12
+        | format::Pixel::NV21
+        | format::Pixel::YUVA420P
+        | format::Pixel::YUVA420P10LE
+        | format::Pixel::YUVA420P16LE => ChromaSubsampling::Yuv420,
+        format::Pixel::YUV422P
+        | format::Pixel::YUVJ422P
+        | format::Pixel::YUV422P10LE
+        | format::Pixel::YUV422P12LE
+        | format::Pixel::YUV422P14LE
+        | format::Pixel::YUV422P16LE
+        | format::Pixel::YUYV422
+        | format::Pixel::UYVY422
+        | format::Pixel::YUVA422P
+        | format::Pixel::YUVA422P10LE
+        | format::Pixel::YUVA422P12LE
+        | format::Pixel::YUVA422P16LE => ChromaSubsampling::Yuv422,
+        format::Pixel::YUV444P
+        | format::Pixel::YUVJ444P
+        | format::Pixel::YUV444P10LE
+        | format::Pixel::YUV444P12LE
+        | format::Pixel::YUV444P14LE
+        | format::Pixel::YUV444P16LE
+        | format::Pixel::YUVA444P
+        | format::Pixel::YUVA444P10LE
+        | format::Pixel::YUVA444P12LE
+        | format::Pixel::YUVA444P16LE => ChromaSubsampling::Yuv444,
+        format::Pixel::YUV440P
+        | format::Pixel::YUVJ440P
+        | format::Pixel::YUV440P10LE
+        | format::Pixel::YUV440P12LE => {
+            return Err(DecoderError::UnsupportedChromaSubsampling { x: 0, y: 1 });
+        }
+        format::Pixel::YUV411P | format::Pixel::YUVJ411P | format::Pixel::UYYVYY411 => {
+            return Err(DecoderError::UnsupportedChromaSubsampling { x: 2, y: 0 });
+        }
+        fmt => {
+            return Err(DecoderError::UnsupportedFormat {
+                fmt: format!("{fmt:?}"),
+            });
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use ffmpeg::codec;
+
+    use super::*;
+    use crate::test_util::{assert_flat_frame, write_ffmpeg_clip, write_flat_raw_clip};
+    use crate::{Decoder, DecoderImpl};
+
+    /// Encodes a 64x64 MPEG-4 clip with B-frames into an MP4 file, one frame per entry of
+    /// `values`, each with a flat luma of that value.
+    fn write_b_frame_clip(values: &[u8]) -> PathBuf {
+        write_ffmpeg_clip(
+            "b-frames.mp4",
+            codec::Id::MPEG4,
+            format::Pixel::YUV420P,
+            (64, 64),
+            values.len(),
+            |encoder| {
+                encoder.set_gop(12);
+                encoder.set_max_b_frames(2);
+            },
+            |index, frame| {
+                frame.data_mut(0).fill(values[index]);
+                frame.data_mut(1).fill(128);
+                frame.data_mut(2).fill(128);
+            },
+        )
+    }
+
+    #[test]
+    fn sequential_decoding_keeps_b_frames_in_order() {
+        let values: Vec<u8> = (0..12).map(|index| 32 + index * 16).collect();
+        let path = write_b_frame_clip(&values);
+        let mut ffmpeg = FfmpegDecoder::new(&path).expect("valid clip");
+        ffmpeg.set_sequential_decoding(true);
+        ffmpeg.set_frame_count_validation(Some(0));
+        let mut decoder =
+            Decoder::from_decoder_impl(DecoderImpl::Ffmpeg(ffmpeg)).expect("video stream");
+        assert_eq!(decoder.get_video_details().total_frames, Some(values.len()));
+
+        let mut decoded = Vec::new();
+        let result = loop {
+            match decoder.read_video_frame_with_pts::<u8>() {
+                Ok((frame, timestamp)) => {
+                    let luma = frame.y_plane.rows().flatten().next().copied();
+                    decoded.push((luma.expect("frame has luma"), timestamp.as_secs_f64()));
+                }
+                Err(error) => break error,
+            }
+        };
+        std::fs::remove_file(&path).expect("remove test file");
+
+        assert!(result.is_eof(), "frame count matches: {result}");
+        assert_eq!(decoded.len(), values.len());
+        // The muxer may delay the first frame to make room for B-frame reordering
+        let start = decoded[0].1;
+        for (index, (&(luma, seconds), &value)) in decoded.iter().zip(&values).enumerate() {
+            assert!(luma.abs_diff(value) <= 2, "frame {index} is out of order");
+            assert!((seconds - start - index as f64 / 25.0).abs() < 0.001);
+        }
+    }
+
+    #[test]
+    fn decodes_nv12_clip_with_odd_width() {
+        let path = write_ffmpeg_clip(
+            "nv12.nut",
+            codec::Id::RAWVIDEO,
+            format::Pixel::NV12,
+            (5, 3),
+            1,
+            |_| {},
+            |_, frame| {
+                frame.data_mut(0).fill(50);
+                let stride = frame.stride(1);
+                for (offset, sample) in frame.data_mut(1).iter_mut().enumerate() {
+                    *sample = if offset % stride % 2 == 0 { 100 } else { 200 };
+                }
+            },
+        );
+        let ffmpeg = FfmpegDecoder::new(&path).expect("valid clip");
+        let mut decoder =
+            Decoder::from_decoder_impl(DecoderImpl::Ffmpeg(ffmpeg)).expect("video stream");
+        let frame = decoder.read_video_frame::<u8>();
+        std::fs::remove_file(&path).expect("remove test file");
+
+        let frame = frame.expect("NV12 frame");
+        assert!(frame.y_plane.rows().flatten().all(|&sample| sample == 50));
+        let u_plane = frame.u_plane.as_ref().expect("U plane");
+        let v_plane = frame.v_plane.as_ref().expect("V plane");
+        assert_eq!(u_plane.rows().next().map(<[u8]>::len), Some(3));
+        assert!(u_plane.rows().flatten().all(|&sample| sample == 100));
+        assert!(v_plane.rows().flatten().all(|&sample| sample == 200));
+    }
+
+    #[test]
+    fn decodes_packed_422_clips() {
+        for (pixel_format, layout) in [
+            (format::Pixel::YUYV422, [50, 100, 50, 200]),
+            (format::Pixel::UYVY422, [100, 50, 200, 50]),
+        ] {
+            let path = write_ffmpeg_clip(
+                "packed-422.nut",
+                codec::Id::RAWVIDEO,
+                pixel_format,
+                (4, 2),
+                1,
+                |_| {},
+                |_, frame| {
+                    for (offset, sample) in frame.data_mut(0).iter_mut().enumerate() {
+                        *sample = layout[offset % 4];
+                    }
+                },
+            );
+            let decoder = FfmpegDecoder::new(&path)
+                .and_then(|ffmpeg| Decoder::from_decoder_impl(DecoderImpl::Ffmpeg(ffmpeg)));
+            let frame = decoder.and_then(|mut decoder| {
+                assert_eq!(
+                    decoder.get_video_details().chroma_sampling,
+                    ChromaSubsampling::Yuv422
+                );
+                decoder.read_video_frame::<u8>()
+            });
+            std::fs::remove_file(&path).expect("remove test file");
+
+            let frame = frame.expect("packed 4:2:2 frame");
+            assert!(frame.y_plane.rows().flatten().all(|&sample| sample == 50));
+            let u_plane = frame.u_plane.as_ref().expect("U plane");
+            assert_eq!(u_plane.rows().count(), 2);
+            assert!(u_plane.rows().flatten().all(|&sample| sample == 100));
+            let v_plane = frame.v_plane.as_ref().expect("V plane");
+            assert!(v_plane.rows().flatten().all(|&sample| sample == 200));
+        }
+    }
+
+    #[test]
+    fn decodes_yuvj_clip_as_full_range() {
+        let path = write_ffmpeg_clip(
+            "yuvj.avi",
+            codec::Id::MJPEG,
+            format::Pixel::YUVJ420P,
+            (16, 16),
+            1,
+            |_| {},
+            |_, frame| {
+                frame.data_mut(0).fill(250);
+                frame.data_mut(1).fill(128);
+                frame.data_mut(2).fill(128);
+            },
+        );
+        let decoder = FfmpegDecoder::new(&path)
+            .and_then(|ffmpeg| Decoder::from_decoder_impl(DecoderImpl::Ffmpeg(ffmpeg)));
+        let frame = decoder.and_then(|mut decoder| {
+            assert_eq!(decoder.get_video_details().color_range, ColorRange::Full);
+            decoder.read_video_frame::<u8>()
+        });
+        std::fs::remove_file(&path).expect("remove test file");
+
+        // Converting to limited range would have scaled luma down to about 235
+        let frame = frame.expect("YUVJ frame");
+        assert!(
+            frame
+                .y_plane
+                .rows()
+                .flatten()
+                .all(|&sample| sample.abs_diff(250) <= 2)
+        );
+    }
+
+    #[test]
+    fn decodes_14_and_16_bit_clips() {
+        for (pixel_format, bit_depth) in [
+            (format::Pixel::YUV420P14LE, 14),
+            (format::Pixel::YUV444P16LE, 16),
+        ] {
+            let values = [
+                (u16::MAX >> (16 - bit_depth)) - 1,
+                1 << (bit_depth - 1),
+                0x0123,
+            ];
+            let path = write_flat_raw_clip(pixel_format, values);
+            let decoder = FfmpegDecoder::new(&path)
+                .and_then(|ffmpeg| Decoder::from_decoder_impl(DecoderImpl::Ffmpeg(ffmpeg)));
+            let frame = decoder.and_then(|mut decoder| {
+                assert_eq!(decoder.get_video_details().bit_depth, bit_depth);
+                decoder.read_video_frame::<u16>()
+            });
+            std::fs::remove_file(&path).expect("remove test file");
+
+            assert_flat_frame(&frame.expect("high bit depth frame"), values);
+        }
+    }
+
+    #[test]
+    fn detects_variable_frame_rate() {
+        let ntsc = Rational32::new_raw(24000, 1001);
+        assert!(!frame_rates_differ(ntsc, Rational32::new_raw(23976, 1000)));
+        assert!(!frame_rates_differ(ntsc, Rational32::new_raw(0, 1)));
+        assert!(frame_rates_differ(
+            Rational32::new_raw(60, 1),
+            Rational32::new_raw(37, 1)
+        ));
+        // Soft telecine is flagged too, although its frames are evenly spaced
+        assert!(frame_rates_differ(Rational32::new_raw(30000, 1001), ntsc));
+
+        let time_base = Rational32::new(1, 1000);
+        assert!(check_frame_duration(42, time_base, ntsc).is_ok());
+        assert!(check_frame_duration(41, time_base, ntsc).is_ok());
+        assert!(matches!(
+            check_frame_duration(83, time_base, ntsc),
+            Err(DecoderError::VariableFramerate)
+        ));
+    }
+
+    #[test]
+    fn checks_bit_depth_against_pixel_format() {
+        assert!(check_luma_storage(format::Pixel::YUV420P, 8).is_ok());
+        assert!(check_luma_storage(format::Pixel::YUV420P10LE, 10).is_ok());
+        assert!(check_luma_storage(format::Pixel::P010LE, 10).is_ok());
+        assert!(matches!(
+            check_luma_storage(format::Pixel::YUV420P10LE, 8),
+            Err(DecoderError::UnsupportedFormat { .. })
+        ));
+        assert!(matches!(
+            check_luma_storage(format::Pixel::YUV420P, 10),
+            Err(DecoderError::UnsupportedFormat { .. })
+        ));
+    }
+
+    #[test]
+    fn retries_only_transient_read_errors() {
+        assert!(is_transient(ffmpeg::Error::Other { errno: EAGAIN }));
+        assert!(is_transient(ffmpeg::Error::Other { errno: ETIMEDOUT }));
+        assert!(!is_transient(ffmpeg::Error::Eof));
+        assert!(!is_transient(ffmpeg::Error::InvalidData));
+    }
+
+    #[test]
+    fn maps_yuva_formats_to_their_yuv_subsampling() {
+        assert_eq!(
+            map_chroma_sampling(format::Pixel::YUVA420P).expect("4:2:0 with alpha"),
+            ChromaSubsampling::Yuv420
+        );
+        assert_eq!(
+            map_chroma_sampling(format::Pixel::YUVA444P16LE).expect("4:4:4 with alpha"),
+            ChromaSubsampling::Yuv444
+        );
+        assert!(is_yuva(format::Pixel::YUVA422P10LE));
+        assert!(!is_yuva(format::Pixel::YUV422P10LE));
+        assert!(check_luma_storage(format::Pixel::YUVA444P12LE, 12).is_ok());
+    }
+
+    #[test]
+    fn reports_unsupported_subsampling_factors() {
+        assert!(matches!(
+            map_chroma_sampling(format::Pixel::YUVJ440P),
+            Err(DecoderError::UnsupportedChromaSubsampling { x: 0, y: 1 })
+        ));
+        assert!(matches!(
+            map_chroma_sampling(format::Pixel::YUV411P),
+            Err(DecoderError::UnsupportedChromaSubsampling { x: 2, y: 0 })
+        ));
+        assert!(matches!(
+            map_chroma_sampling(format::Pixel::RGB24),
+            Err(DecoderError::UnsupportedFormat { .. })
+        ));
+    }
+
+    #[test]
+    fn yuvj_formats_are_full_range() {
+        assert_eq!(
+            map_color_range(format::Pixel::YUVJ420P, ffmpeg::color::Range::Unspecified),
+            ColorRange::Full
+        );
+        assert_eq!(
+            map_color_range(format::Pixel::YUV420P, ffmpeg::color::Range::Unspecified),
+            ColorRange::Limited
+        );
+        assert_eq!(
+            map_color_range(format::Pixel::YUV420P, ffmpeg::color::Range::JPEG),
+            ColorRange::Full
+        );
+        assert_eq!(
+            non_jpeg_format(format::Pixel::YUVJ420P),
+            format::Pixel::YUV420P
+        );
+        assert_eq!(
+            non_jpeg_format(format::Pixel::YUV420P10LE),
+            format::Pixel::YUV420P10LE
+        );
+    }
+
+    #[test]
+    fn prefers_frame_hdr_metadata_over_container() {
+        let light_level = |max_content_light_level| ContentLightLevel {
+            max_content_light_level,
+            max_frame_average_light_level: 400,
+        };
+        let container = HdrMetadata {
+            mastering_display: Some(MasteringDisplay {
+                primaries: [(Rational32::new(17, 25), Rational32::new(8, 25)); 3],
+                white_point: (Rational32::new(3127, 10000), Rational32::new(329, 1000)),
+                min_luminance: Rational32::new(1, 10000),
+                max_luminance: Rational32::from_integer(1000),
+            }),
+            content_light_level: Some(light_level(1000)),
+        };
+        let frame = HdrMetadata {
+            mastering_display: None,
+            content_light_level: Some(light_level(4000)),
+        };
+
+        let merged = merge_hdr_metadata(Some(frame), Some(container)).expect("metadata");
+        assert_eq!(merged.content_light_level, Some(light_level(4000)));
+        assert_eq!(merged.mastering_display, container.mastering_display);
+        assert_eq!(merge_hdr_metadata(None, Some(container)), Some(container));
+        assert_eq!(merge_hdr_metadata(Some(frame), None), Some(frame));
+    }
+
+    #[test]
+    fn snaps_rotation_to_quarter_turns() {
+        assert_eq!(snap_rotation(0.0), 0);
+        assert_eq!(snap_rotation(90.0), 90);
+        assert_eq!(snap_rotation(-90.0), 270);
+        assert_eq!(snap_rotation(180.0), 180);
+        assert_eq!(snap_rotation(-180.0), 180);
+        assert_eq!(snap_rotation(269.6), 270);
+        assert_eq!(snap_rotation(360.0), 0);
+    }
+
+    #[test]
+    fn frame_count_within_tolerance_passes() {
+        assert!(check_frame_count(100, Some(100), 0).is_ok());
+        assert!(check_frame_count(98, Some(100), 2).is_ok());
+        assert!(check_frame_count(102, Some(100), 2).is_ok());
+        assert!(check_frame_count(5, None, 0).is_ok());
+    }
+
+    #[test]
+    fn frame_count_desync_is_reported() {
+        match check_frame_count(97, Some(100), 2) {
+            Err(DecoderError::GenericDecodeError { cause, .. }) => {
+                assert_eq!(cause, "decoded 97 frames, but the container reports 100");
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn maps_output_formats() {
+        assert_eq!(
+            output_pixel_format(10, ChromaSubsampling::Yuv420).expect("supported"),
+            format::Pixel::YUV420P10LE
+        );
+        assert_eq!(
+            output_pixel_format(8, ChromaSubsampling::Monochrome).expect("supported"),
+            format::Pixel::GRAY8
+        );
+        assert!(matches!(
+            output_pixel_format(16, ChromaSubsampling::Yuv444),
+            Err(DecoderError::UnsupportedFormat { .. })
+        ));
+    }
+
+    #[test]
+    fn unpacks_yuyv_and_uyvy_to_planar() {
+        // 3x2 frame; each row ends with a padding luma sample and one byte of stride padding
+        let yuyv = [
+            10, 50, 11, 60, 12, 51, 0, 61, 0, //
+            20, 52, 21, 62, 22, 53, 0, 63, 0,
+        ];
+        let uyvy = [
+            50, 10, 60, 11, 51, 12, 61, 0, 0, //
+            52, 20, 62, 21, 53, 22, 63, 0, 0,
+        ];
+        let planar = [
+            vec![10, 11, 12, 20, 21, 22],
+            vec![50, 51, 52, 53],
+            vec![60, 61, 62, 63],
+        ];
+
+        assert_eq!(
+            unpack_422(&yuyv, 9, 3, 2, true).expect("two full rows"),
+            planar
+        );
+        assert_eq!(
+            unpack_422(&uyvy, 9, 3, 2, false).expect("two full rows"),
+            planar
+        );
+
+        // The last row may end right after its samples, without padding
+        assert_eq!(
+            unpack_422(&yuyv[..17], 9, 3, 2, true).expect("last row is unpadded"),
+            planar
+        );
+        assert!(unpack_422(&yuyv[..16], 9, 3, 2, true).is_err());
+        assert!(unpack_422(&yuyv[..9], 9, 3, 2, true).is_err());
+    }
+
+    #[test]
+    fn deinterleave_keeps_last_odd_column() {
+        // 3x2 chroma of a 5x3 NV12 frame, with one byte of row padding
+        let data = [1, 2, 3, 4, 5, 6, 0, 7, 8, 9, 10, 11, 12, 0];
+
+        let (u_data, v_data) =
+            deinterleave_chroma(&data, 7, 5_usize.div_ceil(2), 2).expect("two full rows");
+        assert_eq!(u_data, [1, 3, 5, 7, 9, 11]);
+        assert_eq!(v_data, [2, 4, 6, 8, 10, 12]);
+
+        // The last row may end right after its samples, without padding
+        let (u_data, _) = deinterleave_chroma(&data[..13], 7, 3, 2).expect("last row is unpadded");
+        assert_eq!(u_data, [1, 3, 5, 7, 9, 11]);
+        assert!(deinterleave_chroma(&data[..12], 7, 3, 2).is_err());
+    }
+}
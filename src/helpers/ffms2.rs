@@ -1,29 +1,62 @@
 use std::{
+    borrow::Cow,
     ffi::CString,
-    ffi::c_char,
-    path::Path,
+    ffi::{c_char, c_int, c_void},
+    path::{Path, PathBuf},
     slice,
     str::FromStr,
-    sync::{LazyLock, Once},
+    sync::{Arc, LazyLock, Once},
 };
 
 use ffms2_sys::{
-    FFMS_CreateIndexer, FFMS_CreateVideoSource, FFMS_DestroyIndex, FFMS_DestroyVideoSource,
-    FFMS_DoIndexing2, FFMS_ErrorInfo, FFMS_GetFirstIndexedTrackOfType, FFMS_GetFrame,
-    FFMS_GetPixFmt, FFMS_GetTrackFromIndex, FFMS_GetTrackType, FFMS_GetVideoProperties, FFMS_Index,
+    FFMS_ColorRanges, FFMS_CreateIndexer, FFMS_CreateVideoSource, FFMS_DestroyIndex,
+    FFMS_DestroyVideoSource, FFMS_DoIndexing2, FFMS_ErrorInfo, FFMS_GetFirstIndexedTrackOfType,
+    FFMS_GetFrame, FFMS_GetFrameInfo, FFMS_GetNumTracks, FFMS_GetPixFmt, FFMS_GetTrackFromIndex,
+    FFMS_GetTrackFromVideo, FFMS_GetTrackType, FFMS_GetVideoProperties, FFMS_Index,
     FFMS_IndexBelongsToFile, FFMS_Init, FFMS_ReadIndex, FFMS_Resizers, FFMS_SetOutputFormatV2,
-    FFMS_TrackType, FFMS_TrackTypeIndexSettings, FFMS_VideoSource, FFMS_WriteIndex,
+    FFMS_SetProgressCallback, FFMS_TrackType, FFMS_TrackTypeIndexSettings, FFMS_VideoProperties,
+    FFMS_VideoSource, FFMS_WriteIndex,
 };
 use num_rational::Rational32;
 use v_frame::{chroma::ChromaSubsampling, frame::Frame, pixel::Pixel};
 
-use crate::{DecoderError, VideoDetails};
-
-use super::frame_builder::new_padded_frame;
+use crate::{
+    ChromaLocation, ColorPrimaries, ColorRange, ContentLightLevel, DecoderError, FieldOrder,
+    HdrMetadata, MasteringDisplay, MatrixCoefficients, Resizer, TransferCharacteristics,
+    VideoDetails,
+    util::{chromaticity, luminance, sample_aspect_ratio},
+};
 
 /// Ensures FFMS2 is initialized only once per process
 static FFMS2_INIT: Once = Once::new();
 
+/// Where [`Ffms2Decoder`] caches the index it builds for a file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum IndexCache {
+    /// Reads and writes `{input}.ffindex` next to the input file.
+    #[default]
+    NextToInput,
+    /// Reads and writes `{file name}.ffindex` in the given directory, e.g. for inputs on
+    /// read-only media. Index files of different inputs with the same file name replace
+    /// each other.
+    Directory(PathBuf),
+    /// Keeps the index in memory only, re-indexing the file every time it is opened.
+    InMemory,
+}
+
+impl IndexCache {
+    /// Returns the path of the index file for `input`, if it is cached on disk.
+    fn index_path(&self, input: &Path) -> Option<PathBuf> {
+        let mut file_name = match self {
+            Self::NextToInput => input.as_os_str().to_owned(),
+            Self::Directory(dir) => dir.join(input.file_name()?).into_os_string(),
+            Self::InMemory => return None,
+        };
+        file_name.push(".ffindex");
+        Some(file_name.into())
+    }
+}
+
 /// Video decoder using the FFMS2 library.
 ///
 /// Contains raw pointers; cleaned up via `Drop`.
@@ -32,7 +65,6 @@ pub struct Ffms2Decoder {
     pub video_details: VideoDetails,
     #[allow(missing_docs)]
     pub video_source: *mut FFMS_VideoSource,
-    #[expect(dead_code, reason = "Keep alive until drop")]
     index_handle: FfmsIndex,
 }
 
@@ -76,6 +108,52 @@ impl Ffms2Decoder {
     /// [`DecoderError::GenericDecodeError`] on indexing or source creation failure.
     #[inline]
     pub fn new<P: AsRef<Path>>(input: P, track_index: Option<u8>) -> Result<Self, DecoderError> {
+        Self::open(input.as_ref(), track_index, &IndexCache::default(), None)
+    }
+
+    /// Creates a new FFMS2 decoder like [`new`](Self::new), caching the index as given by
+    /// `index_cache` instead of next to the input.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`new`](Self::new).
+    #[inline]
+    pub fn new_with_index_cache<P: AsRef<Path>>(
+        input: P,
+        track_index: Option<u8>,
+        index_cache: &IndexCache,
+    ) -> Result<Self, DecoderError> {
+        Self::open(input.as_ref(), track_index, index_cache, None)
+    }
+
+    /// Creates a new FFMS2 decoder like [`new`](Self::new), reporting indexing progress.
+    ///
+    /// `progress` is called periodically with the number of bytes indexed so far and the
+    /// total size of the file. It is not called when an up-to-date `.ffindex` file is reused.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`new`](Self::new).
+    #[inline]
+    pub fn new_with_progress<P: AsRef<Path>, F: FnMut(u64, u64)>(
+        input: P,
+        track_index: Option<u8>,
+        mut progress: F,
+    ) -> Result<Self, DecoderError> {
+        Self::open(
+            input.as_ref(),
+            track_index,
+            &IndexCache::default(),
+            Some(&mut progress),
+        )
+    }
+
+    fn open(
+        input: &Path,
+        track_index: Option<u8>,
+        index_cache: &IndexCache,
+        progress: Option<&mut dyn FnMut(u64, u64)>,
+    ) -> Result<Self, DecoderError> {
         FFMS2_INIT.call_once(|| {
             // SAFETY: FFI call with infallible parameters
             unsafe {
@@ -83,14 +161,11 @@ impl Ffms2Decoder {
             }
         });
 
-        let index_handle = Self::get_index(input.as_ref(), track_index)?;
+        let index_handle = Self::get_index(input, track_index, index_cache, progress)?;
 
         let threads = std::thread::available_parallelism().map_or(8, std::num::NonZero::get) as i32;
 
-        let source =
-            CString::new(index_handle.path.as_str()).map_err(|e| DecoderError::FileReadError {
-                cause: e.to_string(),
-            })?;
+        let source = CString::new(index_handle.path.as_str()).map_err(DecoderError::file_read)?;
         let mut err_buffer: [c_char; ERR_BUFFER_SIZE] = [0; ERR_BUFFER_SIZE];
         let mut err = empty_error_info(&mut err_buffer);
         // SAFETY: `source` is not null since we just created it
@@ -109,6 +184,7 @@ impl Ffms2Decoder {
             let error_msg = get_error_message(err);
             return Err(DecoderError::GenericDecodeError {
                 cause: format!("Failed to create video source: {}", error_msg),
+                source: None,
             });
         }
 
@@ -136,6 +212,7 @@ impl Ffms2Decoder {
     /// * `height` - Desired output height in pixels.
     /// * `bit_depth` - Desired per-plane bit depth (e.g., 10 for 10-bit output).
     /// * `chroma_subsampling` - Tuple matching the FFMS2 chroma layout (horizontal, vertical).
+    /// * `resizer` - Scaling algorithm; [`Resizer::Bicubic`] matches previous versions.
     ///
     /// # Errors
     /// * `DecoderError::UnsupportedFormat` - The bit depth / chroma combination is not currently supported by this library.
@@ -146,7 +223,15 @@ impl Ffms2Decoder {
         height: usize,
         bit_depth: u8,
         chroma_subsampling: ChromaSubsampling,
+        resizer: Resizer,
     ) -> Result<(), DecoderError> {
+        let resizer = match resizer {
+            Resizer::Point => FFMS_Resizers::FFMS_RESIZER_POINT,
+            Resizer::Bilinear => FFMS_Resizers::FFMS_RESIZER_BILINEAR,
+            Resizer::Bicubic => FFMS_Resizers::FFMS_RESIZER_BICUBIC,
+            Resizer::Lanczos => FFMS_Resizers::FFMS_RESIZER_LANCZOS,
+            Resizer::Spline => FFMS_Resizers::FFMS_RESIZER_SPLINE,
+        };
         let mut err_buffer: [c_char; ERR_BUFFER_SIZE] = [0; ERR_BUFFER_SIZE];
         let mut err = empty_error_info(&mut err_buffer);
         // SAFETY: `self.video_source` cannot be null here
@@ -161,7 +246,7 @@ impl Ffms2Decoder {
                 .as_ptr(),
                 width as i32,
                 height as i32,
-                FFMS_Resizers::FFMS_RESIZER_BICUBIC as i32,
+                resizer as i32,
                 std::ptr::addr_of_mut!(err),
             );
         }
@@ -176,27 +261,32 @@ impl Ffms2Decoder {
         Ok(())
     }
 
-    fn get_index(input: &Path, track_index: Option<u8>) -> Result<FfmsIndex, DecoderError> {
+    fn get_index(
+        input: &Path,
+        track_index: Option<u8>,
+        index_cache: &IndexCache,
+        mut progress: Option<&mut dyn FnMut(u64, u64)>,
+    ) -> Result<FfmsIndex, DecoderError> {
         let mut err_buffer: [c_char; ERR_BUFFER_SIZE] = [0; ERR_BUFFER_SIZE];
         let mut err = empty_error_info(&mut err_buffer);
 
-        let input_cstr = CString::from_str(&input.to_string_lossy()).map_err(|e| {
-            DecoderError::FileReadError {
-                cause: e.to_string(),
+        let input_cstr =
+            CString::from_str(&input.to_string_lossy()).map_err(DecoderError::file_read)?;
+
+        let idx_path = index_cache.index_path(input);
+        let idx_cstr = idx_path
+            .as_ref()
+            .map(|idx_path| {
+                CString::new(idx_path.to_string_lossy().as_bytes()).map_err(DecoderError::file_read)
+            })
+            .transpose()?;
+
+        let mut idx = match (&idx_path, &idx_cstr) {
+            (Some(idx_path), Some(idx_cstr)) if idx_path.exists() => {
+                // SAFETY: `idx_cstr` is not null since we just created it
+                unsafe { FFMS_ReadIndex(idx_cstr.as_ptr(), std::ptr::addr_of_mut!(err)) }
             }
-        })?;
-
-        let idx_path = format!("{}.ffindex", input.to_string_lossy());
-        let idx_cstr =
-            CString::new(idx_path.as_str()).map_err(|e| DecoderError::FileReadError {
-                cause: e.to_string(),
-            })?;
-
-        let mut idx = if std::path::Path::new(&idx_path).exists() {
-            // SAFETY: `idx_cstr` is not null since we just created it
-            unsafe { FFMS_ReadIndex(idx_cstr.as_ptr(), std::ptr::addr_of_mut!(err)) }
-        } else {
-            std::ptr::null_mut()
+            _ => std::ptr::null_mut(),
         };
 
         if !idx.is_null()
@@ -220,6 +310,7 @@ impl Ffms2Decoder {
                 let error_msg = get_error_message(err);
                 return Err(DecoderError::GenericDecodeError {
                     cause: format!("Failed to create indexer: {}", error_msg),
+                    source: None,
                 });
             }
 
@@ -236,6 +327,14 @@ impl Ffms2Decoder {
                     0,
                 );
 
+                if let Some(progress) = &mut progress {
+                    FFMS_SetProgressCallback(
+                        idxer,
+                        Some(index_progress),
+                        std::ptr::from_mut(progress).cast(),
+                    );
+                }
+
                 FFMS_DoIndexing2(idxer, 0, std::ptr::addr_of_mut!(err))
             };
 
@@ -243,11 +342,15 @@ impl Ffms2Decoder {
                 let error_msg = get_error_message(err);
                 return Err(DecoderError::GenericDecodeError {
                     cause: format!("Failed to index input file: {}", error_msg),
+                    source: None,
                 });
             }
 
-            // SAFETY: verified `idx` is not null
-            unsafe { FFMS_WriteIndex(idx_cstr.as_ptr(), idx, std::ptr::addr_of_mut!(err)) };
+            if let Some(idx_cstr) = &idx_cstr {
+                // A failed write only means the file is indexed again next time
+                // SAFETY: verified `idx` is not null
+                unsafe { FFMS_WriteIndex(idx_cstr.as_ptr(), idx, std::ptr::addr_of_mut!(err)) };
+            }
             idx
         } else {
             idx
@@ -300,6 +403,13 @@ impl Ffms2Decoder {
             let frame_rate =
                 Rational32::new((*props).FPSNumerator as i32, (*props).FPSDenominator as i32);
             let total_frames = Some((*props).NumFrames as usize);
+            let field_order = if (*frame).InterlacedFrame == 0 {
+                FieldOrder::Progressive
+            } else if (*frame).TopFieldFirst != 0 {
+                FieldOrder::TopFieldFirst
+            } else {
+                FieldOrder::BottomFieldFirst
+            };
 
             // Extract bit depth and chroma sampling from pixel format
             let pix_fmt = (*frame).ConvertedPixelFormat;
@@ -312,17 +422,62 @@ impl Ffms2Decoder {
                 chroma_sampling,
                 frame_rate,
                 total_frames,
+                field_order,
+                chroma_location: map_chroma_location((*props).ChromaLocation),
+                color_range: if (*props).ColorRange == FFMS_ColorRanges::FFMS_CR_JPEG as i32 {
+                    ColorRange::Full
+                } else {
+                    ColorRange::Limited
+                },
+                sample_aspect_ratio: sample_aspect_ratio(
+                    (*props).SARNum.into(),
+                    (*props).SARDen.into(),
+                ),
+                hdr_metadata: get_hdr_metadata(&*props),
+                rotation: 0,
+                color_primaries: ColorPrimaries::from_h273((*props).ColorPrimaries.into()),
+                transfer_characteristics: TransferCharacteristics::from_h273(
+                    (*props).TransferCharateristics.into(),
+                ),
+                matrix_coefficients: MatrixCoefficients::from_h273((*props).ColorSpace.into()),
             };
 
             Ok(inf)
         }
     }
 
+    /// Returns the index of the keyframe at or before `frame_index`, looked up in the
+    /// keyframe table of the index.
+    pub(crate) fn keyframe_at_or_before(&self, frame_index: usize) -> usize {
+        // SAFETY: the video source is valid for the lifetime of the decoder, and the caller
+        // checked that `frame_index` is within the track
+        unsafe {
+            let track = FFMS_GetTrackFromVideo(self.video_source);
+            (0..=frame_index)
+                .rev()
+                .find(|&index| (*FFMS_GetFrameInfo(track, index as i32)).KeyFrame != 0)
+                .unwrap_or(0)
+        }
+    }
+
+    /// Returns `true` if the indexed file has an audio track. Audio is never indexed, but
+    /// its tracks are still listed in the index.
+    pub(crate) fn has_audio(&self) -> bool {
+        let idx = self.index_handle.idx_handle;
+        // SAFETY: the index is kept alive by `index_handle` and its tracks are in range
+        unsafe {
+            (0..FFMS_GetNumTracks(idx)).any(|track| {
+                FFMS_GetTrackType(FFMS_GetTrackFromIndex(idx, track))
+                    == FFMS_TrackType::FFMS_TYPE_AUDIO as i32
+            })
+        }
+    }
+
     pub(crate) fn read_video_frame<T: Pixel>(
         &mut self,
         frame_index: usize,
-        luma_only: bool,
-    ) -> Result<Frame<T>, DecoderError> {
+        frame: &mut Frame<T>,
+    ) -> Result<(), DecoderError> {
         if frame_index
             >= self
                 .video_details
@@ -348,227 +503,232 @@ impl Ffms2Decoder {
             });
         }
 
-        let mut frame: Frame<T> = new_padded_frame(&self.video_details, luma_only)?;
-
-        let chroma_height = self.video_details.height
-            / self
-                .video_details
-                .chroma_sampling
-                .subsample_ratio()
-                .map_or(1, |(_x, y)| y.get()) as usize;
-        // SAFETY: we assume that the values provided by FFMS2 are correct
-        unsafe {
-            frame.y_plane.copy_from_u8_slice_with_stride(
-                slice::from_raw_parts(
-                    (*raw_frame).Data[0],
-                    (*raw_frame).Linesize[0] as usize * self.video_details.height,
-                ),
-                (*raw_frame).Linesize[0] as usize,
+        let heights = plane_heights(
+            self.video_details.height,
+            self.video_details.chroma_sampling,
+        );
+        // SAFETY: verified that `raw_frame` is not null
+        let (data, linesize, pix_fmt, reported_height) = unsafe {
+            (
+                (*raw_frame).Data,
+                (*raw_frame).Linesize,
+                (*raw_frame).ConvertedPixelFormat,
+                if (*raw_frame).ScaledHeight > 0 {
+                    (*raw_frame).ScaledHeight
+                } else {
+                    (*raw_frame).EncodedHeight
+                } as usize,
             )
+        };
+        // The plane lengths below are derived from the stream's height, so reading a frame
+        // of a different size would go out of bounds
+        if plane_heights(reported_height, self.video_details.chroma_sampling) != heights {
+            return Err(DecoderError::VariableResolution);
         }
-        .map_err(|e| DecoderError::GenericDecodeError {
-            cause: format!("Failed to copy Y-plane data: {e}"),
-        })?;
+        let big_endian = is_big_endian(pix_fmt);
+        // SAFETY: we assume that the values provided by FFMS2 are correct
+        let y_data = unsafe { slice::from_raw_parts(data[0], linesize[0] as usize * heights[0]) };
+        frame
+            .y_plane
+            .copy_from_u8_slice_with_stride(
+                &to_little_endian(y_data, big_endian),
+                linesize[0] as usize,
+            )
+            .map_err(|e| DecoderError::GenericDecodeError {
+                cause: format!("Failed to copy Y-plane data: {e}"),
+                source: Some(Arc::new(e)),
+            })?;
         if let Some(u_plane) = frame.u_plane.as_mut() {
             // SAFETY: we assume that the values provided by FFMS2 are correct
-            unsafe {
-                u_plane.copy_from_u8_slice_with_stride(
-                    slice::from_raw_parts(
-                        (*raw_frame).Data[1],
-                        (*raw_frame).Linesize[1] as usize * chroma_height,
-                    ),
-                    (*raw_frame).Linesize[1] as usize,
+            let u_data =
+                unsafe { slice::from_raw_parts(data[1], linesize[1] as usize * heights[1]) };
+            u_plane
+                .copy_from_u8_slice_with_stride(
+                    &to_little_endian(u_data, big_endian),
+                    linesize[1] as usize,
                 )
-            }
-            .map_err(|e| DecoderError::GenericDecodeError {
-                cause: format!("Failed to copy U-plane data: {e}"),
-            })?;
+                .map_err(|e| DecoderError::GenericDecodeError {
+                    cause: format!("Failed to copy U-plane data: {e}"),
+                    source: Some(Arc::new(e)),
+                })?;
         }
         if let Some(v_plane) = frame.v_plane.as_mut() {
             // SAFETY: we assume that the values provided by FFMS2 are correct
-            unsafe {
-                v_plane.copy_from_u8_slice_with_stride(
-                    slice::from_raw_parts(
-                        (*raw_frame).Data[2],
-                        (*raw_frame).Linesize[2] as usize * chroma_height,
-                    ),
-                    (*raw_frame).Linesize[2] as usize,
+            let v_data =
+                unsafe { slice::from_raw_parts(data[2], linesize[2] as usize * heights[2]) };
+            v_plane
+                .copy_from_u8_slice_with_stride(
+                    &to_little_endian(v_data, big_endian),
+                    linesize[2] as usize,
                 )
-            }
-            .map_err(|e| DecoderError::GenericDecodeError {
-                cause: format!("Failed to copy V-plane data: {e}"),
-            })?;
+                .map_err(|e| DecoderError::GenericDecodeError {
+                    cause: format!("Failed to copy V-plane data: {e}"),
+                    source: Some(Arc::new(e)),
+                })?;
         }
 
-        Ok(frame)
+        Ok(())
     }
 }
 
-// FFmpeg pixel format constants (from libavutil/pixfmt.h)
-// These are used to interpret FFMS_Frame::ConvertedPixelFormat values
-// Using `FFMS_GetPixFmt` ensures we have the correct value regardless
-// of the ffmpeg version we are linked against
+/// Reads the mastering display and content light level fields of the video properties.
+fn get_hdr_metadata(props: &FFMS_VideoProperties) -> Option<HdrMetadata> {
+    let mastering_display = (props.HasMasteringDisplayPrimaries != 0
+        && props.HasMasteringDisplayLuminance != 0)
+        .then(|| MasteringDisplay {
+            primaries: [0, 1, 2].map(|i| {
+                (
+                    chromaticity(props.MasteringDisplayPrimariesX[i]),
+                    chromaticity(props.MasteringDisplayPrimariesY[i]),
+                )
+            }),
+            white_point: (
+                chromaticity(props.MasteringDisplayWhitePointX),
+                chromaticity(props.MasteringDisplayWhitePointY),
+            ),
+            min_luminance: luminance(props.MasteringDisplayMinLuminance),
+            max_luminance: luminance(props.MasteringDisplayMaxLuminance),
+        });
+    let content_light_level = (props.HasContentLightLevel != 0).then(|| ContentLightLevel {
+        max_content_light_level: props.ContentLightLevelMax,
+        max_frame_average_light_level: props.ContentLightLevelAverage,
+    });
+    (mastering_display.is_some() || content_light_level.is_some()).then_some(HdrMetadata {
+        mastering_display,
+        content_light_level,
+    })
+}
+
+/// Computes the number of rows in the Y, U, and V planes of a frame.
+///
+/// Chroma heights round up, matching FFmpeg's `AV_CEIL_RSHIFT` for odd luma heights.
+fn plane_heights(height: usize, chroma_sampling: ChromaSubsampling) -> [usize; 3] {
+    let chroma_height = chroma_sampling
+        .subsample_ratio()
+        .map_or(0, |(_x, y)| height.div_ceil(y.get() as usize));
+    [height, chroma_height, chroma_height]
+}
+
+/// An FFmpeg pixel format that FFMS2 may output.
+#[derive(Debug, Clone, Copy)]
+struct PixelFormat {
+    /// The `AVPixelFormat` value of the FFmpeg version FFMS2 is linked against
+    id: i32,
+    bit_depth: usize,
+    chroma_sampling: ChromaSubsampling,
+    big_endian: bool,
+}
+
+// FFmpeg pixel format values (from libavutil/pixfmt.h) are looked up by name with
+// `FFMS_GetPixFmt`, which ensures we have the correct value regardless of the FFmpeg
+// version we are linked against.
 //
-// These are stored in `LazyLock`s because they can be frequently called,
-// which should be a fast operation, but because it is an FFI,
-// would always invoke an expensive `call` operation`.
-static AV_PIX_FMT_YUV420P: LazyLock<i32> = LazyLock::new(|| {
-    // SAFETY: FFI call with a const C string
-    unsafe { FFMS_GetPixFmt(c"yuv420p".as_ptr().cast()) }
-});
-static AV_PIX_FMT_YUV422P: LazyLock<i32> = LazyLock::new(|| {
-    // SAFETY: FFI call with a const C string
-    unsafe { FFMS_GetPixFmt(c"yuv422p".as_ptr().cast()) }
-});
-static AV_PIX_FMT_YUV444P: LazyLock<i32> = LazyLock::new(|| {
-    // SAFETY: FFI call with a const C string
-    unsafe { FFMS_GetPixFmt(c"yuv444p".as_ptr().cast()) }
-});
-static AV_PIX_FMT_GRAY8: LazyLock<i32> = LazyLock::new(|| {
-    // SAFETY: FFI call with a const C string
-    unsafe { FFMS_GetPixFmt(c"gray8".as_ptr().cast()) }
-});
-static AV_PIX_FMT_YUV420P10BE: LazyLock<i32> = LazyLock::new(|| {
-    // SAFETY: FFI call with a const C string
-    unsafe { FFMS_GetPixFmt(c"yuv420p10be".as_ptr().cast()) }
-});
-static AV_PIX_FMT_YUV420P10LE: LazyLock<i32> = LazyLock::new(|| {
-    // SAFETY: FFI call with a const C string
-    unsafe { FFMS_GetPixFmt(c"yuv420p10le".as_ptr().cast()) }
-});
-static AV_PIX_FMT_YUV422P10BE: LazyLock<i32> = LazyLock::new(|| {
-    // SAFETY: FFI call with a const C string
-    unsafe { FFMS_GetPixFmt(c"yuv422p10be".as_ptr().cast()) }
-});
-static AV_PIX_FMT_YUV422P10LE: LazyLock<i32> = LazyLock::new(|| {
-    // SAFETY: FFI call with a const C string
-    unsafe { FFMS_GetPixFmt(c"yuv422p10le".as_ptr().cast()) }
-});
-static AV_PIX_FMT_YUV444P10BE: LazyLock<i32> = LazyLock::new(|| {
-    // SAFETY: FFI call with a const C string
-    unsafe { FFMS_GetPixFmt(c"yuv444p10be".as_ptr().cast()) }
-});
-static AV_PIX_FMT_YUV444P10LE: LazyLock<i32> = LazyLock::new(|| {
-    // SAFETY: FFI call with a const C string
-    unsafe { FFMS_GetPixFmt(c"yuv444p10le".as_ptr().cast()) }
-});
-static AV_PIX_FMT_YUV420P12BE: LazyLock<i32> = LazyLock::new(|| {
-    // SAFETY: FFI call with a const C string
-    unsafe { FFMS_GetPixFmt(c"yuv420p12be".as_ptr().cast()) }
-});
-static AV_PIX_FMT_YUV420P12LE: LazyLock<i32> = LazyLock::new(|| {
-    // SAFETY: FFI call with a const C string
-    unsafe { FFMS_GetPixFmt(c"yuv420p12le".as_ptr().cast()) }
-});
-static AV_PIX_FMT_YUV422P12BE: LazyLock<i32> = LazyLock::new(|| {
-    // SAFETY: FFI call with a const C string
-    unsafe { FFMS_GetPixFmt(c"yuv422p12be".as_ptr().cast()) }
-});
-static AV_PIX_FMT_YUV422P12LE: LazyLock<i32> = LazyLock::new(|| {
-    // SAFETY: FFI call with a const C string
-    unsafe { FFMS_GetPixFmt(c"yuv422p12le".as_ptr().cast()) }
-});
-static AV_PIX_FMT_YUV444P12BE: LazyLock<i32> = LazyLock::new(|| {
-    // SAFETY: FFI call with a const C string
-    unsafe { FFMS_GetPixFmt(c"yuv444p12be".as_ptr().cast()) }
-});
-static AV_PIX_FMT_YUV444P12LE: LazyLock<i32> = LazyLock::new(|| {
-    // SAFETY: FFI call with a const C string
-    unsafe { FFMS_GetPixFmt(c"yuv444p12le".as_ptr().cast()) }
-});
-static AV_PIX_FMT_GRAY12BE: LazyLock<i32> = LazyLock::new(|| {
-    // SAFETY: FFI call with a const C string
-    unsafe { FFMS_GetPixFmt(c"gray12be".as_ptr().cast()) }
-});
-static AV_PIX_FMT_GRAY12LE: LazyLock<i32> = LazyLock::new(|| {
-    // SAFETY: FFI call with a const C string
-    unsafe { FFMS_GetPixFmt(c"gray12le".as_ptr().cast()) }
-});
-static AV_PIX_FMT_GRAY10BE: LazyLock<i32> = LazyLock::new(|| {
-    // SAFETY: FFI call with a const C string
-    unsafe { FFMS_GetPixFmt(c"gray10be".as_ptr().cast()) }
-});
-static AV_PIX_FMT_GRAY10LE: LazyLock<i32> = LazyLock::new(|| {
-    // SAFETY: FFI call with a const C string
-    unsafe { FFMS_GetPixFmt(c"gray10le".as_ptr().cast()) }
+// They are stored in a `LazyLock` because they can be frequently needed, which should be
+// a fast operation, but because it is an FFI, would always invoke an expensive `call`
+// operation.
+static PIXEL_FORMATS: LazyLock<Vec<PixelFormat>> = LazyLock::new(|| {
+    let layouts = [
+        ("yuv420p", ChromaSubsampling::Yuv420),
+        ("yuv422p", ChromaSubsampling::Yuv422),
+        ("yuv444p", ChromaSubsampling::Yuv444),
+        ("gray", ChromaSubsampling::Monochrome),
+    ];
+    let mut formats = Vec::new();
+    for (layout, chroma_sampling) in layouts {
+        let mut names = vec![(format!("{layout}\0"), 8, false)];
+        for bit_depth in [10, 12, 14, 16] {
+            names.push((format!("{layout}{bit_depth}le\0"), bit_depth, false));
+            names.push((format!("{layout}{bit_depth}be\0"), bit_depth, true));
+        }
+        for (name, bit_depth, big_endian) in names {
+            formats.push(PixelFormat {
+                // SAFETY: FFI call with a nul-terminated C string
+                id: unsafe { FFMS_GetPixFmt(name.as_ptr().cast()) },
+                bit_depth,
+                chroma_sampling,
+                big_endian,
+            });
+        }
+    }
+    formats
 });
 
 /// Maps FFmpeg pixel format to bit depth and chroma sampling
 fn pixel_format_to_video_info(pix_fmt: i32) -> Result<(usize, ChromaSubsampling), DecoderError> {
-    match pix_fmt {
-        // 8-bit formats
-        x if x == *AV_PIX_FMT_YUV420P => Ok((8, ChromaSubsampling::Yuv420)),
-        x if x == *AV_PIX_FMT_YUV422P => Ok((8, ChromaSubsampling::Yuv422)),
-        x if x == *AV_PIX_FMT_YUV444P => Ok((8, ChromaSubsampling::Yuv444)),
-        x if x == *AV_PIX_FMT_GRAY8 => Ok((8, ChromaSubsampling::Monochrome)),
-
-        // 10-bit formats
-        x if x == *AV_PIX_FMT_YUV420P10LE || x == *AV_PIX_FMT_YUV420P10BE => {
-            Ok((10, ChromaSubsampling::Yuv420))
-        }
-        x if x == *AV_PIX_FMT_YUV422P10LE || x == *AV_PIX_FMT_YUV422P10BE => {
-            Ok((10, ChromaSubsampling::Yuv422))
-        }
-        x if x == *AV_PIX_FMT_YUV444P10LE || x == *AV_PIX_FMT_YUV444P10BE => {
-            Ok((10, ChromaSubsampling::Yuv444))
-        }
-        x if x == *AV_PIX_FMT_GRAY10LE || x == *AV_PIX_FMT_GRAY10BE => {
-            Ok((10, ChromaSubsampling::Monochrome))
-        }
+    PIXEL_FORMATS
+        .iter()
+        .find(|format| format.id == pix_fmt)
+        .map(|format| (format.bit_depth, format.chroma_sampling))
+        .ok_or_else(|| DecoderError::UnsupportedFormat {
+            fmt: format!("Unsupported pixel format: {}", pix_fmt),
+        })
+}
 
-        // 12-bit formats
-        x if x == *AV_PIX_FMT_YUV420P12LE || x == *AV_PIX_FMT_YUV420P12BE => {
-            Ok((12, ChromaSubsampling::Yuv420))
-        }
-        x if x == *AV_PIX_FMT_YUV422P12LE || x == *AV_PIX_FMT_YUV422P12BE => {
-            Ok((12, ChromaSubsampling::Yuv422))
-        }
-        x if x == *AV_PIX_FMT_YUV444P12LE || x == *AV_PIX_FMT_YUV444P12BE => {
-            Ok((12, ChromaSubsampling::Yuv444))
-        }
-        x if x == *AV_PIX_FMT_GRAY12LE || x == *AV_PIX_FMT_GRAY12BE => {
-            Ok((12, ChromaSubsampling::Monochrome))
-        }
+/// Maps an `AVChromaLocation` value onto a [`ChromaLocation`], defaulting to `Left` when unspecified
+const fn map_chroma_location(location: i32) -> ChromaLocation {
+    match location {
+        2 => ChromaLocation::Center,
+        3 => ChromaLocation::TopLeft,
+        4 => ChromaLocation::Top,
+        5 => ChromaLocation::BottomLeft,
+        6 => ChromaLocation::Bottom,
+        _ => ChromaLocation::Left,
+    }
+}
 
-        _ => Err(DecoderError::UnsupportedFormat {
-            fmt: format!("Unsupported pixel format: {}", pix_fmt),
-        }),
+/// Returns `true` for the big-endian high bit depth formats FFMS2 may output.
+fn is_big_endian(pix_fmt: i32) -> bool {
+    PIXEL_FORMATS
+        .iter()
+        .any(|format| format.id == pix_fmt && format.big_endian)
+}
+
+/// Byte-swaps 16-bit big-endian samples to little-endian, the byte order `v_frame`
+/// expects when copying from bytes regardless of the host's.
+///
+/// Data that is already little-endian is borrowed unchanged.
+fn to_little_endian(data: &[u8], big_endian: bool) -> Cow<'_, [u8]> {
+    if !big_endian {
+        return Cow::Borrowed(data);
+    }
+    let mut swapped = data.to_vec();
+    for sample in swapped.chunks_exact_mut(2) {
+        sample.swap(0, 1);
     }
+    Cow::Owned(swapped)
 }
 
+/// Picks the little-endian (or 8-bit) pixel format with the given bit depth and chroma
+/// sampling, to request as FFMS2's output format.
 fn video_info_to_pixel_format(
     bit_depth: u8,
     chroma_subsampling: ChromaSubsampling,
 ) -> Result<i32, DecoderError> {
-    Ok(match (bit_depth, chroma_subsampling) {
-        // 8-bit formats
-        (8, ChromaSubsampling::Yuv420) => *AV_PIX_FMT_YUV420P,
-        (8, ChromaSubsampling::Yuv422) => *AV_PIX_FMT_YUV422P,
-        (8, ChromaSubsampling::Yuv444) => *AV_PIX_FMT_YUV444P,
-        (8, ChromaSubsampling::Monochrome) => *AV_PIX_FMT_GRAY8,
-
-        // 10-bit formats
-        (10, ChromaSubsampling::Yuv420) => *AV_PIX_FMT_YUV420P10LE,
-        (10, ChromaSubsampling::Yuv422) => *AV_PIX_FMT_YUV422P10LE,
-        (10, ChromaSubsampling::Yuv444) => *AV_PIX_FMT_YUV444P10LE,
-        (10, ChromaSubsampling::Monochrome) => *AV_PIX_FMT_GRAY10LE,
-
-        // 12-bit formats
-        (12, ChromaSubsampling::Yuv420) => *AV_PIX_FMT_YUV420P12LE,
-        (12, ChromaSubsampling::Yuv422) => *AV_PIX_FMT_YUV422P12LE,
-        (12, ChromaSubsampling::Yuv444) => *AV_PIX_FMT_YUV444P12LE,
-        (12, ChromaSubsampling::Monochrome) => *AV_PIX_FMT_GRAY12LE,
-
-        _ => {
-            return Err(DecoderError::UnsupportedFormat {
-                fmt: "Unsupported bit depth and subsampling combination".to_string(),
-            });
-        }
-    })
+    PIXEL_FORMATS
+        .iter()
+        .find(|format| {
+            format.bit_depth == usize::from(bit_depth)
+                && format.chroma_sampling == chroma_subsampling
+                && !format.big_endian
+        })
+        .map(|format| format.id)
+        .ok_or_else(|| DecoderError::UnsupportedFormat {
+            fmt: "Unsupported bit depth and subsampling combination".to_string(),
+        })
 }
 
 const ERR_BUFFER_SIZE: usize = 1024;
 
+/// Forwards FFMS2 indexing progress to the Rust callback passed as `private`.
+unsafe extern "C" fn index_progress(current: i64, total: i64, private: *mut c_void) -> c_int {
+    // SAFETY: `private` is the `&mut dyn FnMut` registered in `get_index`, which outlives
+    // the `FFMS_DoIndexing2` call that invokes this
+    let progress = unsafe { &mut *private.cast::<&mut dyn FnMut(u64, u64)>() };
+    progress(current.max(0) as u64, total.max(0) as u64);
+    // Returning non-zero would cancel indexing
+    0
+}
+
 /// Builds an `FFMS_ErrorInfo` that writes into the caller-owned stack buffer.
 fn empty_error_info(buffer: &mut [c_char; ERR_BUFFER_SIZE]) -> FFMS_ErrorInfo {
     FFMS_ErrorInfo {
@@ -597,6 +757,81 @@ fn get_error_message(err: FFMS_ErrorInfo) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    #[cfg(feature = "ffmpeg")]
+    use crate::test_util::{assert_flat_frame, write_flat_raw_clip};
+    #[cfg(feature = "ffmpeg")]
+    use crate::{Decoder, DecoderImpl};
+
+    #[test]
+    fn to_little_endian_swaps_big_endian_samples() {
+        // 10-bit samples 0x0123 and 0x03ff stored big-endian
+        let data = [0x01, 0x23, 0x03, 0xff];
+
+        assert_eq!(&*to_little_endian(&data, true), &[0x23, 0x01, 0xff, 0x03]);
+        assert!(matches!(to_little_endian(&data, false), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    #[cfg(feature = "ffmpeg")]
+    fn decodes_high_bit_depth_clips() {
+        use ffmpeg_the_third::format::Pixel;
+
+        for (pixel_format, bit_depth) in [
+            (Pixel::YUV420P10LE, 10),
+            (Pixel::YUV422P14LE, 14),
+            (Pixel::YUV444P16LE, 16),
+        ] {
+            let values = [
+                (u16::MAX >> (16 - bit_depth)) - 1,
+                1 << (bit_depth - 1),
+                0x0123,
+            ];
+            let path = write_flat_raw_clip(pixel_format, values);
+            let decoder = Ffms2Decoder::new_with_index_cache(&path, None, &IndexCache::InMemory)
+                .and_then(|ffms2| Decoder::from_decoder_impl(DecoderImpl::Ffms2(ffms2)));
+            let frame = decoder.and_then(|mut decoder| {
+                assert_eq!(decoder.get_video_details().bit_depth, bit_depth);
+                decoder.read_video_frame::<u16>()
+            });
+            std::fs::remove_file(&path).expect("remove test file");
+
+            assert_flat_frame(&frame.expect("high bit depth frame"), values);
+        }
+    }
+
+    #[test]
+    fn index_cache_paths() {
+        let input = Path::new("/media/dvd/movie.mkv");
+        assert_eq!(
+            IndexCache::NextToInput.index_path(input),
+            Some(PathBuf::from("/media/dvd/movie.mkv.ffindex"))
+        );
+        assert_eq!(
+            IndexCache::Directory(PathBuf::from("/tmp/cache")).index_path(input),
+            Some(PathBuf::from("/tmp/cache/movie.mkv.ffindex"))
+        );
+        assert_eq!(IndexCache::InMemory.index_path(input), None);
+    }
+
+    #[test]
+    fn plane_heights_follow_chroma_subsampling() {
+        assert_eq!(
+            plane_heights(480, ChromaSubsampling::Yuv420),
+            [480, 240, 240]
+        );
+        assert_eq!(
+            plane_heights(481, ChromaSubsampling::Yuv420),
+            [481, 241, 241]
+        );
+        assert_eq!(
+            plane_heights(480, ChromaSubsampling::Yuv422),
+            [480, 480, 480]
+        );
+        assert_eq!(
+            plane_heights(480, ChromaSubsampling::Monochrome),
+            [480, 0, 0]
+        );
+    }
 
     #[test]
     fn empty_error_info_points_to_caller_owned_buffer() {
@@ -2,44 +2,347 @@ use v_frame::{
     chroma::ChromaSubsampling,
     frame::{Frame, FrameBuilder},
     pixel::Pixel,
+    plane::Plane,
 };
 
-use crate::{DecoderError, LUMA_PADDING, VideoDetails};
+use crate::{DecoderConfig, DecoderError, Padding, VideoDetails, chroma_label};
 
-pub(super) fn new_padded_frame<T: Pixel>(
+/// Number of pixels removed from each edge of a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Crop {
+    pub left: usize,
+    pub top: usize,
+    pub right: usize,
+    pub bottom: usize,
+}
+
+pub(crate) fn new_padded_frame<T: Pixel>(
     cfg: &VideoDetails,
-    luma_only: bool,
+    config: DecoderConfig,
 ) -> Result<Frame<T>, DecoderError> {
     if cfg.width == 0 || cfg.height == 0 || cfg.bit_depth == 0 {
         return Err(DecoderError::GenericDecodeError {
             cause: "Zero resolution is not supported".to_string(),
+            source: None,
         });
     }
 
-    let chroma_sampling = if luma_only {
+    let chroma_sampling = if config.luma_only {
         ChromaSubsampling::Monochrome
     } else {
         cfg.chroma_sampling
     };
 
     FrameBuilder::new(cfg.width, cfg.height, chroma_sampling, cfg.bit_depth as u8)
-        .luma_padding_bottom(LUMA_PADDING)
-        .luma_padding_top(LUMA_PADDING)
-        .luma_padding_left(LUMA_PADDING)
-        .luma_padding_right(LUMA_PADDING)
+        .luma_padding_bottom(config.padding.bottom)
+        .luma_padding_top(config.padding.top)
+        .luma_padding_left(config.padding.left)
+        .luma_padding_right(config.padding.right)
         .build()
-        .map_err(|e| DecoderError::GenericDecodeError {
-            cause: e.to_string(),
+        .map_err(DecoderError::generic)
+}
+
+/// Checks that each side of `padding` is a multiple of the chroma subsampling, so that
+/// chroma planes are padded by whole samples.
+pub(crate) fn check_padding(details: &VideoDetails, padding: Padding) -> Result<(), DecoderError> {
+    let (ss_x, ss_y) = details
+        .chroma_sampling
+        .subsample_ratio()
+        .map_or((1, 1), |(x, y)| (x.get() as usize, y.get() as usize));
+    if padding.left % ss_x != 0
+        || padding.right % ss_x != 0
+        || padding.top % ss_y != 0
+        || padding.bottom % ss_y != 0
+    {
+        return Err(DecoderError::UnsupportedFormat {
+            fmt: format!(
+                "padding of {}/{}/{}/{} (left/top/right/bottom) with {} chroma subsampling",
+                padding.left,
+                padding.top,
+                padding.right,
+                padding.bottom,
+                chroma_label(details.chroma_sampling)
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// Returns `details` with the width and height reduced by `crop`.
+///
+/// Crop amounts must be multiples of the chroma subsampling, so that chroma planes are
+/// cropped by whole samples.
+pub(crate) fn cropped_details(
+    details: &VideoDetails,
+    crop: Crop,
+) -> Result<VideoDetails, DecoderError> {
+    let (ss_x, ss_y) = details
+        .chroma_sampling
+        .subsample_ratio()
+        .map_or((1, 1), |(x, y)| (x.get() as usize, y.get() as usize));
+    if crop.left % ss_x != 0
+        || crop.right % ss_x != 0
+        || crop.top % ss_y != 0
+        || crop.bottom % ss_y != 0
+    {
+        return Err(DecoderError::UnsupportedFormat {
+            fmt: format!(
+                "crop of {}/{}/{}/{} (left/top/right/bottom) with {} chroma subsampling",
+                crop.left,
+                crop.top,
+                crop.right,
+                crop.bottom,
+                chroma_label(details.chroma_sampling)
+            ),
+        });
+    }
+    if crop.left + crop.right >= details.width || crop.top + crop.bottom >= details.height {
+        return Err(DecoderError::GenericDecodeError {
+            cause: format!(
+                "Crop is larger than the {}x{} frame",
+                details.width, details.height
+            ),
+            source: None,
+        });
+    }
+
+    Ok(VideoDetails {
+        width: details.width - crop.left - crop.right,
+        height: details.height - crop.top - crop.bottom,
+        ..*details
+    })
+}
+
+/// Copies the region of `frame` left after `crop` into a new frame sized for `cropped`.
+pub(crate) fn crop_frame<T: Pixel>(
+    frame: &Frame<T>,
+    cropped: &VideoDetails,
+    crop: Crop,
+    config: DecoderConfig,
+) -> Result<Frame<T>, DecoderError> {
+    let mut output: Frame<T> = new_padded_frame(cropped, config)?;
+    crop_frame_into(frame, crop, cropped.chroma_sampling, &mut output);
+    Ok(output)
+}
+
+/// Copies the region of `frame` left after `crop` into `output`, which must already have
+/// the cropped dimensions.
+pub(crate) fn crop_frame_into<T: Pixel>(
+    frame: &Frame<T>,
+    crop: Crop,
+    chroma_sampling: ChromaSubsampling,
+    output: &mut Frame<T>,
+) {
+    crop_plane(&frame.y_plane, &mut output.y_plane, crop.left, crop.top);
+    if let Some((ss_x, ss_y)) = chroma_sampling.subsample_ratio() {
+        let (x, y) = (
+            crop.left / ss_x.get() as usize,
+            crop.top / ss_y.get() as usize,
+        );
+        if let (Some(src), Some(dst)) = (frame.u_plane.as_ref(), output.u_plane.as_mut()) {
+            crop_plane(src, dst, x, y);
+        }
+        if let (Some(src), Some(dst)) = (frame.v_plane.as_ref(), output.v_plane.as_mut()) {
+            crop_plane(src, dst, x, y);
+        }
+    }
+}
+
+/// Checks that the planes of `frame` have the dimensions `details` calls for.
+///
+/// Missing chroma planes are allowed, so luma-only frames pass.
+pub(crate) fn check_frame_geometry<T: Pixel>(
+    frame: &Frame<T>,
+    details: &VideoDetails,
+) -> Result<(), DecoderError> {
+    let planes = [
+        Some(&frame.y_plane),
+        frame.u_plane.as_ref(),
+        frame.v_plane.as_ref(),
+    ];
+    for (index, plane) in planes.into_iter().enumerate() {
+        let Some(plane) = plane else {
+            continue;
+        };
+        let expected = details.plane_dimensions(index);
+        let actual = (
+            plane.rows().next().map_or(0, <[T]>::len),
+            plane.rows().count(),
+        );
+        if actual != expected {
+            return Err(DecoderError::GenericDecodeError {
+                cause: format!(
+                    "plane {index} is {}x{}, expected {}x{}",
+                    actual.0, actual.1, expected.0, expected.1
+                ),
+                source: None,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Checks that `T` is wide enough, and no wider than needed, for the video's samples.
+pub(crate) fn check_pixel_type<T: Pixel>(details: &VideoDetails) -> Result<(), DecoderError> {
+    let expected = details.bytes_per_sample();
+    if size_of::<T>() == expected {
+        Ok(())
+    } else {
+        Err(DecoderError::PixelTypeMismatch {
+            expected,
+            got: size_of::<T>(),
         })
+    }
+}
+
+/// Checks that a backend stores `bit_depth`-bit samples in as many bytes as
+/// [`VideoDetails::bytes_per_sample`] assumes, i.e. one byte up to 8 bits and two above.
+///
+/// A nominal depth that disagrees with the storage, such as 10-bit data in 8-bit samples
+/// or 8-bit data padded to 16 bits, would otherwise be read as garbage.
+#[cfg_attr(
+    not(any(feature = "vapoursynth", feature = "ffmpeg")),
+    expect(dead_code)
+)]
+pub(crate) fn check_sample_storage(
+    bit_depth: usize,
+    bytes_per_sample: usize,
+) -> Result<(), DecoderError> {
+    let expected = match bit_depth {
+        1..=8 => 1,
+        9..=16 => 2,
+        _ => 0,
+    };
+    if bytes_per_sample == expected {
+        Ok(())
+    } else {
+        Err(DecoderError::UnsupportedFormat {
+            fmt: format!("{bit_depth}-bit samples stored in {bytes_per_sample} bytes"),
+        })
+    }
+}
+
+/// Copies `dst`'s size worth of samples from `src`, starting at column `x` and row `y`.
+fn crop_plane<T: Pixel>(src: &Plane<T>, dst: &mut Plane<T>, x: usize, y: usize) {
+    for (dst_row, src_row) in dst.rows_mut().zip(src.rows().skip(y)) {
+        let width = dst_row.len();
+        dst_row.copy_from_slice(&src_row[x..x + width]);
+    }
+}
+
+/// Returns `details` as seen after rotating the video clockwise by `rotation` degrees.
+///
+/// Quarter turns swap the dimensions and invert the sample aspect ratio. They are not
+/// supported for 4:2:2, whose chroma would end up subsampled vertically instead.
+pub(crate) fn rotated_details(
+    details: &VideoDetails,
+    rotation: i32,
+) -> Result<VideoDetails, DecoderError> {
+    match rotation {
+        0 | 180 => Ok(VideoDetails {
+            rotation: 0,
+            ..*details
+        }),
+        90 | 270 if details.chroma_sampling != ChromaSubsampling::Yuv422 => Ok(VideoDetails {
+            width: details.height,
+            height: details.width,
+            sample_aspect_ratio: details.sample_aspect_ratio.recip(),
+            rotation: 0,
+            ..*details
+        }),
+        _ => Err(DecoderError::UnsupportedFormat {
+            fmt: format!(
+                "{rotation} degree rotation of {} video",
+                chroma_label(details.chroma_sampling)
+            ),
+        }),
+    }
+}
+
+/// Rotates `frame` clockwise by `rotation` degrees into a new frame sized for `rotated`.
+pub(crate) fn rotate_frame<T: Pixel>(
+    frame: &Frame<T>,
+    rotated: &VideoDetails,
+    rotation: i32,
+    config: DecoderConfig,
+) -> Result<Frame<T>, DecoderError> {
+    let mut output: Frame<T> = new_padded_frame(rotated, config)?;
+    rotate_plane(&frame.y_plane, &mut output.y_plane, rotation);
+    for (src, dst) in [
+        (frame.u_plane.as_ref(), output.u_plane.as_mut()),
+        (frame.v_plane.as_ref(), output.v_plane.as_mut()),
+    ] {
+        if let (Some(src), Some(dst)) = (src, dst) {
+            rotate_plane(src, dst, rotation);
+        }
+    }
+    Ok(output)
+}
+
+/// Fills `dst` with `src` rotated clockwise by `rotation` degrees.
+fn rotate_plane<T: Pixel>(src: &Plane<T>, dst: &mut Plane<T>, rotation: i32) {
+    let rows: Vec<&[T]> = src.rows().collect();
+    let (width, height) = (rows.first().map_or(0, |row| row.len()), rows.len());
+    for (y, dst_row) in dst.rows_mut().enumerate() {
+        for (x, sample) in dst_row.iter_mut().enumerate() {
+            *sample = match rotation {
+                90 => rows[height - 1 - x][y],
+                180 => rows[height - 1 - y][width - 1 - x],
+                270 => rows[x][width - 1 - y],
+                _ => rows[y][x],
+            };
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn rotates_subsampled_frames() {
+        let details = VideoDetails {
+            width: 4,
+            height: 2,
+            ..VideoDetails::default()
+        };
+        let rotated = rotated_details(&details, 90).expect("4:2:0 can be rotated");
+        assert_eq!((rotated.width, rotated.height), (2, 4));
+        let mut frame: Frame<u8> =
+            new_padded_frame(&details, DecoderConfig::default()).expect("source frame");
+        frame
+            .y_plane
+            .copy_from_u8_slice(&[1, 2, 3, 4, 5, 6, 7, 8])
+            .expect("luma");
+        if let Some(u_plane) = frame.u_plane.as_mut() {
+            u_plane.copy_from_u8_slice(&[1, 2]).expect("chroma");
+        }
+
+        let frame =
+            rotate_frame(&frame, &rotated, 90, DecoderConfig::default()).expect("rotated frame");
+        let luma: Vec<_> = frame.y_plane.rows().map(<[u8]>::to_vec).collect();
+        assert_eq!(luma, [[5, 1], [6, 2], [7, 3], [8, 4]]);
+        let u_plane = frame.u_plane.as_ref().expect("4:2:0 has chroma");
+        let chroma: Vec<_> = u_plane.rows().map(<[u8]>::to_vec).collect();
+        assert_eq!(chroma, [[1], [2]]);
+
+        let details = VideoDetails {
+            chroma_sampling: ChromaSubsampling::Yuv422,
+            ..details
+        };
+        assert!(rotated_details(&details, 270).is_err());
+        assert!(rotated_details(&details, 180).is_ok());
+    }
+
     #[test]
     fn uses_monochrome_chroma_when_luma_only() {
-        let frame = match new_padded_frame::<u8>(&VideoDetails::default(), true) {
+        let frame = match new_padded_frame::<u8>(
+            &VideoDetails::default(),
+            DecoderConfig {
+                luma_only: true,
+                ..DecoderConfig::default()
+            },
+        ) {
             Ok(frame) => frame,
             Err(err) => panic!("valid default details should build: {err}"),
         };
@@ -48,6 +351,50 @@ mod tests {
         assert!(frame.v_plane.is_none());
     }
 
+    #[test]
+    fn checks_pixel_type_against_bit_depth() {
+        let mut details = VideoDetails::default();
+        assert!(check_pixel_type::<u8>(&details).is_ok());
+        assert!(matches!(
+            check_pixel_type::<u16>(&details),
+            Err(DecoderError::PixelTypeMismatch {
+                expected: 1,
+                got: 2
+            })
+        ));
+        details.bit_depth = 10;
+        assert!(check_pixel_type::<u16>(&details).is_ok());
+        assert!(matches!(
+            check_pixel_type::<u8>(&details),
+            Err(DecoderError::PixelTypeMismatch {
+                expected: 2,
+                got: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn rejects_sample_storage_mismatches() {
+        assert!(check_sample_storage(8, 1).is_ok());
+        assert!(check_sample_storage(10, 2).is_ok());
+        assert!(check_sample_storage(16, 2).is_ok());
+        // 8-bit data padded to 16-bit samples
+        assert!(matches!(
+            check_sample_storage(8, 2),
+            Err(DecoderError::UnsupportedFormat { fmt }) if fmt == "8-bit samples stored in 2 bytes"
+        ));
+        // 10-bit data squeezed into 8-bit samples
+        assert!(matches!(
+            check_sample_storage(10, 1),
+            Err(DecoderError::UnsupportedFormat { .. })
+        ));
+        // 32-bit float samples
+        assert!(matches!(
+            check_sample_storage(32, 4),
+            Err(DecoderError::UnsupportedFormat { .. })
+        ));
+    }
+
     #[test]
     fn rejects_zero_width() {
         let cfg = VideoDetails {
@@ -55,12 +402,62 @@ mod tests {
             ..VideoDetails::default()
         };
 
-        match new_padded_frame::<u8>(&cfg, false) {
-            Err(DecoderError::GenericDecodeError { cause }) => {
+        match new_padded_frame::<u8>(&cfg, DecoderConfig::default()) {
+            Err(DecoderError::GenericDecodeError { cause, .. }) => {
                 assert_eq!(cause, "Zero resolution is not supported");
             }
             Err(err) => panic!("unexpected error: {err}"),
             Ok(_) => panic!("zero width should fail"),
         }
     }
+
+    #[test]
+    fn crop_reduces_dimensions() {
+        let crop = Crop {
+            left: 0,
+            top: 60,
+            right: 0,
+            bottom: 60,
+        };
+
+        match cropped_details(&VideoDetails::default(), crop) {
+            Ok(details) => assert_eq!((details.width, details.height), (640, 360)),
+            Err(err) => panic!("aligned crop should succeed: {err}"),
+        }
+    }
+
+    #[test]
+    fn rejects_crop_misaligned_with_chroma() {
+        let crop = Crop {
+            left: 1,
+            top: 0,
+            right: 0,
+            bottom: 0,
+        };
+
+        assert!(matches!(
+            cropped_details(&VideoDetails::default(), crop),
+            Err(DecoderError::UnsupportedFormat { .. })
+        ));
+        let details = VideoDetails {
+            chroma_sampling: ChromaSubsampling::Yuv444,
+            ..VideoDetails::default()
+        };
+        assert!(cropped_details(&details, crop).is_ok());
+    }
+
+    #[test]
+    fn rejects_crop_covering_frame() {
+        let crop = Crop {
+            left: 320,
+            top: 0,
+            right: 320,
+            bottom: 0,
+        };
+
+        assert!(matches!(
+            cropped_details(&VideoDetails::default(), crop),
+            Err(DecoderError::GenericDecodeError { .. })
+        ));
+    }
 }
@@ -0,0 +1,168 @@
+use std::{
+    fs::File,
+    io::{BufReader, ErrorKind, Read, Seek},
+    path::Path,
+};
+
+use v_frame::{frame::Frame, pixel::Pixel};
+
+use crate::{DecoderError, VideoDetails};
+
+/// Decoder for headerless planar YUV streams, such as those written by `ffmpeg -f rawvideo`.
+///
+/// Raw files carry no metadata, so the layout is taken entirely from the caller-provided
+/// [`VideoDetails`]. Planes are expected to be tightly packed in Y, U, V order, with
+/// samples above 8 bits stored as little-endian `u16`.
+pub struct RawYuvDecoder {
+    /// The caller-provided layout, with `total_frames` derived from the file size
+    pub video_details: VideoDetails,
+    reader: BufReader<File>,
+    plane_sizes: [usize; 3],
+    buffer: Vec<u8>,
+}
+
+impl RawYuvDecoder {
+    /// Opens a raw YUV file using the given frame layout.
+    ///
+    /// `width`, `height`, `bit_depth`, and `chroma_sampling` from `details` determine the
+    /// size of each frame. `total_frames` is ignored and recomputed from the file size;
+    /// any trailing partial frame is not counted.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecoderError::Io`] if the file cannot be opened,
+    /// [`DecoderError::GenericDecodeError`] if `details` describes a zero-sized frame.
+    #[inline]
+    pub fn new<P: AsRef<Path>>(input: P, details: VideoDetails) -> Result<Self, DecoderError> {
+        let plane_sizes = plane_sizes(&details);
+        let frame_size = details.frame_size_bytes();
+        if frame_size == 0 {
+            return Err(DecoderError::GenericDecodeError {
+                cause: "Zero resolution is not supported".to_string(),
+                source: None,
+            });
+        }
+
+        let file = File::open(input)?;
+        let file_size = file.metadata()?.len();
+
+        Ok(Self {
+            video_details: VideoDetails {
+                total_frames: Some((file_size / frame_size as u64) as usize),
+                ..details
+            },
+            reader: BufReader::new(file),
+            plane_sizes,
+            buffer: vec![0; frame_size],
+        })
+    }
+
+    /// Seeks forward over `count` frames without reading them.
+    pub(crate) fn skip_frames(&mut self, count: usize) -> Result<(), DecoderError> {
+        let offset = count
+            .checked_mul(self.buffer.len())
+            .and_then(|bytes| i64::try_from(bytes).ok())
+            .ok_or_else(|| DecoderError::GenericDecodeError {
+                cause: format!("cannot skip {count} frames of {} bytes", self.buffer.len()),
+                source: None,
+            })?;
+        self.reader.seek_relative(offset)?;
+        Ok(())
+    }
+
+    pub(crate) fn read_video_frame<T: Pixel>(
+        &mut self,
+        frame: &mut Frame<T>,
+    ) -> Result<(), DecoderError> {
+        self.reader
+            .read_exact(&mut self.buffer)
+            .map_err(|e| match e.kind() {
+                ErrorKind::UnexpectedEof => DecoderError::EndOfFile,
+                _ => e.into(),
+            })?;
+
+        let (y_data, chroma_data) = self.buffer.split_at(self.plane_sizes[0]);
+        let (u_data, v_data) = chroma_data.split_at(self.plane_sizes[1]);
+        frame
+            .y_plane
+            .copy_from_u8_slice(y_data)
+            .map_err(DecoderError::generic)?;
+        if let Some(u_plane) = frame.u_plane.as_mut() {
+            u_plane
+                .copy_from_u8_slice(u_data)
+                .map_err(DecoderError::generic)?;
+        }
+        if let Some(v_plane) = frame.v_plane.as_mut() {
+            v_plane
+                .copy_from_u8_slice(v_data)
+                .map_err(DecoderError::generic)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Computes the size in bytes of the Y, U, and V planes of one frame.
+fn plane_sizes(details: &VideoDetails) -> [usize; 3] {
+    let bytes_per_sample = details.bytes_per_sample();
+    [0, 1, 2].map(|plane| {
+        let (width, height) = details.plane_dimensions(plane);
+        width * height * bytes_per_sample
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use v_frame::chroma::ChromaSubsampling;
+
+    use super::*;
+    use crate::test_util::temp_path;
+
+    #[test]
+    fn computes_subsampled_plane_sizes() {
+        assert_eq!(
+            plane_sizes(&VideoDetails::default()),
+            [640 * 480, 320 * 240, 320 * 240]
+        );
+
+        let details = VideoDetails {
+            width: 5,
+            height: 3,
+            bit_depth: 10,
+            ..VideoDetails::default()
+        };
+        assert_eq!(plane_sizes(&details), [5 * 3 * 2, 3 * 2 * 2, 3 * 2 * 2]);
+    }
+
+    #[test]
+    fn monochrome_has_no_chroma_planes() {
+        let details = VideoDetails {
+            chroma_sampling: ChromaSubsampling::Monochrome,
+            ..VideoDetails::default()
+        };
+
+        assert_eq!(plane_sizes(&details), [640 * 480, 0, 0]);
+    }
+
+    #[test]
+    fn rejects_skips_past_the_addressable_range() {
+        let details = VideoDetails {
+            width: 4,
+            height: 2,
+            ..VideoDetails::default()
+        };
+        let path = temp_path("skip-overflow.yuv");
+        std::fs::write(&path, [16; 2 * (4 * 2 + 2 * 2)]).expect("write test file");
+        let decoder = RawYuvDecoder::new(&path, details);
+        std::fs::remove_file(&path).expect("remove test file");
+        let mut decoder = decoder.expect("valid raw layout");
+
+        assert!(matches!(
+            decoder.skip_frames(usize::MAX),
+            Err(DecoderError::GenericDecodeError { .. })
+        ));
+        decoder.skip_frames(1).expect("skip first frame");
+        let mut frame = details.new_black_frame::<u8>().expect("8-bit frame");
+        decoder.read_video_frame(&mut frame).expect("second frame");
+    }
+}
@@ -1,20 +1,43 @@
-use crate::VideoDetails;
 use crate::error::DecoderError;
+use crate::util::{chromaticity, luminance, sample_aspect_ratio};
+use crate::{
+    ChromaLocation, ColorPrimaries, ColorRange, ContentLightLevel, DecoderConfig, FieldOrder,
+    FrameFlags, HdrMetadata, MasteringDisplay, MatrixCoefficients, Resizer,
+    TransferCharacteristics, VideoDetails,
+};
 use num_rational::Rational32;
-use std::{collections::HashMap, path::Path, slice};
-use v_frame::{chroma::ChromaSubsampling, frame::Frame, pixel::Pixel};
+use std::{
+    collections::HashMap,
+    marker::PhantomData,
+    mem::size_of,
+    ops::Range,
+    path::Path,
+    slice,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+    },
+    time::Duration,
+};
+use v_frame::{chroma::ChromaSubsampling, frame::Frame, pixel::Pixel, plane::Plane};
 use vapoursynth::{
     api::API,
     core::CoreRef,
-    map::OwnedMap,
+    format::PresetFormat,
+    frame::FrameRef,
+    map::{Map, OwnedMap, ValueType},
     node::Node,
     video_info::{Property, VideoInfo},
     vsscript::{Environment, Error as VsscriptError, EvalFlags},
 };
 
-use super::frame_builder::new_padded_frame;
+use super::frame_builder::{check_sample_storage, new_padded_frame};
 
 const DEFAULT_OUTPUT_INDEX: i32 = 0;
+/// Number of output indices searched for a video clip when a script's default output is
+/// not video, e.g. because it is an audio node.
+const OUTPUT_SEARCH_LIMIT: i32 = 16;
 
 /// Callback to modify the VapourSynth output node before frame decoding.
 ///
@@ -33,8 +56,22 @@ pub type Height = usize;
 pub type BitDepth = usize;
 // The name of the variable to set in the VapourSynth environment.
 pub type VariableName = String;
-// The value of the variable to set in the VapourSynth environment.
-pub type VariableValue = String;
+
+/// A typed value for a variable in the VapourSynth environment, set with
+/// [`VapoursynthDecoder::set_variable_typed`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum VariableValue {
+    /// An integer, which scripts receive as an `int`.
+    Int(i64),
+    /// A floating point number, which scripts receive as a `float`.
+    Float(f64),
+    /// Binary or string data, set like the values passed to
+    /// [`VapoursynthDecoder::set_variables`].
+    Data(Vec<u8>),
+    /// A boolean, which scripts receive as the `int` 0 or 1, since VapourSynth has no
+    /// boolean type.
+    Bool(bool),
+}
 
 fn map_vsscript_error(error: &VsscriptError) -> DecoderError {
     match error {
@@ -43,9 +80,11 @@ fn map_vsscript_error(error: &VsscriptError) -> DecoderError {
         | VsscriptError::FileRead(_)
         | VsscriptError::PathInvalidUnicode => DecoderError::FileReadError {
             cause: error.to_string(),
+            source: None,
         },
         VsscriptError::VSScript(vsscript_error) => DecoderError::FileReadError {
             cause: vsscript_error.to_string(),
+            source: None,
         },
         VsscriptError::NoSuchVariable
         | VsscriptError::NoCore
@@ -62,16 +101,32 @@ pub struct VapoursynthDecoder {
     #[allow(missing_docs)]
     pub env: Environment,
     #[allow(missing_docs)]
-    modify_node: Option<ModifyNode>,
+    modify_nodes: Vec<ModifyNode>,
     video_details: Option<VideoDetails>,
     output_index: i32,
+    prefetcher: Prefetcher,
+    frame_timeout: Option<Duration>,
+    cancel_flag: Option<Arc<AtomicBool>>,
+    last_flags: FrameFlags,
+}
+
+impl Drop for VapoursynthDecoder {
+    #[inline]
+    fn drop(&mut self) {
+        // Outstanding requests must not outlive the core they were made on
+        self.prefetcher.drain();
+    }
 }
 
 impl VapoursynthDecoder {
     /// Creates a new decoder with an empty VapourSynth environment.
     ///
-    /// A valid output node must be registered via [`register_node_modifier`](Self::register_node_modifier)
-    /// before decoding frames.
+    /// The environment has no output, so the clip to decode must be created by a modifier
+    /// registered with [`register_node_modifier`](Self::register_node_modifier), which
+    /// receives `None` as its input node. This suits synthetic sources such as
+    /// `std.BlankClip`, or loading a file with one of the [`source`](crate::source)
+    /// helpers. Until a modifier is registered, reading video details or frames fails with
+    /// [`DecoderError::NoVideoStream`].
     ///
     /// # Errors
     ///
@@ -82,88 +137,171 @@ impl VapoursynthDecoder {
         let env = Environment::new().map_err(|e| map_vsscript_error(&e))?;
         Ok(Self {
             env,
-            modify_node: None,
+            modify_nodes: Vec::new(),
             video_details: None,
             output_index: DEFAULT_OUTPUT_INDEX,
+            prefetcher: Prefetcher::new(),
+            frame_timeout: None,
+            cancel_flag: None,
+            last_flags: FrameFlags::default(),
         })
     }
 
     /// Creates a decoder from a VapourSynth script file (`.vpy`).
     ///
     /// The working directory is set to the directory containing the script.
-    /// Pass `HashMap::new()` for `variables` if none are needed; `output_index` defaults to 0.
+    /// Pass `HashMap::new()` for `variables` if none are needed. If `output_index` is
+    /// `None`, output 0 is used when it is a video clip, otherwise the first video output,
+    /// so that scripts setting an audio output first still decode.
     ///
     /// # Errors
     ///
-    /// Returns [`DecoderError`] if the file cannot be read, the script fails to execute,
-    /// or the output has variable format/resolution/framerate.
+    /// Returns [`DecoderError::NoVideoStream`] if the script has no video output (or none at
+    /// `output_index`), otherwise [`DecoderError`] if the file cannot be read, the script
+    /// fails to execute, or the output has variable format/resolution/framerate.
     #[inline]
     pub fn from_file<P: AsRef<Path>>(
         input: P,
-        variables: HashMap<VariableName, VariableValue>,
+        variables: HashMap<VariableName, String>,
         output_index: Option<u8>,
     ) -> Result<VapoursynthDecoder, DecoderError> {
-        let mut decoder = Self::new()?;
-        decoder.set_variables(variables)?;
-        if let Some(index) = output_index {
-            decoder.output_index = index as i32;
-        }
-        decoder
-            .get_env()
-            .eval_file(input, EvalFlags::SetWorkingDir)
-            .map_err(|e| map_vsscript_error(&e))?;
-        Ok(decoder)
+        Self::evaluate(variables, output_index, |env| {
+            env.eval_file(input, EvalFlags::SetWorkingDir)
+        })
+    }
+
+    /// Creates a decoder from a VapourSynth script file (`.vpy`), with `variables` set as
+    /// script globals before it is evaluated.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`from_file`](Self::from_file).
+    #[inline]
+    pub fn from_file_with_variables<P: AsRef<Path>>(
+        input: P,
+        variables: HashMap<VariableName, String>,
+    ) -> Result<VapoursynthDecoder, DecoderError> {
+        Self::from_file(input, variables, None)
     }
 
     /// Creates a decoder from a VapourSynth script string.
     ///
     /// The script must call `clip.set_output()`. Pass `HashMap::new()` for `variables`
-    /// if none are needed; `output_index` defaults to 0.
+    /// if none are needed. `output_index` is chosen as in [`from_file`](Self::from_file).
     ///
     /// # Errors
     ///
-    /// Returns [`DecoderError`] if the script fails to execute, or the output has
-    /// variable format/resolution/framerate.
+    /// Returns [`DecoderError::NoVideoStream`] if the script has no video output (or none at
+    /// `output_index`), otherwise [`DecoderError`] if the script fails to execute, or the
+    /// output has variable format/resolution/framerate.
     #[inline]
     pub fn from_script(
         script: &str,
-        variables: HashMap<VariableName, VariableValue>,
+        variables: HashMap<VariableName, String>,
+        output_index: Option<u8>,
+    ) -> Result<VapoursynthDecoder, DecoderError> {
+        Self::evaluate(variables, output_index, |env| env.eval_script(script))
+    }
+
+    /// Creates a decoder from a VapourSynth script string, with `variables` set as script
+    /// globals before it is evaluated.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`from_script`](Self::from_script).
+    #[inline]
+    pub fn from_script_with_variables(
+        script: &str,
+        variables: HashMap<VariableName, String>,
+    ) -> Result<VapoursynthDecoder, DecoderError> {
+        Self::from_script(script, variables, None)
+    }
+
+    /// Creates an environment, sets `variables` on it, and only then runs `eval`, so that
+    /// the script sees the variables as globals.
+    fn evaluate(
+        variables: HashMap<VariableName, String>,
         output_index: Option<u8>,
+        eval: impl FnOnce(&mut Environment) -> Result<(), VsscriptError>,
     ) -> Result<VapoursynthDecoder, DecoderError> {
         let mut decoder = Self::new()?;
         decoder.set_variables(variables)?;
-        if let Some(index) = output_index {
-            decoder.output_index = index as i32;
+        eval(decoder.get_env()).map_err(|e| map_vsscript_error(&e))?;
+        decoder.output_index = match output_index {
+            Some(index) => Some(i32::from(index)).filter(|&index| decoder.has_video_output(index)),
+            None => (DEFAULT_OUTPUT_INDEX..OUTPUT_SEARCH_LIMIT)
+                .find(|&index| decoder.has_video_output(index)),
         }
-        decoder
-            .get_env()
-            .eval_script(script)
-            .map_err(|e| map_vsscript_error(&e))?;
+        .ok_or(DecoderError::NoVideoStream)?;
         Ok(decoder)
     }
 
+    /// Returns `true` if the script set a video clip as output `index`.
+    ///
+    /// Audio nodes are not returned as outputs by the VSScript API, so an audio output is
+    /// indistinguishable from a missing one here.
+    fn has_video_output(&self, index: i32) -> bool {
+        self.env.get_output(index).is_ok()
+    }
+
     /// Sets variables in the VapourSynth environment, accessible from scripts via `vs.get_output()`.
     ///
+    /// Variables only reach scripts evaluated after this call. To pass variables to a
+    /// script, use [`from_script_with_variables`](Self::from_script_with_variables) or
+    /// [`from_file_with_variables`](Self::from_file_with_variables) instead of setting them
+    /// on an already evaluated decoder.
+    ///
     /// # Errors
     ///
     /// Returns [`DecoderError::VapoursynthArgsError`] if a variable cannot be set.
     #[inline]
     pub fn set_variables(
         &mut self,
-        variables: HashMap<VariableName, VariableValue>,
+        variables: HashMap<VariableName, String>,
+    ) -> Result<(), DecoderError> {
+        self.set_variable_map(|map| {
+            for (name, value) in variables {
+                map.set_data(name.as_str(), value.as_bytes())?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Sets a variable with a typed value in the VapourSynth environment, so that scripts
+    /// receive e.g. an `int` instead of parsing a string.
+    ///
+    /// Like [`set_variables`](Self::set_variables), this only affects scripts evaluated
+    /// after this call.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecoderError::VapoursynthArgsError`] if the variable cannot be set.
+    #[inline]
+    pub fn set_variable_typed(
+        &mut self,
+        name: &str,
+        value: VariableValue,
+    ) -> Result<(), DecoderError> {
+        self.set_variable_map(|map| match value {
+            VariableValue::Int(value) => map.set_int(name, value),
+            VariableValue::Float(value) => map.set_float(name, value),
+            VariableValue::Data(value) => map.set_data(name, &value),
+            VariableValue::Bool(value) => map.set_int(name, i64::from(value)),
+        })
+    }
+
+    /// Builds a map with `fill` and sets its entries as variables in the environment.
+    fn set_variable_map(
+        &mut self,
+        fill: impl FnOnce(&mut OwnedMap<'_>) -> Result<(), vapoursynth::map::Error>,
     ) -> Result<(), DecoderError> {
         let api = API::get().ok_or_else(|| DecoderError::VapoursynthInternalError {
             cause: "failed to get Vapoursynth API instance".to_string(),
         })?;
         let mut variables_map = OwnedMap::new(api);
-
-        for (name, value) in variables {
-            variables_map
-                .set_data(name.as_str(), value.as_bytes())
-                .map_err(|e| DecoderError::VapoursynthArgsError {
-                    cause: e.to_string(),
-                })?;
-        }
+        fill(&mut variables_map).map_err(|e| DecoderError::VapoursynthArgsError {
+            cause: e.to_string(),
+        })?;
 
         self.env
             .set_variables(&variables_map)
@@ -172,23 +310,107 @@ impl VapoursynthDecoder {
             })
     }
 
-    pub(crate) fn get_video_details(&self) -> Result<VideoDetails, DecoderError> {
+    /// Returns the video details, parsing them from the output node and the first frame's
+    /// properties on the first call and caching them until the output changes.
+    pub(crate) fn get_video_details(&mut self) -> Result<VideoDetails, DecoderError> {
+        if let Some(details) = self.video_details {
+            return Ok(details);
+        }
+        let details = parse_video_details(&self.get_output_node()?)?;
+        self.video_details = Some(details);
+        Ok(details)
+    }
+
+    /// Reads the video details from the clip's `VideoInfo` alone, without rendering the
+    /// first frame. Frame properties are not consulted, so chroma siting, sample aspect
+    /// ratio, and HDR metadata are left at their defaults.
+    pub(crate) fn probe_video_details(&self) -> Result<VideoDetails, DecoderError> {
         match self.video_details {
             Some(details) => Ok(details),
-            None => {
-                let node = self.get_output_node();
-                let details = parse_video_details(node.info())?;
-                Ok(details)
-            }
+            None => parse_clip_details(&self.get_output_node()?, None),
         }
     }
 
     pub(crate) fn read_video_frame<T: Pixel>(
+        &mut self,
+        frame_index: usize,
+        frame: &mut Frame<T>,
+    ) -> Result<(), DecoderError> {
+        self.last_flags = self.read_video_frame_inspect(frame_index, frame, |vs_frame| {
+            read_frame_flags(vs_frame, frame_index)
+        })?;
+        Ok(())
+    }
+
+    pub(crate) fn read_video_frame_with_props<T: Pixel>(
         &mut self,
         cfg: &VideoDetails,
         frame_index: usize,
-        luma_only: bool,
-    ) -> Result<Frame<T>, DecoderError> {
+        config: DecoderConfig,
+    ) -> Result<(Frame<T>, HashMap<String, PropValue>), DecoderError> {
+        let mut frame: Frame<T> = new_padded_frame(cfg, config)?;
+        let (props, flags) =
+            self.read_video_frame_inspect(frame_index, &mut frame, |vs_frame| {
+                (
+                    read_frame_props(vs_frame),
+                    read_frame_flags(vs_frame, frame_index),
+                )
+            })?;
+        self.last_flags = flags;
+        Ok((frame, props))
+    }
+
+    /// Reads a frame along with the alpha clip frame attached to it as the `_Alpha`
+    /// property, copying it into `alpha`.
+    ///
+    /// Returns `None` if the frame has no alpha, otherwise whether it is premultiplied.
+    pub(crate) fn read_video_frame_with_alpha<T: Pixel>(
+        &mut self,
+        frame_index: usize,
+        frame: &mut Frame<T>,
+        alpha: &mut Plane<T>,
+    ) -> Result<Option<bool>, DecoderError> {
+        let (flags, premultiplied) =
+            self.read_video_frame_inspect(frame_index, frame, |vs_frame| {
+                (
+                    read_frame_flags(vs_frame, frame_index),
+                    read_alpha(vs_frame, alpha),
+                )
+            })?;
+        self.last_flags = flags;
+        premultiplied
+    }
+
+    /// Requests the `count` frames starting at `frame_index` at once, so that VapourSynth
+    /// filters them in parallel while they are read one by one.
+    pub(crate) fn request_frames(
+        &mut self,
+        frame_index: usize,
+        count: usize,
+    ) -> Result<(), DecoderError> {
+        let node = output_node(&self.env, self.output_index, &self.modify_nodes)?;
+        let total_frames = self.video_details.and_then(|details| details.total_frames);
+        let end = frame_index
+            .saturating_add(count)
+            .min(total_frames.unwrap_or(usize::MAX));
+        self.prefetcher
+            .request(&node, frame_index..end.max(frame_index));
+        Ok(())
+    }
+
+    /// Returns the flags of the most recently read frame.
+    pub(crate) const fn last_frame_flags(&self) -> FrameFlags {
+        self.last_flags
+    }
+
+    /// Reads a frame, also passing the source VapourSynth frame to `inspect` before
+    /// it is released.
+    fn read_video_frame_inspect<T: Pixel, R>(
+        &mut self,
+        frame_index: usize,
+        frame: &mut Frame<T>,
+        inspect: impl FnOnce(&FrameRef<'_>) -> R,
+    ) -> Result<R, DecoderError> {
         if self.video_details.is_some_and(|details| {
             details
                 .total_frames
@@ -197,49 +419,45 @@ impl VapoursynthDecoder {
             return Err(DecoderError::EndOfFile);
         }
 
-        let node = {
-            let output_node = match self.env.get_output(self.output_index) {
-                Ok(output) => {
-                    let (output_node, _) = output;
-                    Some(output_node)
-                }
-                Err(vapoursynth::vsscript::Error::NoOutput) => {
-                    if self.modify_node.is_some() {
-                        None
-                    } else {
-                        panic!("output node exists--validated during initialization");
-                    }
-                }
-                Err(_) => panic!("unexpected error when getting output node"),
-            };
-            if let Some(modify_node) = self.modify_node.as_ref() {
-                let core =
-                    self.env
-                        .get_core()
-                        .map_err(|e| DecoderError::VapoursynthInternalError {
-                            cause: e.to_string(),
-                        })?;
-                modify_node(core, output_node).map_err(|e| {
-                    DecoderError::VapoursynthInternalError {
-                        cause: e.to_string(),
-                    }
-                })?
-            } else {
-                output_node.expect("output node exists--validated during initialization")
-            }
-        };
+        let node = output_node(&self.env, self.output_index, &self.modify_nodes)?;
 
         // Lazy load the total frame count
         if self.video_details.is_none() {
-            let video_details = parse_video_details(node.info())?;
+            let video_details = parse_video_details(&node)?;
             self.video_details = Some(video_details);
         }
 
-        let vs_frame = node
-            .get_frame(frame_index)
-            .map_err(|_| DecoderError::EndOfFile)?;
+        if self
+            .cancel_flag
+            .as_ref()
+            .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+        {
+            return Err(DecoderError::GenericDecodeError {
+                cause: "frame request cancelled".to_string(),
+                source: None,
+            });
+        }
+        let vs_frame = match self.frame_timeout {
+            Some(timeout) => self
+                .prefetcher
+                .wait_for(&node, frame_index, timeout)
+                .ok_or_else(|| DecoderError::GenericDecodeError {
+                    cause: "frame request timed out".to_string(),
+                    source: None,
+                })?,
+            None => node.get_frame(frame_index).ok(),
+        }
+        .ok_or(DecoderError::EndOfFile)?;
+        self.prefetcher.request_after(
+            &node,
+            frame_index,
+            self.video_details.and_then(|details| details.total_frames),
+        );
 
-        let mut frame: Frame<T> = new_padded_frame(cfg, luma_only)?;
+        // Gray clips have a single plane, so there is no chroma to read even if the
+        // caller's frame has chroma planes
+        let has_chroma = vs_frame.format().plane_count() > 1;
+        check_plane_sizes(&vs_frame, frame, has_chroma)?;
 
         frame
             .y_plane
@@ -253,10 +471,8 @@ impl VapoursynthDecoder {
                 },
                 vs_frame.stride(0),
             )
-            .map_err(|e| DecoderError::GenericDecodeError {
-                cause: e.to_string(),
-            })?;
-        if let Some(u_plane) = frame.u_plane.as_mut() {
+            .map_err(DecoderError::generic)?;
+        if has_chroma && let Some(u_plane) = frame.u_plane.as_mut() {
             u_plane
                 .copy_from_u8_slice_with_stride(
                     // SAFETY: we assume that the values provided by VapourSynth are correct
@@ -268,11 +484,9 @@ impl VapoursynthDecoder {
                     },
                     vs_frame.stride(1),
                 )
-                .map_err(|e| DecoderError::GenericDecodeError {
-                    cause: e.to_string(),
-                })?;
+                .map_err(DecoderError::generic)?;
         }
-        if let Some(v_plane) = frame.v_plane.as_mut() {
+        if has_chroma && let Some(v_plane) = frame.v_plane.as_mut() {
             v_plane
                 .copy_from_u8_slice_with_stride(
                     // SAFETY: we assume that the values provided by VapourSynth are correct
@@ -284,12 +498,150 @@ impl VapoursynthDecoder {
                     },
                     vs_frame.stride(2),
                 )
-                .map_err(|e| DecoderError::GenericDecodeError {
+                .map_err(DecoderError::generic)?;
+        }
+
+        Ok(inspect(&vs_frame))
+    }
+
+    /// Selects which script output node is decoded, for scripts that call
+    /// `set_output` with several indices (e.g. a reference and a distorted clip).
+    ///
+    /// Registered node modifiers are kept and applied to the new output.
+    /// Video details are re-derived from the new output on next use. Use this to pick a
+    /// specific clip when a script sets several video outputs; audio outputs cannot be
+    /// selected.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecoderError::NoVideoStream`] if the script has no video output at `index`,
+    /// [`DecoderError::VapoursynthInternalError`] if the output cannot be queried.
+    #[inline]
+    pub fn set_output_index(&mut self, index: i32) -> Result<(), DecoderError> {
+        match self.env.get_output(index) {
+            Ok(_) => {}
+            Err(vapoursynth::vsscript::Error::NoOutput) => return Err(DecoderError::NoVideoStream),
+            Err(e) => {
+                return Err(DecoderError::VapoursynthInternalError {
                     cause: e.to_string(),
-                })?;
+                });
+            }
         }
 
-        Ok(frame)
+        self.prefetcher.drain();
+        self.output_index = index;
+        self.video_details = None;
+        Ok(())
+    }
+
+    /// Returns the version of the VapourSynth core and the plugins it has loaded, e.g. to
+    /// diagnose a missing plugin or to include in a bug report.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecoderError::VapoursynthInternalError`] if the core cannot be accessed.
+    #[inline]
+    pub fn core_info(&self) -> Result<CoreInfo, DecoderError> {
+        let core = self
+            .env
+            .get_core()
+            .map_err(|e| DecoderError::VapoursynthInternalError {
+                cause: e.to_string(),
+            })?;
+        let info = core.info();
+        let plugins = core.plugins();
+        let mut plugins: Vec<PluginInfo> = plugins
+            .keys()
+            .filter_map(|key| plugins.get_data(key).ok())
+            .map(PluginInfo::parse)
+            .collect();
+        plugins.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(CoreInfo {
+            version: info.version_string.to_string(),
+            core_version: info.core_version,
+            api_version: info.api_version,
+            plugins,
+        })
+    }
+
+    /// Sets how many frame requests are kept in flight while reading sequentially.
+    ///
+    /// VapourSynth filters frames in parallel, so requesting several frames ahead of the
+    /// current one can multiply throughput on multi-core systems, at the cost of holding
+    /// up to `depth` decoded frames in VapourSynth's cache. The default of `1` requests
+    /// only the frame being read. Values below `1` are treated as `1`.
+    #[inline]
+    pub fn set_prefetch(&mut self, depth: usize) {
+        self.prefetcher.depth = depth.max(1);
+    }
+
+    /// Limits how long a frame read waits for the script to produce a frame.
+    ///
+    /// A read that takes longer fails with [`DecoderError::GenericDecodeError`]. VapourSynth
+    /// cannot abort a request once made, so the filter keeps running in the background,
+    /// and dropping the decoder still waits for it to finish. `None`, the default, waits
+    /// indefinitely.
+    #[inline]
+    pub fn set_frame_timeout(&mut self, timeout: Option<Duration>) {
+        self.frame_timeout = timeout;
+    }
+
+    /// Sets a flag that aborts frame reads once it is set to `true`.
+    ///
+    /// The flag is checked before each frame request, so a request already waiting on the
+    /// script is not interrupted; combine it with
+    /// [`set_frame_timeout`](Self::set_frame_timeout) to bound that wait.
+    #[inline]
+    pub fn set_cancel_flag(&mut self, cancel: Arc<AtomicBool>) {
+        self.cancel_flag = Some(cancel);
+    }
+
+    /// Fetches a frame by index without copying its pixel data.
+    ///
+    /// The returned [`BorrowedFrame`] keeps the underlying VapourSynth frame alive and
+    /// borrows the decoder, so it must be dropped before the decoder is used mutably again.
+    /// `T` must match the sample size of the output node (`u8` for 8-bit, `u16` otherwise).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecoderError::EndOfFile`] if the frame cannot be fetched,
+    /// [`DecoderError::GenericDecodeError`] if `T` does not match the sample size.
+    #[inline]
+    pub fn read_video_frame_borrowed<T: Pixel>(
+        &self,
+        frame_index: usize,
+    ) -> Result<BorrowedFrame<'_, T>, DecoderError> {
+        let video_details = match self.video_details {
+            Some(details) => details,
+            None => parse_video_details(&self.get_output_node()?)?,
+        };
+        if video_details
+            .total_frames
+            .is_some_and(|total_frames| frame_index >= total_frames)
+        {
+            return Err(DecoderError::EndOfFile);
+        }
+
+        let vs_frame = self
+            .get_output_node()?
+            .get_frame(frame_index)
+            .map_err(|_| DecoderError::EndOfFile)?;
+        let bytes_per_sample = vs_frame.format().bytes_per_sample() as usize;
+        if bytes_per_sample != size_of::<T>() {
+            return Err(DecoderError::GenericDecodeError {
+                cause: format!(
+                    "requested {}-byte pixels from a clip with {bytes_per_sample}-byte samples",
+                    size_of::<T>()
+                ),
+                source: None,
+            });
+        }
+
+        Ok(BorrowedFrame {
+            vs_frame,
+            video_details,
+            _pixel: PhantomData,
+        })
     }
 
     /// Returns a mutable reference to the VapourSynth environment.
@@ -297,32 +649,14 @@ impl VapoursynthDecoder {
         &mut self.env
     }
 
-    /// Returns the VapourSynth output node, applying the registered modifier if any.
-    pub(crate) fn get_output_node(&self) -> Node<'_> {
-        let output_node = match self.env.get_output(self.output_index) {
-            Ok(output) => {
-                let (output_node, _) = output;
-                Some(output_node)
-            }
-            Err(vapoursynth::vsscript::Error::NoOutput) => {
-                if self.modify_node.is_some() {
-                    None
-                } else {
-                    panic!("output node does not exist");
-                }
-            }
-            Err(_) => panic!("unexpected error when getting output node"),
-        };
-        if let Some(modify_node) = self.modify_node.as_ref() {
-            let core = self
-                .env
-                .get_core()
-                .expect("core exists--validated during initialization");
-            modify_node(core, output_node)
-                .expect("modified node exists--validated during registration")
-        } else {
-            output_node.expect("output node exists--validated during initialization")
-        }
+    /// Returns the VapourSynth output node, applying the registered modifiers if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecoderError::NoVideoStream`] if the environment has no output and no
+    /// modifier produces one, e.g. for [`new`](Self::new) before a modifier is registered.
+    pub(crate) fn get_output_node(&self) -> Result<Node<'_>, DecoderError> {
+        output_node(&self.env, self.output_index, &self.modify_nodes)
     }
 
     /// Registers a callback to modify the VapourSynth output node before each frame decode.
@@ -330,6 +664,10 @@ impl VapoursynthDecoder {
     /// The callback is invoked with the `CoreRef` and current output node, and must return
     /// the modified node. The returned node is used for decoding and its metadata is cached.
     ///
+    /// Modifiers are chained in registration order: each one receives the node returned by
+    /// the previous one, so transformations can be layered (e.g. crop, then resize).
+    /// Use [`clear_node_modifiers`](Self::clear_node_modifiers) to start over.
+    ///
     /// # Errors
     ///
     /// Returns [`DecoderError::VapoursynthInternalError`] if the core cannot be obtained
@@ -339,6 +677,9 @@ impl VapoursynthDecoder {
         &mut self,
         modify_node: ModifyNode,
     ) -> Result<Node<'_>, DecoderError> {
+        // Frames requested from the previous node are no longer relevant
+        self.prefetcher.drain();
+
         let core = self
             .env
             .get_core()
@@ -346,28 +687,614 @@ impl VapoursynthDecoder {
                 cause: e.to_string(),
             })?;
 
-        let output_node = {
-            let res = self.env.get_output(self.output_index);
-            match res {
-                Ok((node, _)) => Some(node),
-                Err(vapoursynth::vsscript::Error::NoOutput) => None,
-                Err(e) => {
-                    return Err(DecoderError::VapoursynthInternalError {
-                        cause: e.to_string(),
-                    });
-                }
-            }
-        };
+        let output_node = script_output(&self.env, self.output_index)?;
+        let output_node = apply_node_modifiers(&self.env, output_node, &self.modify_nodes)?;
         let modified_node = modify_node(core, output_node)?;
 
         // Set the updated video details and total frames
-        let video_details = parse_video_details(modified_node.info())?;
+        let video_details = parse_video_details(&modified_node)?;
         self.video_details = Some(video_details);
         // Register the node modifier to be used during read_video_frame
-        self.modify_node = Some(modify_node);
+        self.modify_nodes.push(modify_node);
 
         Ok(modified_node)
     }
+
+    /// Converts the output to the given resolution, bit depth, and chroma subsampling.
+    ///
+    /// This registers a node modifier that runs the output through the `resize` function
+    /// matching `resizer`, e.g. `resize.Bicubic`, mirroring
+    /// `Ffms2Decoder::set_output_format`. Supports
+    /// YUV 4:2:0, 4:2:2, or 4:4:4 at 8, 10, 12, or 16-bit, and grayscale at 8 or 16-bit.
+    /// Video details are updated to match the converted output.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecoderError::UnsupportedFormat`] if the bit depth / chroma combination is not
+    /// supported, [`DecoderError::VapoursynthInternalError`] if the resize plugin is unavailable
+    /// or rejects the conversion.
+    #[inline]
+    pub fn set_output_format(
+        &mut self,
+        width: usize,
+        height: usize,
+        bit_depth: u8,
+        chroma_subsampling: ChromaSubsampling,
+        resizer: Resizer,
+    ) -> Result<(), DecoderError> {
+        let format = video_info_to_preset_format(bit_depth, chroma_subsampling)?;
+        self.register_node_modifier(Box::new(move |core, node| {
+            let node = node.ok_or(DecoderError::NoVideoStream)?;
+            resize_node(core, &node, width, height, format, resizer)
+        }))?;
+        Ok(())
+    }
+
+    /// Crops the given number of pixels from each edge of the output with `std.Crop`.
+    ///
+    /// This registers a node modifier, so it applies on top of any previously registered
+    /// modifiers. Video details are updated to the cropped size.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecoderError::VapoursynthInternalError`] if `std.Crop` rejects the crop,
+    /// for example because it is not aligned to the chroma subsampling.
+    #[inline]
+    pub fn set_crop(
+        &mut self,
+        left: usize,
+        top: usize,
+        right: usize,
+        bottom: usize,
+    ) -> Result<(), DecoderError> {
+        self.register_node_modifier(Box::new(move |core, node| {
+            let node = node.ok_or(DecoderError::NoVideoStream)?;
+            crop_node(core, &node, left, top, right, bottom)
+        }))?;
+        Ok(())
+    }
+
+    /// Removes all registered node modifiers, restoring the script's own output node.
+    ///
+    /// Video details are re-derived from the unmodified output on next use.
+    #[inline]
+    pub fn clear_node_modifiers(&mut self) {
+        self.prefetcher.drain();
+        self.modify_nodes.clear();
+        self.video_details = None;
+    }
+}
+
+/// Maps a bit depth and chroma subsampling onto the matching VapourSynth preset format.
+fn video_info_to_preset_format(
+    bit_depth: u8,
+    chroma_subsampling: ChromaSubsampling,
+) -> Result<PresetFormat, DecoderError> {
+    Ok(match (bit_depth, chroma_subsampling) {
+        (8, ChromaSubsampling::Yuv420) => PresetFormat::YUV420P8,
+        (8, ChromaSubsampling::Yuv422) => PresetFormat::YUV422P8,
+        (8, ChromaSubsampling::Yuv444) => PresetFormat::YUV444P8,
+        (8, ChromaSubsampling::Monochrome) => PresetFormat::Gray8,
+
+        (10, ChromaSubsampling::Yuv420) => PresetFormat::YUV420P10,
+        (10, ChromaSubsampling::Yuv422) => PresetFormat::YUV422P10,
+        (10, ChromaSubsampling::Yuv444) => PresetFormat::YUV444P10,
+
+        (12, ChromaSubsampling::Yuv420) => PresetFormat::YUV420P12,
+        (12, ChromaSubsampling::Yuv422) => PresetFormat::YUV422P12,
+        (12, ChromaSubsampling::Yuv444) => PresetFormat::YUV444P12,
+
+        (16, ChromaSubsampling::Yuv420) => PresetFormat::YUV420P16,
+        (16, ChromaSubsampling::Yuv422) => PresetFormat::YUV422P16,
+        (16, ChromaSubsampling::Yuv444) => PresetFormat::YUV444P16,
+        (16, ChromaSubsampling::Monochrome) => PresetFormat::Gray16,
+
+        _ => {
+            return Err(DecoderError::UnsupportedFormat {
+                fmt: "Unsupported bit depth and subsampling combination".to_string(),
+            });
+        }
+    })
+}
+
+/// Resizes and converts `node` with the `resize` function matching `resizer`.
+fn resize_node<'core>(
+    core: CoreRef<'core>,
+    node: &Node<'core>,
+    width: usize,
+    height: usize,
+    format: PresetFormat,
+    resizer: Resizer,
+) -> Result<Node<'core>, DecoderError> {
+    let api = API::get().ok_or_else(|| DecoderError::VapoursynthInternalError {
+        cause: "failed to get Vapoursynth API instance".to_string(),
+    })?;
+    let resize = core
+        .get_plugin_by_id("com.vapoursynth.resize")
+        .ok()
+        .flatten()
+        .ok_or_else(|| DecoderError::VapoursynthInternalError {
+            cause: "failed to get Vapoursynth resize plugin".to_string(),
+        })?;
+
+    let map_args_error = |e: vapoursynth::map::Error| DecoderError::VapoursynthArgsError {
+        cause: e.to_string(),
+    };
+    let mut arguments = OwnedMap::new(api);
+    arguments.set("clip", node).map_err(map_args_error)?;
+    arguments
+        .set_int("width", width as i64)
+        .map_err(map_args_error)?;
+    arguments
+        .set_int("height", height as i64)
+        .map_err(map_args_error)?;
+    arguments
+        .set_int("format", format as i64)
+        .map_err(map_args_error)?;
+
+    resize
+        .invoke(
+            match resizer {
+                Resizer::Point => "Point",
+                Resizer::Bilinear => "Bilinear",
+                Resizer::Bicubic => "Bicubic",
+                Resizer::Lanczos => "Lanczos",
+                Resizer::Spline => "Spline36",
+            },
+            &arguments,
+        )
+        .map_err(|e| DecoderError::VapoursynthInternalError {
+            cause: e.to_string(),
+        })?
+        .get_video_node("clip")
+        .map_err(|e| DecoderError::VapoursynthInternalError {
+            cause: e.to_string(),
+        })
+}
+
+/// Crops `node` with `std.Crop`.
+fn crop_node<'core>(
+    core: CoreRef<'core>,
+    node: &Node<'core>,
+    left: usize,
+    top: usize,
+    right: usize,
+    bottom: usize,
+) -> Result<Node<'core>, DecoderError> {
+    let api = API::get().ok_or_else(|| DecoderError::VapoursynthInternalError {
+        cause: "failed to get Vapoursynth API instance".to_string(),
+    })?;
+    let std_plugin = core
+        .get_plugin_by_id("com.vapoursynth.std")
+        .ok()
+        .flatten()
+        .ok_or_else(|| DecoderError::VapoursynthInternalError {
+            cause: "failed to get Vapoursynth std plugin".to_string(),
+        })?;
+
+    let map_args_error = |e: vapoursynth::map::Error| DecoderError::VapoursynthArgsError {
+        cause: e.to_string(),
+    };
+    let mut arguments = OwnedMap::new(api);
+    arguments.set("clip", node).map_err(map_args_error)?;
+    for (name, value) in [
+        ("left", left),
+        ("top", top),
+        ("right", right),
+        ("bottom", bottom),
+    ] {
+        arguments
+            .set_int(name, value as i64)
+            .map_err(map_args_error)?;
+    }
+
+    std_plugin
+        .invoke("Crop", &arguments)
+        .map_err(|e| DecoderError::VapoursynthInternalError {
+            cause: e.to_string(),
+        })?
+        .get_video_node("clip")
+        .map_err(|e| DecoderError::VapoursynthInternalError {
+            cause: e.to_string(),
+        })
+}
+
+/// Returns the clip a script set as output `index`, or `None` if there is none.
+fn script_output(env: &Environment, index: i32) -> Result<Option<Node<'_>>, DecoderError> {
+    match env.get_output(index) {
+        Ok((node, _)) => Ok(Some(node)),
+        Err(vapoursynth::vsscript::Error::NoOutput) => Ok(None),
+        Err(e) => Err(DecoderError::VapoursynthInternalError {
+            cause: e.to_string(),
+        }),
+    }
+}
+
+/// Returns the node decoded from output `index`: the script's output with `modifiers`
+/// applied. Modifiers may create the node from scratch when the script has no output.
+fn output_node<'core>(
+    env: &'core Environment,
+    index: i32,
+    modifiers: &[ModifyNode],
+) -> Result<Node<'core>, DecoderError> {
+    apply_node_modifiers(env, script_output(env, index)?, modifiers)?
+        .ok_or(DecoderError::NoVideoStream)
+}
+
+/// Applies each modifier in registration order, feeding each the previous one's output.
+fn apply_node_modifiers<'core>(
+    env: &'core Environment,
+    node: Option<Node<'core>>,
+    modifiers: &[ModifyNode],
+) -> Result<Option<Node<'core>>, DecoderError> {
+    modifiers.iter().try_fold(node, |node, modify_node| {
+        let core = env
+            .get_core()
+            .map_err(|e| DecoderError::VapoursynthInternalError {
+                cause: e.to_string(),
+            })?;
+        modify_node(core, node).map(Some)
+    })
+}
+
+/// Keeps asynchronous frame requests in flight ahead of the current read position.
+///
+/// Completed frames are not stored here: requesting them is enough to populate
+/// VapourSynth's frame cache, so the subsequent synchronous `get_frame` returns immediately.
+struct Prefetcher {
+    depth: usize,
+    /// First frame of the requested window, i.e. the one after the last frame read
+    window_start: usize,
+    /// Frame after the last one requested
+    next_frame: usize,
+    in_flight: usize,
+    done_tx: Sender<()>,
+    done_rx: Receiver<()>,
+}
+
+impl Prefetcher {
+    fn new() -> Self {
+        let (done_tx, done_rx) = mpsc::channel();
+        Self {
+            depth: 1,
+            window_start: 0,
+            next_frame: 0,
+            in_flight: 0,
+            done_tx,
+            done_rx,
+        }
+    }
+
+    /// Requests the frames following `frame_index`, up to `depth - 1` frames ahead.
+    ///
+    /// A read outside the current window is treated as a seek and drains the queue first.
+    fn request_after(&mut self, node: &Node, frame_index: usize, total_frames: Option<usize>) {
+        let start = frame_index + 1;
+        let end = (frame_index + self.depth).min(total_frames.unwrap_or(usize::MAX));
+        self.request(node, start..end.max(start));
+    }
+
+    /// Requests every frame in `frames` that is not requested yet, e.g. all frames of a
+    /// batched read at once.
+    ///
+    /// Frames before the window, or a gap after it, are treated as a seek and drain the
+    /// queue first.
+    fn request(&mut self, node: &Node, frames: Range<usize>) {
+        while self.in_flight > 0 && self.done_rx.try_recv().is_ok() {
+            self.in_flight -= 1;
+        }
+
+        if frames.start < self.window_start || frames.start > self.next_frame {
+            self.drain();
+            self.next_frame = frames.start;
+        }
+        self.window_start = frames.start;
+
+        while self.next_frame < frames.end {
+            let done_tx = self.done_tx.clone();
+            node.get_frame_async(self.next_frame, move |_, _, _| {
+                let _ = done_tx.send(());
+            });
+            self.in_flight += 1;
+            self.next_frame += 1;
+        }
+    }
+
+    /// Requests `frame_index` and waits up to `timeout` for it to complete.
+    ///
+    /// Returns `None` if the request timed out, otherwise the frame, or `Some(None)` if
+    /// the script failed to produce it. A request that times out is still counted as in
+    /// flight, so it is drained before the core is freed.
+    fn wait_for<'core>(
+        &mut self,
+        node: &Node<'core>,
+        frame_index: usize,
+        timeout: Duration,
+    ) -> Option<Option<FrameRef<'core>>> {
+        let (ready_tx, ready_rx) = mpsc::channel();
+        let done_tx = self.done_tx.clone();
+        node.get_frame_async(frame_index, move |frame, _, _| {
+            let _ = ready_tx.send(frame.ok());
+            let _ = done_tx.send(());
+        });
+        self.in_flight += 1;
+        ready_rx.recv_timeout(timeout).ok()
+    }
+
+    /// Blocks until every outstanding request has completed.
+    fn drain(&mut self) {
+        while self.in_flight > 0 {
+            if self.done_rx.recv().is_err() {
+                break;
+            }
+            self.in_flight -= 1;
+        }
+    }
+}
+
+/// The VapourSynth core version and loaded plugins, returned by
+/// [`VapoursynthDecoder::core_info`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoreInfo {
+    /// Human-readable version banner of the core, as printed by `vspipe --version`.
+    pub version: String,
+    /// Core release number, e.g. `70` for R70.
+    pub core_version: i32,
+    /// Version of the VapourSynth API implemented by the core.
+    pub api_version: i32,
+    /// Loaded plugins, sorted by identifier.
+    pub plugins: Vec<PluginInfo>,
+}
+
+/// A plugin loaded into the VapourSynth core.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluginInfo {
+    /// Unique identifier, e.g. `com.vapoursynth.ffms2`.
+    pub id: String,
+    /// Namespace its functions are called through in scripts, e.g. `ffms2`.
+    pub namespace: String,
+    /// Full name of the plugin.
+    pub name: String,
+}
+
+impl PluginInfo {
+    /// Parses a `namespace;identifier;name` entry of the core's plugin list.
+    fn parse(entry: &[u8]) -> Self {
+        let entry = String::from_utf8_lossy(entry);
+        let mut fields = entry.splitn(3, ';').map(str::to_string);
+        let namespace = fields.next().unwrap_or_default();
+        let id = fields.next().unwrap_or_default();
+        let name = fields.next().unwrap_or_default();
+        Self {
+            id,
+            namespace,
+            name,
+        }
+    }
+}
+
+/// The value of a VapourSynth frame property.
+///
+/// Every property is an array; single values are stored as one-element vectors.
+/// Properties holding clips, frames, or functions, such as `_Alpha`, cannot outlive the
+/// script environment and are not returned.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropValue {
+    /// Integer values, e.g. `_Matrix` or `_SceneChangeNext`.
+    Int(Vec<i64>),
+    /// Floating point values, e.g. `_AbsoluteTime`.
+    Float(Vec<f64>),
+    /// Binary or string data, e.g. `_PictType`.
+    Data(Vec<Vec<u8>>),
+}
+
+/// Reads the keyframe and scene change flags of a frame from its `_PictType` and
+/// `_SceneChangePrev` properties.
+fn read_frame_flags(frame: &FrameRef<'_>, frame_index: usize) -> FrameFlags {
+    let props = frame.props();
+    FrameFlags {
+        is_keyframe: props
+            .get_data("_PictType")
+            .map_or(frame_index == 0, |pict_type| pict_type == b"I"),
+        is_scene_change: props
+            .get_int("_SceneChangePrev")
+            .is_ok_and(|scene_change| scene_change != 0),
+    }
+}
+
+/// Copies the `_Alpha` frame attached to `frame` into `alpha`.
+///
+/// Returns `None` if there is none, otherwise whether the `_AlphaIsPremultiplied`
+/// property of the frame, or of its alpha frame, marks it as premultiplied. Alpha is
+/// straight when neither sets it.
+fn read_alpha<T: Pixel>(
+    frame: &FrameRef<'_>,
+    alpha: &mut Plane<T>,
+) -> Result<Option<bool>, DecoderError> {
+    let props = frame.props();
+    let Ok(alpha_frame) = props.get_video_frame("_Alpha") else {
+        return Ok(None);
+    };
+
+    let bytes_per_sample = alpha_frame.format().bytes_per_sample() as usize;
+    if bytes_per_sample != size_of::<T>() {
+        return Err(DecoderError::PixelTypeMismatch {
+            expected: bytes_per_sample,
+            got: size_of::<T>(),
+        });
+    }
+    let expected = (
+        alpha.rows().next().map_or(0, <[T]>::len),
+        alpha.rows().count(),
+    );
+    if (alpha_frame.width(0), alpha_frame.height(0)) != expected {
+        return Err(DecoderError::VariableResolution);
+    }
+    alpha
+        .copy_from_u8_slice_with_stride(
+            // SAFETY: we assume that the values provided by VapourSynth are correct
+            unsafe {
+                slice::from_raw_parts(
+                    alpha_frame.data_ptr(0),
+                    alpha_frame.stride(0) * alpha_frame.height(0),
+                )
+            },
+            alpha_frame.stride(0),
+        )
+        .map_err(DecoderError::generic)?;
+
+    let premultiplied = props
+        .get_int("_AlphaIsPremultiplied")
+        .or_else(|_| alpha_frame.props().get_int("_AlphaIsPremultiplied"))
+        .is_ok_and(|premultiplied| premultiplied != 0);
+    Ok(Some(premultiplied))
+}
+
+/// Copies every integer, float, and data property of a VapourSynth frame into an owned
+/// map.
+///
+/// Clip, frame, and function properties, and properties that cannot be read, are skipped.
+fn read_frame_props(frame: &FrameRef<'_>) -> HashMap<String, PropValue> {
+    let props = frame.props();
+    props
+        .keys()
+        .filter_map(|key| {
+            let value = match props.value_type(key).ok()? {
+                ValueType::Int => PropValue::Int(props.get_int_iter(key).ok()?.collect()),
+                ValueType::Float => PropValue::Float(props.get_float_iter(key).ok()?.collect()),
+                ValueType::Data => {
+                    PropValue::Data(props.get_data_iter(key).ok()?.map(<[u8]>::to_vec).collect())
+                }
+                _ => return None,
+            };
+            Some((key.to_string(), value))
+        })
+        .collect()
+}
+
+/// A decoded VapourSynth frame whose planes are read in place, without copying.
+///
+/// Obtained from [`VapoursynthDecoder::read_video_frame_borrowed`]. The frame is tied to
+/// the lifetime of the decoder's VapourSynth core. It cannot be passed to APIs expecting
+/// a [`Frame<T>`] directly; use [`to_frame`](Self::to_frame) to make a padded copy.
+pub struct BorrowedFrame<'core, T: Pixel> {
+    vs_frame: FrameRef<'core>,
+    video_details: VideoDetails,
+    _pixel: PhantomData<T>,
+}
+
+impl<T: Pixel> BorrowedFrame<'_, T> {
+    /// Returns the number of planes in the frame (1 for grayscale, 3 for YUV).
+    #[inline]
+    #[must_use]
+    pub fn plane_count(&self) -> usize {
+        self.vs_frame.format().plane_count()
+    }
+
+    /// Returns the samples of a plane, including any row padding.
+    ///
+    /// Rows are [`stride`](Self::stride) samples apart; only the first `width` samples
+    /// of each row are picture data.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `plane` is not less than [`plane_count`](Self::plane_count).
+    #[inline]
+    #[must_use]
+    pub fn plane(&self, plane: usize) -> &[T] {
+        assert!(plane < self.plane_count(), "plane index out of range");
+        // SAFETY: VapourSynth guarantees `stride * height` bytes are readable for the
+        // lifetime of the frame, and we verified that `T` matches the sample size on creation
+        unsafe {
+            slice::from_raw_parts(
+                self.vs_frame.data_ptr(plane).cast::<T>(),
+                self.stride(plane) * self.vs_frame.height(plane),
+            )
+        }
+    }
+
+    /// Returns the distance between the starts of consecutive rows of a plane, in samples.
+    #[inline]
+    #[must_use]
+    pub fn stride(&self, plane: usize) -> usize {
+        self.vs_frame.stride(plane) / size_of::<T>()
+    }
+
+    /// Returns the width of a plane in samples.
+    #[inline]
+    #[must_use]
+    pub fn width(&self, plane: usize) -> usize {
+        self.vs_frame.width(plane)
+    }
+
+    /// Returns the height of a plane in rows.
+    #[inline]
+    #[must_use]
+    pub fn height(&self, plane: usize) -> usize {
+        self.vs_frame.height(plane)
+    }
+
+    /// Copies the frame into a newly allocated, padded [`Frame<T>`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecoderError::GenericDecodeError`] if the frame cannot be allocated or copied.
+    #[inline]
+    pub fn to_frame(&self) -> Result<Frame<T>, DecoderError> {
+        let mut frame: Frame<T> = new_padded_frame(&self.video_details, DecoderConfig::default())?;
+        let planes = [
+            Some(&mut frame.y_plane),
+            frame.u_plane.as_mut(),
+            frame.v_plane.as_mut(),
+        ];
+        for (index, plane) in planes.into_iter().enumerate() {
+            let Some(plane) = plane else {
+                continue;
+            };
+            plane
+                .copy_from_u8_slice_with_stride(
+                    // SAFETY: VapourSynth guarantees `stride * height` bytes are readable
+                    // for the lifetime of the frame
+                    unsafe {
+                        slice::from_raw_parts(
+                            self.vs_frame.data_ptr(index),
+                            self.vs_frame.stride(index) * self.vs_frame.height(index),
+                        )
+                    },
+                    self.vs_frame.stride(index),
+                )
+                .map_err(DecoderError::generic)?;
+        }
+
+        Ok(frame)
+    }
+}
+
+/// Checks that the planes of `vs_frame` match the planes of `frame` they are copied into,
+/// which differ if a node modifier changed the resolution after `frame` was allocated.
+fn check_plane_sizes<T: Pixel>(
+    vs_frame: &FrameRef<'_>,
+    frame: &Frame<T>,
+    has_chroma: bool,
+) -> Result<(), DecoderError> {
+    let planes = [
+        Some(&frame.y_plane),
+        frame.u_plane.as_ref().filter(|_| has_chroma),
+        frame.v_plane.as_ref().filter(|_| has_chroma),
+    ];
+    for (index, plane) in planes.into_iter().enumerate() {
+        let Some(plane) = plane else {
+            continue;
+        };
+        let expected = (
+            plane.rows().next().map_or(0, <[T]>::len),
+            plane.rows().count(),
+        );
+        if (vs_frame.width(index), vs_frame.height(index)) != expected {
+            return Err(DecoderError::VariableResolution);
+        }
+    }
+    Ok(())
 }
 
 /// Extracts frame count from `VideoInfo`; rejects variable/zero-length streams.
@@ -390,11 +1317,13 @@ fn get_num_frames(info: VideoInfo) -> Result<TotalFrames, DecoderError> {
     Ok(num_frames)
 }
 
-/// Extracts bit depth from `VideoInfo`.
+/// Extracts bit depth from `VideoInfo`; rejects formats whose samples are not stored in
+/// the number of bytes the bit depth implies.
 fn get_bit_depth(info: VideoInfo) -> Result<BitDepth, DecoderError> {
-    let bits_per_sample = info.format.bits_per_sample();
+    let bits_per_sample = info.format.bits_per_sample() as usize;
+    check_sample_storage(bits_per_sample, info.format.bytes_per_sample() as usize)?;
 
-    Ok(bits_per_sample as usize)
+    Ok(bits_per_sample)
 }
 
 /// Extracts resolution from `VideoInfo`; rejects variable resolution.
@@ -445,8 +1374,93 @@ fn get_chroma_sampling(info: VideoInfo) -> Result<ChromaSubsampling, DecoderErro
     }
 }
 
-/// Parses all video metadata from a VapourSynth `VideoInfo`.
-fn parse_video_details(info: VideoInfo) -> Result<VideoDetails, DecoderError> {
+/// Reads the `_ChromaLocation` property of the first frame, defaulting to `Left` when unset.
+///
+/// Unlike the rest of the metadata, chroma siting is a per-frame property, so this has
+/// to look at a frame from the node.
+fn get_chroma_location(first_frame: Option<&FrameRef>) -> ChromaLocation {
+    let Some(frame) = first_frame else {
+        return ChromaLocation::default();
+    };
+    match frame.props().get_int("_ChromaLocation") {
+        Ok(1) => ChromaLocation::Center,
+        Ok(2) => ChromaLocation::TopLeft,
+        Ok(3) => ChromaLocation::Top,
+        Ok(4) => ChromaLocation::BottomLeft,
+        Ok(5) => ChromaLocation::Bottom,
+        _ => ChromaLocation::Left,
+    }
+}
+
+/// Reads the `_SARNum` and `_SARDen` properties of the first frame, defaulting to square
+/// pixels when unset.
+fn get_sample_aspect_ratio(first_frame: Option<&FrameRef>) -> Rational32 {
+    let Some(props) = first_frame.map(|frame| frame.props()) else {
+        return sample_aspect_ratio(0, 0);
+    };
+    sample_aspect_ratio(
+        props.get_int("_SARNum").unwrap_or(0),
+        props.get_int("_SARDen").unwrap_or(0),
+    )
+}
+
+/// Reads the `MasteringDisplay*` and `ContentLightLevel*` properties of the first frame.
+fn get_hdr_metadata(first_frame: Option<&FrameRef>) -> Option<HdrMetadata> {
+    let props = first_frame?.props();
+    let mastering_display = get_mastering_display(&props);
+    let content_light_level =
+        props
+            .get_int("ContentLightLevelMax")
+            .ok()
+            .map(|max_content_light_level| ContentLightLevel {
+                max_content_light_level: max_content_light_level as u32,
+                max_frame_average_light_level: props
+                    .get_int("ContentLightLevelAverage")
+                    .unwrap_or(0) as u32,
+            });
+    (mastering_display.is_some() || content_light_level.is_some()).then_some(HdrMetadata {
+        mastering_display,
+        content_light_level,
+    })
+}
+
+/// Reads the mastering display properties, which are only meaningful when all are set.
+fn get_mastering_display(props: &Map<'_>) -> Option<MasteringDisplay> {
+    let primaries_x: Vec<f64> = props
+        .get_float_iter("MasteringDisplayPrimariesX")
+        .ok()?
+        .collect();
+    let primaries_y: Vec<f64> = props
+        .get_float_iter("MasteringDisplayPrimariesY")
+        .ok()?
+        .collect();
+    let (&[rx, gx, bx], &[ry, gy, by]) = (primaries_x.as_slice(), primaries_y.as_slice()) else {
+        return None;
+    };
+
+    Some(MasteringDisplay {
+        primaries: [(rx, ry), (gx, gy), (bx, by)].map(|(x, y)| (chromaticity(x), chromaticity(y))),
+        white_point: (
+            chromaticity(props.get_float("MasteringDisplayWhitePointX").ok()?),
+            chromaticity(props.get_float("MasteringDisplayWhitePointY").ok()?),
+        ),
+        min_luminance: luminance(props.get_float("MasteringDisplayMinLuminance").ok()?),
+        max_luminance: luminance(props.get_float("MasteringDisplayMaxLuminance").ok()?),
+    })
+}
+
+/// Parses all video metadata from a VapourSynth output node.
+fn parse_video_details(node: &Node) -> Result<VideoDetails, DecoderError> {
+    parse_clip_details(node, node.get_frame(0).ok().as_ref())
+}
+
+/// Builds video details from the clip's `VideoInfo`, reading per-frame metadata such as
+/// chroma siting from `first_frame` when it is given.
+fn parse_clip_details(
+    node: &Node,
+    first_frame: Option<&FrameRef>,
+) -> Result<VideoDetails, DecoderError> {
+    let info = node.info();
     let total_frames = get_num_frames(info)?;
     let (width, height) = get_resolution(info)?;
     Ok(VideoDetails {
@@ -456,5 +1470,30 @@ fn parse_video_details(info: VideoInfo) -> Result<VideoDetails, DecoderError> {
         chroma_sampling: get_chroma_sampling(info)?,
         frame_rate: get_frame_rate(info)?,
         total_frames: Some(total_frames),
+        // `_FieldBased` is a per-frame property and is not part of the clip's `VideoInfo`
+        field_order: FieldOrder::Progressive,
+        chroma_location: get_chroma_location(first_frame),
+        // `_ColorRange` is 0 for full range and 1 for limited range
+        color_range: match first_frame.and_then(|frame| frame.props().get_int("_ColorRange").ok()) {
+            Some(0) => ColorRange::Full,
+            _ => ColorRange::Limited,
+        },
+        sample_aspect_ratio: get_sample_aspect_ratio(first_frame),
+        hdr_metadata: get_hdr_metadata(first_frame),
+        rotation: 0,
+        color_primaries: ColorPrimaries::from_h273(get_color_prop(first_frame, "_Primaries")),
+        transfer_characteristics: TransferCharacteristics::from_h273(get_color_prop(
+            first_frame,
+            "_Transfer",
+        )),
+        matrix_coefficients: MatrixCoefficients::from_h273(get_color_prop(first_frame, "_Matrix")),
     })
 }
+
+/// Reads an H.273 code point such as `_Matrix` from the first frame, returning the
+/// unspecified code 2 when it is unset.
+fn get_color_prop(first_frame: Option<&FrameRef>, key: &str) -> i64 {
+    first_frame
+        .and_then(|frame| frame.props().get_int(key).ok())
+        .unwrap_or(2)
+}
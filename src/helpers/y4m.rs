@@ -1,79 +1,573 @@
-use std::io::Read;
+use std::cell::RefCell;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::rc::Rc;
 
-use crate::VideoDetails;
 use crate::error::DecoderError;
+use crate::util::sample_aspect_ratio;
+use crate::{
+    ChromaLocation, ColorPrimaries, ColorRange, FieldOrder, MatrixCoefficients,
+    TransferCharacteristics, VideoDetails,
+};
 use num_rational::Rational32;
 use v_frame::{chroma::ChromaSubsampling, frame::Frame, pixel::Pixel};
 
-use super::frame_builder::new_padded_frame;
+/// The header of a frame without parameters.
+const FRAME_HEADER: &[u8] = b"FRAME\n";
 
-pub fn get_video_details<R: Read>(dec: &y4m::Decoder<R>) -> VideoDetails {
+/// Parses and validates the Y4M stream header from `reader`.
+pub fn open(reader: Box<dyn Read>) -> Result<y4m::Decoder<Box<dyn Read>>, DecoderError> {
+    decode(reader)
+}
+
+fn decode<R: Read>(reader: R) -> Result<y4m::Decoder<R>, DecoderError> {
+    let dec = y4m::decode(reader).map_err(|e| match e {
+        y4m::Error::EOF | y4m::Error::IoError(_) => map_y4m_error(e),
+        y4m::Error::UnknownColorspace => DecoderError::UnsupportedFormat {
+            fmt: "unknown Y4M colorspace".to_owned(),
+        },
+        _ => DecoderError::InvalidHeader {
+            cause: e.to_string(),
+        },
+    })?;
+    validate_header(&dec)?;
+    Ok(dec)
+}
+
+/// Rejects headers the parser accepts but that cannot describe a decodable stream, so that
+/// untrusted input fails here instead of panicking later.
+fn validate_header<R: Read>(dec: &y4m::Decoder<R>) -> Result<(), DecoderError> {
+    let (width, height) = (dec.get_width(), dec.get_height());
+    if width == 0 || height == 0 {
+        return Err(DecoderError::InvalidHeader {
+            cause: format!("frame size {width}x{height}"),
+        });
+    }
+    let framerate = dec.get_framerate();
+    if framerate.num == 0
+        || framerate.den == 0
+        || i32::try_from(framerate.num).is_err()
+        || i32::try_from(framerate.den).is_err()
+    {
+        return Err(DecoderError::InvalidHeader {
+            cause: format!("frame rate {}:{}", framerate.num, framerate.den),
+        });
+    }
+    map_y4m_color_space(dec.get_colorspace())?;
+    Ok(())
+}
+
+/// Maps a Y4M parser error, keeping the kind of I/O errors.
+fn map_y4m_error(error: y4m::Error) -> DecoderError {
+    match error {
+        y4m::Error::EOF => DecoderError::EndOfFile,
+        y4m::Error::IoError(e) => e.into(),
+        _ => DecoderError::generic(error),
+    }
+}
+
+pub fn get_video_details<R: Read>(dec: &y4m::Decoder<R>) -> Result<VideoDetails, DecoderError> {
     let width = dec.get_width();
     let height = dec.get_height();
     let color_space = dec.get_colorspace();
     let bit_depth = color_space.get_bit_depth();
-    let chroma_sampling = map_y4m_color_space(color_space);
+    let chroma_sampling = map_y4m_color_space(color_space)?;
     let framerate = dec.get_framerate();
     let frame_rate = Rational32::new(framerate.num as i32, framerate.den as i32);
 
-    VideoDetails {
+    Ok(VideoDetails {
         width,
         height,
         bit_depth,
         chroma_sampling,
         frame_rate,
         total_frames: None,
-    }
+        field_order: parse_field_order(dec.get_raw_params()),
+        chroma_location: map_y4m_chroma_location(color_space),
+        color_range: parse_color_range(dec.get_raw_params()),
+        sample_aspect_ratio: parse_sample_aspect_ratio(dec.get_raw_params()),
+        hdr_metadata: None,
+        rotation: 0,
+        // Y4M does not signal colorimetry
+        color_primaries: ColorPrimaries::Unspecified,
+        transfer_characteristics: TransferCharacteristics::Unspecified,
+        matrix_coefficients: MatrixCoefficients::Unspecified,
+    })
+}
+
+/// Reads the sample aspect ratio from the `A` parameter of a Y4M stream header.
+///
+/// A missing or unknown (`A0:0`) parameter is treated as square pixels.
+fn parse_sample_aspect_ratio(raw_params: &[u8]) -> Rational32 {
+    raw_params
+        .split(|&b| b == b' ')
+        .find_map(|param| {
+            let (num, den) = std::str::from_utf8(param.strip_prefix(b"A")?)
+                .ok()?
+                .split_once(':')?;
+            Some(sample_aspect_ratio(num.parse().ok()?, den.parse().ok()?))
+        })
+        .unwrap_or_else(|| sample_aspect_ratio(0, 0))
+}
+
+/// Reads the `XCOLORRANGE` extension parameter of a Y4M stream header, as written by
+/// FFmpeg.
+///
+/// A missing parameter is treated as limited range.
+fn parse_color_range(raw_params: &[u8]) -> ColorRange {
+    raw_params
+        .split(|&b| b == b' ')
+        .find_map(|param| match param {
+            b"XCOLORRANGE=FULL" => Some(ColorRange::Full),
+            b"XCOLORRANGE=LIMITED" => Some(ColorRange::Limited),
+            _ => None,
+        })
+        .unwrap_or_default()
 }
 
-const fn map_y4m_color_space(color_space: y4m::Colorspace) -> ChromaSubsampling {
+/// Reads the interlacing mode from the `I` parameter of a Y4M stream header.
+///
+/// A missing or unknown (`I?`) parameter is treated as progressive.
+fn parse_field_order(raw_params: &[u8]) -> FieldOrder {
+    raw_params
+        .split(|&b| b == b' ')
+        .find_map(|param| match param {
+            b"It" => Some(FieldOrder::TopFieldFirst),
+            b"Ib" => Some(FieldOrder::BottomFieldFirst),
+            b"Im" => Some(FieldOrder::Mixed),
+            b"Ip" => Some(FieldOrder::Progressive),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+/// Maps a Y4M colorspace onto the subsampling modes supported by `v_frame`.
+///
+/// Subsamplings without a `v_frame` equivalent (such as 4:1:1) are rejected rather than
+/// decoded with the wrong plane sizes.
+fn map_y4m_color_space(color_space: y4m::Colorspace) -> Result<ChromaSubsampling, DecoderError> {
     use y4m::Colorspace::{
         C420, C420jpeg, C420mpeg2, C420p10, C420p12, C420paldv, C422, C422p10, C422p12, C444,
         C444p10, C444p12, Cmono, Cmono12,
     };
-    match color_space {
+    Ok(match color_space {
         Cmono | Cmono12 => ChromaSubsampling::Monochrome,
         C420jpeg | C420paldv | C420mpeg2 | C420 | C420p10 | C420p12 => ChromaSubsampling::Yuv420,
         C422 | C422p10 | C422p12 => ChromaSubsampling::Yuv422,
         C444 | C444p10 | C444p12 => ChromaSubsampling::Yuv444,
-        _ => unimplemented!(),
+        _ => {
+            return Err(DecoderError::UnsupportedFormat {
+                fmt: format!("{color_space:?}"),
+            });
+        }
+    })
+}
+
+/// Y4M only distinguishes chroma siting for 8-bit 4:2:0; a bare `C420` follows the
+/// spec's JPEG siting, everything else falls back to the MPEG-2 default.
+const fn map_y4m_chroma_location(color_space: y4m::Colorspace) -> ChromaLocation {
+    use y4m::Colorspace::{C420, C420jpeg, C420paldv};
+    match color_space {
+        C420 | C420jpeg => ChromaLocation::Center,
+        C420paldv => ChromaLocation::TopLeft,
+        _ => ChromaLocation::Left,
     }
 }
 
+/// Reads past the next frame without copying it into a [`Frame`].
+pub fn skip_video_frame<R: Read>(dec: &mut y4m::Decoder<R>) -> Result<(), DecoderError> {
+    dec.read_frame().map(|_| ()).map_err(map_y4m_error)
+}
+
+/// Reads the next frame into `frame`, skipping any planes `frame` does not have.
 pub fn read_video_frame<R: Read, T: Pixel>(
     dec: &mut y4m::Decoder<R>,
-    cfg: &VideoDetails,
-    luma_only: bool,
-) -> Result<Frame<T>, DecoderError> {
-    let dec_frame = dec.read_frame().map_err(|e| match e {
-        y4m::Error::EOF => DecoderError::EndOfFile,
-        _ => DecoderError::GenericDecodeError {
-            cause: e.to_string(),
-        },
-    })?;
-
-    let mut frame: Frame<T> = new_padded_frame(cfg, luma_only)?;
+    frame: &mut Frame<T>,
+) -> Result<(), DecoderError> {
+    let dec_frame = dec.read_frame().map_err(map_y4m_error)?;
 
     frame
         .y_plane
         .copy_from_u8_slice(dec_frame.get_y_plane())
-        .map_err(|e| DecoderError::GenericDecodeError {
-            cause: e.to_string(),
-        })?;
+        .map_err(DecoderError::generic)?;
     if let Some(u_plane) = frame.u_plane.as_mut() {
         u_plane
             .copy_from_u8_slice(dec_frame.get_u_plane())
-            .map_err(|e| DecoderError::GenericDecodeError {
-                cause: e.to_string(),
-            })?;
+            .map_err(DecoderError::generic)?;
     }
     if let Some(v_plane) = frame.v_plane.as_mut() {
         v_plane
             .copy_from_u8_slice(dec_frame.get_v_plane())
-            .map_err(|e| DecoderError::GenericDecodeError {
-                cause: e.to_string(),
-            })?;
+            .map_err(DecoderError::generic)?;
+    }
+
+    Ok(())
+}
+
+/// A reader that can also seek, such as a [`File`](std::fs::File) or a
+/// [`Cursor`](std::io::Cursor).
+pub trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek + ?Sized> ReadSeek for T {}
+
+/// Shares a stream between the Y4M parser, which reads from it, and
+/// [`SeekableY4mDecoder`], which repositions it.
+#[derive(Clone)]
+struct SharedReader(Rc<RefCell<Box<dyn ReadSeek>>>);
+
+impl Read for SharedReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.borrow_mut().read(buf)
+    }
+}
+
+/// Y4M decoder over a seekable stream, which can jump to any frame.
+pub struct SeekableY4mDecoder {
+    decoder: y4m::Decoder<SharedReader>,
+    reader: SharedReader,
+    /// Offset of the first frame header.
+    data_start: u64,
+    /// Size of a frame with a header without parameters.
+    frame_len: u64,
+    /// Length of the whole stream.
+    stream_len: u64,
+    /// Number of frames, if every frame has a header without parameters.
+    total_frames: Option<usize>,
+    /// Offsets of the frames read so far, starting with the first, when the number of
+    /// frames is unknown and frames cannot be located from `frame_len`.
+    frame_offsets: Vec<u64>,
+    /// Index of the next frame the stream will produce.
+    position: usize,
+}
+
+impl SeekableY4mDecoder {
+    /// Parses and validates the Y4M stream header from `reader`.
+    pub(crate) fn new(reader: Box<dyn ReadSeek>) -> Result<Self, DecoderError> {
+        let reader = SharedReader(Rc::new(RefCell::new(reader)));
+        let decoder = decode(reader.clone())?;
+        // The parser reads the header byte by byte, so the stream is now at the first frame
+        let data_start = reader.0.borrow_mut().stream_position()?;
+        let stream_len = reader.0.borrow_mut().seek(SeekFrom::End(0))?;
+        let frame_size = get_video_details(&decoder)?.frame_size_bytes();
+        let mut dec = Self {
+            decoder,
+            reader,
+            data_start,
+            frame_len: (FRAME_HEADER.len() + frame_size) as u64,
+            stream_len,
+            total_frames: None,
+            frame_offsets: vec![data_start],
+            position: 0,
+        };
+        dec.total_frames = dec.count_fixed_size_frames()?;
+        dec.seek(data_start)?;
+        Ok(dec)
+    }
+
+    pub(crate) fn video_details(&self) -> Result<VideoDetails, DecoderError> {
+        Ok(VideoDetails {
+            total_frames: self.total_frames,
+            ..get_video_details(&self.decoder)?
+        })
     }
 
-    Ok(frame)
+    /// Returns the number of frames if the stream holds a whole number of frames whose
+    /// headers have no parameters, checking the header of the last one.
+    fn count_fixed_size_frames(&self) -> Result<Option<usize>, DecoderError> {
+        let data_len = self.stream_len.saturating_sub(self.data_start);
+        if data_len % self.frame_len != 0 {
+            return Ok(None);
+        }
+        let total_frames = data_len / self.frame_len;
+        if total_frames > 0
+            && !self.has_frame_header_at(self.data_start + (total_frames - 1) * self.frame_len)?
+        {
+            return Ok(None);
+        }
+        Ok(usize::try_from(total_frames).ok())
+    }
+
+    /// Returns the index of the next frame the stream will produce.
+    pub(crate) const fn position(&self) -> usize {
+        self.position
+    }
+
+    pub(crate) fn read_video_frame<T: Pixel>(
+        &mut self,
+        frame: &mut Frame<T>,
+    ) -> Result<(), DecoderError> {
+        read_video_frame(&mut self.decoder, frame)?;
+        self.advance()
+    }
+
+    pub(crate) fn skip_frame(&mut self) -> Result<(), DecoderError> {
+        skip_video_frame(&mut self.decoder)?;
+        self.advance()
+    }
+
+    /// Moves past the frame just read, recording where the next one starts if its offset
+    /// cannot be computed.
+    fn advance(&mut self) -> Result<(), DecoderError> {
+        self.position += 1;
+        if self.total_frames.is_none() && self.position == self.frame_offsets.len() {
+            let offset = self.reader.0.borrow_mut().stream_position()?;
+            self.frame_offsets.push(offset);
+        }
+        Ok(())
+    }
+
+    /// Positions the stream so that the next frame read is `frame_index`.
+    ///
+    /// Frames almost always have a header without parameters, so the frame's offset is
+    /// computed directly. Otherwise, the stream is positioned at the closest frame read
+    /// before and skips forward from there, recording the offset of each frame it passes.
+    ///
+    /// Seeking to or past the end of the stream returns [`DecoderError::EndOfFile`] and
+    /// leaves the stream at its end.
+    pub(crate) fn seek_to_frame(&mut self, frame_index: usize) -> Result<(), DecoderError> {
+        if let Some(total_frames) = self.total_frames {
+            let index = frame_index.min(total_frames);
+            self.seek(self.data_start + index as u64 * self.frame_len)?;
+            self.position = index;
+            return if frame_index >= total_frames {
+                Err(DecoderError::EndOfFile)
+            } else {
+                Ok(())
+            };
+        }
+        let known = frame_index.min(self.frame_offsets.len() - 1);
+        self.seek(self.frame_offsets[known])?;
+        self.position = known;
+        while self.position < frame_index {
+            self.skip_frame()?;
+        }
+        // The offset recorded after the last frame is the end of the stream
+        if self.frame_offsets[self.position] >= self.stream_len {
+            return Err(DecoderError::EndOfFile);
+        }
+        Ok(())
+    }
+
+    fn has_frame_header_at(&self, offset: u64) -> Result<bool, DecoderError> {
+        self.seek(offset)?;
+        let mut header = [0; FRAME_HEADER.len()];
+        match self.reader.0.borrow_mut().read_exact(&mut header) {
+            Ok(()) => Ok(header[..] == *FRAME_HEADER),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn seek(&self, offset: u64) -> io::Result<()> {
+        self.reader
+            .0
+            .borrow_mut()
+            .seek(SeekFrom::Start(offset))
+            .map(drop)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::y4m_fixture;
+
+    fn two_frame_y4m(second_frame_header: &[u8]) -> Vec<u8> {
+        let mut data = y4m_fixture(&[16]);
+        data.extend_from_slice(second_frame_header);
+        data.extend_from_slice(&[48; 4 * 2 + 2 * 2]);
+        data
+    }
+
+    fn first_luma_after_seek(data: Vec<u8>, frame_index: usize) -> u8 {
+        let mut dec =
+            SeekableY4mDecoder::new(Box::new(std::io::Cursor::new(data))).expect("valid header");
+        dec.seek_to_frame(frame_index).expect("seek");
+        let details = dec.video_details().expect("details");
+        let mut frame = details.new_black_frame::<u8>().expect("black frame");
+        dec.read_video_frame(&mut frame).expect("frame");
+        frame
+            .y_plane
+            .rows()
+            .flatten()
+            .next()
+            .copied()
+            .expect("luma sample")
+    }
+
+    #[test]
+    fn seeks_directly_to_frames() {
+        assert_eq!(first_luma_after_seek(two_frame_y4m(b"FRAME\n"), 1), 48);
+        assert_eq!(first_luma_after_seek(two_frame_y4m(b"FRAME\n"), 0), 16);
+    }
+
+    #[test]
+    fn seeks_past_frame_parameters() {
+        assert_eq!(first_luma_after_seek(two_frame_y4m(b"FRAME Ip\n"), 1), 48);
+    }
+
+    #[test]
+    fn seeks_back_to_frames_with_parameters() {
+        let data = two_frame_y4m(b"FRAME Ip\n");
+        let mut dec =
+            SeekableY4mDecoder::new(Box::new(std::io::Cursor::new(data))).expect("valid header");
+        let mut frame = dec
+            .video_details()
+            .and_then(|details| details.new_black_frame::<u8>())
+            .expect("black frame");
+        for (frame_index, luma) in [(1, 48), (0, 16), (1, 48)] {
+            dec.seek_to_frame(frame_index).expect("seek");
+            dec.read_video_frame(&mut frame).expect("frame");
+            assert!(frame.y_plane.rows().flatten().all(|&sample| sample == luma));
+        }
+        assert_eq!(dec.position(), 2);
+    }
+
+    #[test]
+    fn counts_frames_without_parameters() {
+        let count = |data: Vec<u8>| {
+            SeekableY4mDecoder::new(Box::new(std::io::Cursor::new(data)))
+                .and_then(|dec| dec.video_details())
+                .expect("valid header")
+                .total_frames
+        };
+        assert_eq!(count(two_frame_y4m(b"FRAME\n")), Some(2));
+        assert_eq!(count(y4m_fixture(&[])), Some(0));
+        assert_eq!(count(two_frame_y4m(b"FRAME Ip\n")), None);
+    }
+
+    #[test]
+    fn seeking_past_the_end_stops_at_the_end() {
+        for second_frame_header in [&b"FRAME\n"[..], b"FRAME Ip\n"] {
+            let data = two_frame_y4m(second_frame_header);
+            let mut dec = SeekableY4mDecoder::new(Box::new(std::io::Cursor::new(data)))
+                .expect("valid header");
+            for frame_index in [2, 3, usize::MAX] {
+                assert!(
+                    dec.seek_to_frame(frame_index)
+                        .expect_err("only two frames")
+                        .is_eof()
+                );
+                assert_eq!(dec.position(), 2);
+            }
+        }
+    }
+
+    fn open_header(header: &[u8]) -> Result<(), DecoderError> {
+        open(Box::new(std::io::Cursor::new(header.to_vec()))).map(|_| ())
+    }
+
+    #[test]
+    fn rejects_invalid_headers() {
+        for header in [
+            &b"NOTY4M W4 H2 F25:1\n"[..],
+            b"YUV4MPEG2 W0 H2 F25:1\n",
+            b"YUV4MPEG2 W4 H2 F25:0\n",
+            b"YUV4MPEG2 W4 H2 F0:1\n",
+            b"YUV4MPEG2 W4 H2 F4294967295:1\n",
+            b"YUV4MPEG2 Wx H2 F25:1\n",
+        ] {
+            assert!(
+                matches!(open_header(header), Err(DecoderError::InvalidHeader { .. })),
+                "{}",
+                String::from_utf8_lossy(header)
+            );
+        }
+        assert!(matches!(
+            open_header(b"YUV4MPEG2 W4 H2 F25:1 C411\n"),
+            Err(DecoderError::UnsupportedFormat { .. })
+        ));
+    }
+
+    #[test]
+    fn truncated_header_is_end_of_file() {
+        assert!(
+            open_header(b"YUV4MPEG2 W4 H2")
+                .expect_err("no header terminator")
+                .is_eof()
+        );
+        assert!(open_header(b"").expect_err("empty stream").is_eof());
+    }
+
+    #[test]
+    fn parses_interlace_parameter() {
+        assert_eq!(
+            parse_field_order(b"W640 H480 F30:1 It A1:1 C420jpeg"),
+            FieldOrder::TopFieldFirst
+        );
+        assert_eq!(
+            parse_field_order(b"W640 H480 F30:1 Ib A1:1"),
+            FieldOrder::BottomFieldFirst
+        );
+        assert_eq!(parse_field_order(b"W640 H480 Im"), FieldOrder::Mixed);
+    }
+
+    #[test]
+    fn parses_aspect_ratio_parameter() {
+        assert_eq!(
+            parse_sample_aspect_ratio(b"W720 H480 F30000:1001 It A10:11"),
+            Rational32::new(10, 11)
+        );
+        assert_eq!(
+            parse_sample_aspect_ratio(b"W720 H480 A0:0"),
+            Rational32::new(1, 1)
+        );
+        assert_eq!(
+            parse_sample_aspect_ratio(b"W720 H480"),
+            Rational32::new(1, 1)
+        );
+    }
+
+    #[test]
+    fn maps_420_chroma_siting() {
+        assert_eq!(
+            map_y4m_chroma_location(y4m::Colorspace::C420jpeg),
+            ChromaLocation::Center
+        );
+        assert_eq!(
+            map_y4m_chroma_location(y4m::Colorspace::C420mpeg2),
+            ChromaLocation::Left
+        );
+        assert_eq!(
+            map_y4m_chroma_location(y4m::Colorspace::C420paldv),
+            ChromaLocation::TopLeft
+        );
+        assert_eq!(
+            map_y4m_chroma_location(y4m::Colorspace::C420p10),
+            ChromaLocation::Left
+        );
+    }
+
+    #[test]
+    fn maps_monochrome_color_spaces() {
+        assert!(matches!(
+            map_y4m_color_space(y4m::Colorspace::Cmono),
+            Ok(ChromaSubsampling::Monochrome)
+        ));
+        assert!(matches!(
+            map_y4m_color_space(y4m::Colorspace::Cmono12),
+            Ok(ChromaSubsampling::Monochrome)
+        ));
+    }
+
+    #[test]
+    fn parses_color_range_extension() {
+        assert_eq!(
+            parse_color_range(b"W640 H480 F25:1 XYSCSS=420JPEG XCOLORRANGE=FULL"),
+            ColorRange::Full
+        );
+        assert_eq!(
+            parse_color_range(b"W640 H480 XCOLORRANGE=LIMITED"),
+            ColorRange::Limited
+        );
+        assert_eq!(parse_color_range(b"W640 H480"), ColorRange::Limited);
+    }
+
+    #[test]
+    fn defaults_to_progressive() {
+        assert_eq!(
+            parse_field_order(b"W640 H480 F30:1 A1:1"),
+            FieldOrder::Progressive
+        );
+        assert_eq!(parse_field_order(b"W640 H480 I?"), FieldOrder::Progressive);
+    }
 }
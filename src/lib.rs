@@ -24,55 +24,138 @@
 //! }
 //! # Ok::<(), av_decoders::DecoderError>(())
 //! ```
+//!
+//! # Decoding on other threads
+//!
+//! A [`Decoder`] is not [`Send`]: backends such as VapourSynth hold handles that must
+//! stay on the thread that created them. The methods that decode on other threads,
+//! [`Decoder::spawn_background`], [`Decoder::decode_range_parallel`], and (with the
+//! `stream` feature) `Decoder::frame_stream`, therefore take an `open` closure instead of
+//! a decoder, and call it on the decoding thread to create the decoder there.
 
 #[cfg(feature = "vapoursynth")]
 use std::collections::HashMap;
+use std::fmt;
 use std::fs::File;
-use std::io::{BufReader, Read, stdin};
-use std::path::Path;
+use std::io::{BufRead, BufReader, Cursor, Read, Seek, stdin};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::str::FromStr;
+use std::time::Duration;
 use v_frame::chroma::ChromaSubsampling;
 use v_frame::frame::Frame;
 use v_frame::pixel::Pixel;
 
+mod alpha;
+mod background;
+mod debug;
+mod encoder;
 mod error;
+mod frames;
+mod hash;
 mod helpers {
+    pub(crate) mod convert;
     #[cfg(feature = "ffmpeg")]
     pub(crate) mod ffmpeg;
     #[cfg(feature = "ffms2")]
     pub(crate) mod ffms2;
-    mod frame_builder;
+    pub(crate) mod frame_builder;
+    pub(crate) mod raw_yuv;
     #[cfg(feature = "vapoursynth")]
     pub(crate) mod vapoursynth;
     pub(crate) mod y4m;
 }
+mod frame_cache;
+mod lookahead;
+#[cfg(feature = "memmap")]
+mod mmap;
+mod parallel;
+mod planes;
+#[cfg(feature = "image")]
+mod preview;
+mod raw;
+mod reverse;
+mod rgb;
+#[cfg(feature = "vapoursynth")]
+pub mod source;
+#[cfg(feature = "stream")]
+mod stream;
+#[cfg(test)]
+mod test_util;
+mod trim;
 mod util;
+mod worker;
 
+pub use crate::alpha::AlphaPlane;
+pub use crate::debug::dump_frame_raw;
+pub use crate::encoder::Y4mEncoder;
+use crate::frame_cache::FrameCache;
+pub use crate::frames::{Frames, IntoFrames, MapFrames};
+pub use crate::hash::hash_frame;
+use crate::helpers::convert::{convert_frame, push_sample, reduce_to_8bit};
 #[cfg(feature = "ffmpeg")]
-pub use crate::helpers::ffmpeg::FfmpegDecoder;
+pub use crate::helpers::ffmpeg::{ErrorResilience, FfmpegDecoder, RetryPolicy};
 #[cfg(feature = "ffms2")]
 pub use crate::helpers::ffms2::Ffms2Decoder;
+#[cfg(feature = "ffms2")]
+pub use crate::helpers::ffms2::IndexCache;
+use crate::helpers::frame_builder::{
+    Crop, check_frame_geometry, check_padding, check_pixel_type, crop_frame, crop_frame_into,
+    cropped_details, new_padded_frame, rotate_frame, rotated_details,
+};
+pub use crate::helpers::raw_yuv::RawYuvDecoder;
+#[cfg(feature = "vapoursynth")]
+pub use crate::helpers::vapoursynth::BorrowedFrame;
 #[cfg(feature = "vapoursynth")]
 pub use crate::helpers::vapoursynth::ModifyNode;
 #[cfg(feature = "vapoursynth")]
+pub use crate::helpers::vapoursynth::PropValue;
+#[cfg(feature = "vapoursynth")]
 pub use crate::helpers::vapoursynth::VapoursynthDecoder;
 #[cfg(feature = "vapoursynth")]
-use crate::helpers::vapoursynth::{VariableName, VariableValue};
-pub use error::DecoderError;
+use crate::helpers::vapoursynth::VariableName;
+#[cfg(feature = "vapoursynth")]
+pub use crate::helpers::vapoursynth::VariableValue;
+#[cfg(feature = "vapoursynth")]
+pub use crate::helpers::vapoursynth::{CoreInfo, PluginInfo};
+pub use crate::helpers::y4m::{ReadSeek, SeekableY4mDecoder};
+use crate::lookahead::Lookahead;
+pub use crate::parallel::ParallelFrames;
+pub use crate::planes::PackedPlane;
+pub use crate::raw::{RawFrame, RawPlane};
+pub use crate::reverse::FramesRev;
+#[cfg(feature = "stream")]
+pub use crate::stream::FrameStream;
+pub use crate::trim::TrimmedDecoder;
+use crate::util::ticks_to_duration;
+pub use error::{DecoderError, ErrorSource};
 pub use num_rational::Rational32;
 pub use v_frame;
 pub use y4m::Decoder as Y4mDecoder;
 
 const Y4M_EXTENSIONS: &[&str] = &["y4m", "yuv"];
+const Y4M_MAGIC: &[u8] = b"YUV4MPEG2";
+/// Backends [`Decoder::from_file`] tries, in order, for files that are not Y4M.
+const DEFAULT_BACKEND_ORDER: [Backend; 3] = [Backend::Ffms2, Backend::Ffmpeg, Backend::Vapoursynth];
+
+/// How long [`Decoder::from_url`] waits for a stalled network read before failing.
+pub const URL_TIMEOUT: Duration = Duration::from_secs(30);
 
 // TODO: Get rid of these and make padding an optional parameter
 const SB_SIZE_LOG2: usize = 6;
 const SB_SIZE: usize = 1 << SB_SIZE_LOG2;
 const SUBPEL_FILTER_SIZE: usize = 8;
 const FRAME_MARGIN: usize = 16 + SUBPEL_FILTER_SIZE;
-const LUMA_PADDING: usize = SB_SIZE + FRAME_MARGIN;
+/// Padding in samples added on every side of the luma plane of each returned frame.
+///
+/// Chroma planes are padded by the same amount divided by their subsampling ratio. Use
+/// [`Decoder::estimated_memory_per_frame`] to get the resulting allocation size.
+pub const LUMA_PADDING: usize = SB_SIZE + FRAME_MARGIN;
 
 /// Video metadata and configuration details, populated by every decoder on init.
-#[derive(Debug, Clone, Copy)]
+///
+/// Equality and hashing compare `frame_rate` by value, so `30/1` and `60/2` are equal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct VideoDetails {
     /// The width of the video frame in pixels.
     pub width: usize,
@@ -86,6 +169,616 @@ pub struct VideoDetails {
     pub frame_rate: Rational32,
     /// Total number of frames, if known.
     pub total_frames: Option<usize>,
+    /// Interlacing mode and field order of the source.
+    pub field_order: FieldOrder,
+    /// Siting of the chroma samples relative to the luma samples.
+    pub chroma_location: ChromaLocation,
+    /// Sample (pixel) aspect ratio, `1/1` for square pixels or when unknown.
+    ///
+    /// The display width of the video is `width * sample_aspect_ratio`.
+    pub sample_aspect_ratio: Rational32,
+    /// HDR mastering display and content light level metadata, if the source has any.
+    pub hdr_metadata: Option<HdrMetadata>,
+    /// Clockwise rotation in degrees (0, 90, 180, or 270) needed to display the video
+    /// upright, e.g. for phone recordings. Only FFmpeg reads this from the container;
+    /// other backends report 0.
+    pub rotation: i32,
+    /// Range of the sample values, limited unless the source signals full range.
+    pub color_range: ColorRange,
+    /// Color primaries, if signaled by the source.
+    pub color_primaries: ColorPrimaries,
+    /// Transfer characteristics, if signaled by the source.
+    pub transfer_characteristics: TransferCharacteristics,
+    /// Matrix coefficients, if signaled by the source.
+    pub matrix_coefficients: MatrixCoefficients,
+}
+
+impl VideoDetails {
+    /// Returns the `(width, height)` in samples of the given plane (0 = Y, 1 = U, 2 = V).
+    ///
+    /// Chroma dimensions are rounded up for odd luma sizes. Planes that are not present,
+    /// such as chroma for monochrome video or indices above 2, are `(0, 0)`.
+    #[inline]
+    #[must_use]
+    pub fn plane_dimensions(&self, plane: usize) -> (usize, usize) {
+        match plane {
+            0 => (self.width, self.height),
+            1 | 2 => self
+                .chroma_sampling
+                .subsample_ratio()
+                .map_or((0, 0), |(x, y)| {
+                    (
+                        self.width.div_ceil(x.get() as usize),
+                        self.height.div_ceil(y.get() as usize),
+                    )
+                }),
+            _ => (0, 0),
+        }
+    }
+
+    /// Returns the number of bytes each sample takes: 1 for up to 8 bits, 2 otherwise.
+    ///
+    /// This is the size of the [`Pixel`] type frames must be read as.
+    #[inline]
+    #[must_use]
+    pub const fn bytes_per_sample(&self) -> usize {
+        if self.bit_depth > 8 { 2 } else { 1 }
+    }
+
+    /// Returns the frame rate in frames per second, e.g. `29.97` for `30000/1001`.
+    ///
+    /// Returns `0.0` for a zero denominator, which some broken files report.
+    #[inline]
+    #[must_use]
+    pub fn frame_rate_f64(&self) -> f64 {
+        match *self.frame_rate.denom() {
+            0 => 0.0,
+            denom => f64::from(*self.frame_rate.numer()) / f64::from(denom),
+        }
+    }
+
+    /// Returns the length of the video, i.e. `total_frames / frame_rate`.
+    ///
+    /// The division is exact up to the nanosecond. Returns `None` if the frame count is
+    /// unknown or the frame rate is not positive.
+    #[inline]
+    #[must_use]
+    pub fn duration(&self) -> Option<Duration> {
+        let total_frames = u64::try_from(self.total_frames?).ok()?;
+        let frame_duration =
+            Rational32::new_raw(*self.frame_rate.denom(), *self.frame_rate.numer());
+        ticks_to_duration(total_frames, frame_duration)
+    }
+
+    /// Returns the frame rate in lowest terms, e.g. `30000/1001` for `60000/2002`.
+    ///
+    /// Returns `None` for a zero denominator, which some broken files report.
+    #[inline]
+    #[must_use]
+    pub fn frame_rate_reduced(&self) -> Option<Rational32> {
+        (*self.frame_rate.denom() != 0).then(|| self.frame_rate.reduced())
+    }
+
+    /// Builds a black frame in this format, e.g. to pad a short clip.
+    ///
+    /// Luma is set to black for the [`color_range`](Self::color_range), i.e. 16 for
+    /// limited and 0 for full range at 8 bits, and chroma to its neutral value, i.e. 128
+    /// at 8 bits. Values scale with the bit depth. The frame has the default
+    /// [`LUMA_PADDING`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecoderError::UnsupportedFormat`] if the bit depth is not between 1 and
+    /// 16, [`DecoderError::PixelTypeMismatch`] if `T` does not match
+    /// [`bytes_per_sample`](Self::bytes_per_sample), [`DecoderError::GenericDecodeError`]
+    /// for a zero-sized format.
+    #[inline]
+    pub fn new_black_frame<T: Pixel>(&self) -> Result<Frame<T>, DecoderError> {
+        if !(1..=16).contains(&self.bit_depth) {
+            return Err(DecoderError::UnsupportedFormat {
+                fmt: format!("{}-bit samples", self.bit_depth),
+            });
+        }
+        check_pixel_type::<T>(self)?;
+        let mut frame: Frame<T> = new_padded_frame(self, DecoderConfig::default())?;
+        let black = match self.color_range {
+            ColorRange::Limited if self.bit_depth >= 8 => 16_u16 << (self.bit_depth - 8),
+            ColorRange::Limited => 16_u16 >> (8 - self.bit_depth),
+            ColorRange::Full => 0,
+        };
+        let neutral = 1_u16 << (self.bit_depth - 1);
+        let planes = [
+            (Some(&mut frame.y_plane), black),
+            (frame.u_plane.as_mut(), neutral),
+            (frame.v_plane.as_mut(), neutral),
+        ];
+        let mut buffer = Vec::new();
+        for (index, (plane, value)) in planes.into_iter().enumerate() {
+            let Some(plane) = plane else {
+                continue;
+            };
+            let (width, height) = self.plane_dimensions(index);
+            buffer.clear();
+            for _ in 0..width * height {
+                push_sample(&mut buffer, value, self.bytes_per_sample());
+            }
+            plane
+                .copy_from_u8_slice(&buffer)
+                .map_err(DecoderError::generic)?;
+        }
+        Ok(frame)
+    }
+
+    /// Returns the size in bytes of one tightly packed frame, without padding.
+    ///
+    /// Samples above 8 bits take two bytes each.
+    #[inline]
+    #[must_use]
+    pub fn frame_size_bytes(&self) -> usize {
+        let bytes_per_sample = self.bytes_per_sample();
+        (0..3)
+            .map(|plane| {
+                let (width, height) = self.plane_dimensions(plane);
+                width * height * bytes_per_sample
+            })
+            .sum()
+    }
+}
+
+impl fmt::Display for VideoDetails {
+    /// Formats the details as a short human-readable summary,
+    /// e.g. `640x480 8-bit 4:2:0 @ 30 fps (112 frames)`.
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}x{} {}-bit {} @ {} fps",
+            self.width,
+            self.height,
+            self.bit_depth,
+            chroma_label(self.chroma_sampling),
+            self.frame_rate_reduced().unwrap_or(self.frame_rate)
+        )?;
+        if let Some(total_frames) = self.total_frames {
+            write!(f, " ({total_frames} frames)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Interlacing mode of a video stream.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum FieldOrder {
+    /// Progressive (non-interlaced) frames. Assumed when the source does not say otherwise.
+    #[default]
+    Progressive,
+    /// Interlaced, with the top field displayed first.
+    TopFieldFirst,
+    /// Interlaced, with the bottom field displayed first.
+    BottomFieldFirst,
+    /// The field order varies from frame to frame.
+    Mixed,
+}
+
+/// Position of subsampled chroma samples relative to the luma grid.
+///
+/// Naming follows FFmpeg's `AVChromaLocation`. Only meaningful for subsampled formats.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ChromaLocation {
+    /// Horizontally co-sited with the left luma sample, vertically centered (MPEG-2/4, H.264).
+    /// Assumed when the source does not say otherwise.
+    #[default]
+    Left,
+    /// Centered between luma samples in both directions (JPEG/MPEG-1).
+    Center,
+    /// Co-sited with the top-left luma sample (ITU-R BT.2020, DV PAL 4:2:0).
+    TopLeft,
+    /// Horizontally centered, co-sited with the top luma row.
+    Top,
+    /// Co-sited with the bottom-left luma sample.
+    BottomLeft,
+    /// Horizontally centered, co-sited with the bottom luma row.
+    Bottom,
+}
+
+/// Range of the sample values of a video stream.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ColorRange {
+    /// Limited ("TV" or "MPEG") range, e.g. 16–235 for 8-bit luma. Assumed when the
+    /// source does not say otherwise.
+    #[default]
+    Limited,
+    /// Full ("PC" or "JPEG") range, using every code value.
+    Full,
+}
+
+/// Color primaries of a video stream, with the code points of ITU-T H.273.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum ColorPrimaries {
+    /// ITU-R BT.709, also used by sRGB.
+    Bt709 = 1,
+    /// Not signaled. Assumed when the source does not say otherwise.
+    #[default]
+    Unspecified = 2,
+    /// ITU-R BT.470 System M.
+    Bt470M = 4,
+    /// ITU-R BT.470 System B/G, as used by PAL.
+    Bt470Bg = 5,
+    /// SMPTE 170M, as used by NTSC and BT.601.
+    Smpte170M = 6,
+    /// SMPTE 240M.
+    Smpte240M = 7,
+    /// Generic film (color filters using illuminant C).
+    Film = 8,
+    /// ITU-R BT.2020 and BT.2100.
+    Bt2020 = 9,
+    /// SMPTE ST 428-1 (CIE 1931 XYZ).
+    Smpte428 = 10,
+    /// SMPTE RP 431-2 (DCI-P3).
+    Smpte431 = 11,
+    /// SMPTE EG 432-1 (Display P3).
+    Smpte432 = 12,
+    /// EBU Tech. 3213-E.
+    Ebu3213 = 22,
+}
+
+impl ColorPrimaries {
+    /// Maps an H.273 `ColourPrimaries` code point, with unknown and reserved codes
+    /// mapping to [`Unspecified`](Self::Unspecified).
+    #[inline]
+    #[must_use]
+    pub const fn from_h273(code: i64) -> Self {
+        match code {
+            1 => Self::Bt709,
+            4 => Self::Bt470M,
+            5 => Self::Bt470Bg,
+            6 => Self::Smpte170M,
+            7 => Self::Smpte240M,
+            8 => Self::Film,
+            9 => Self::Bt2020,
+            10 => Self::Smpte428,
+            11 => Self::Smpte431,
+            12 => Self::Smpte432,
+            22 => Self::Ebu3213,
+            _ => Self::Unspecified,
+        }
+    }
+}
+
+/// Transfer characteristics of a video stream, with the code points of ITU-T H.273.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum TransferCharacteristics {
+    /// ITU-R BT.709.
+    Bt709 = 1,
+    /// Not signaled. Assumed when the source does not say otherwise.
+    #[default]
+    Unspecified = 2,
+    /// ITU-R BT.470 System M (gamma 2.2).
+    Bt470M = 4,
+    /// ITU-R BT.470 System B/G (gamma 2.8).
+    Bt470Bg = 5,
+    /// SMPTE 170M, as used by BT.601.
+    Smpte170M = 6,
+    /// SMPTE 240M.
+    Smpte240M = 7,
+    /// Linear light.
+    Linear = 8,
+    /// Logarithmic, 100:1 range.
+    Log100 = 9,
+    /// Logarithmic, 100 * sqrt(10):1 range.
+    Log100Sqrt10 = 10,
+    /// IEC 61966-2-4 (xvYCC).
+    Iec61966_2_4 = 11,
+    /// ITU-R BT.1361 extended color gamut.
+    Bt1361 = 12,
+    /// IEC 61966-2-1 (sRGB).
+    Srgb = 13,
+    /// ITU-R BT.2020 for 10-bit systems.
+    Bt2020Ten = 14,
+    /// ITU-R BT.2020 for 12-bit systems.
+    Bt2020Twelve = 15,
+    /// SMPTE ST 2084 perceptual quantizer (PQ), as used by HDR10.
+    Smpte2084 = 16,
+    /// SMPTE ST 428-1.
+    Smpte428 = 17,
+    /// ARIB STD-B67 hybrid log-gamma (HLG).
+    AribStdB67 = 18,
+}
+
+impl TransferCharacteristics {
+    /// Maps an H.273 `TransferCharacteristics` code point, with unknown and reserved codes
+    /// mapping to [`Unspecified`](Self::Unspecified).
+    #[inline]
+    #[must_use]
+    pub const fn from_h273(code: i64) -> Self {
+        match code {
+            1 => Self::Bt709,
+            4 => Self::Bt470M,
+            5 => Self::Bt470Bg,
+            6 => Self::Smpte170M,
+            7 => Self::Smpte240M,
+            8 => Self::Linear,
+            9 => Self::Log100,
+            10 => Self::Log100Sqrt10,
+            11 => Self::Iec61966_2_4,
+            12 => Self::Bt1361,
+            13 => Self::Srgb,
+            14 => Self::Bt2020Ten,
+            15 => Self::Bt2020Twelve,
+            16 => Self::Smpte2084,
+            17 => Self::Smpte428,
+            18 => Self::AribStdB67,
+            _ => Self::Unspecified,
+        }
+    }
+}
+
+/// Matrix coefficients used to derive luma and chroma from RGB, with the code points of
+/// ITU-T H.273.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum MatrixCoefficients {
+    /// No matrix: the planes hold RGB (GBR order) or XYZ directly.
+    Identity = 0,
+    /// ITU-R BT.709.
+    Bt709 = 1,
+    /// Not signaled. Assumed when the source does not say otherwise.
+    #[default]
+    Unspecified = 2,
+    /// US FCC 73.682.
+    Fcc = 4,
+    /// ITU-R BT.470 System B/G, equivalent to BT.601 for PAL.
+    Bt470Bg = 5,
+    /// SMPTE 170M, equivalent to BT.601 for NTSC.
+    Smpte170M = 6,
+    /// SMPTE 240M.
+    Smpte240M = 7,
+    /// YCgCo.
+    YCgCo = 8,
+    /// ITU-R BT.2020 non-constant luminance.
+    Bt2020Ncl = 9,
+    /// ITU-R BT.2020 constant luminance.
+    Bt2020Cl = 10,
+    /// SMPTE ST 2085 (Y'D'zD'x).
+    Smpte2085 = 11,
+    /// Chromaticity-derived non-constant luminance.
+    ChromaDerivedNcl = 12,
+    /// Chromaticity-derived constant luminance.
+    ChromaDerivedCl = 13,
+    /// ITU-R BT.2100 ICtCp.
+    ICtCp = 14,
+}
+
+impl MatrixCoefficients {
+    /// Maps an H.273 `MatrixCoefficients` code point, with unknown and reserved codes
+    /// mapping to [`Unspecified`](Self::Unspecified).
+    #[inline]
+    #[must_use]
+    pub const fn from_h273(code: i64) -> Self {
+        match code {
+            0 => Self::Identity,
+            1 => Self::Bt709,
+            4 => Self::Fcc,
+            5 => Self::Bt470Bg,
+            6 => Self::Smpte170M,
+            7 => Self::Smpte240M,
+            8 => Self::YCgCo,
+            9 => Self::Bt2020Ncl,
+            10 => Self::Bt2020Cl,
+            11 => Self::Smpte2085,
+            12 => Self::ChromaDerivedNcl,
+            13 => Self::ChromaDerivedCl,
+            14 => Self::ICtCp,
+            _ => Self::Unspecified,
+        }
+    }
+}
+
+/// Static HDR metadata, as used by HDR10.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct HdrMetadata {
+    /// Color volume of the display the content was mastered on (SMPTE ST 2086).
+    pub mastering_display: Option<MasteringDisplay>,
+    /// Light level of the content itself (CTA-861.3).
+    pub content_light_level: Option<ContentLightLevel>,
+}
+
+/// Mastering display color volume.
+///
+/// Chromaticities are CIE 1931 `(x, y)` coordinates; luminances are in cd/m².
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MasteringDisplay {
+    /// Red, green, and blue primaries, in that order.
+    pub primaries: [(Rational32, Rational32); 3],
+    /// White point.
+    pub white_point: (Rational32, Rational32),
+    /// Minimum display luminance.
+    pub min_luminance: Rational32,
+    /// Maximum display luminance.
+    pub max_luminance: Rational32,
+}
+
+/// Content light levels, in cd/m².
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct ContentLightLevel {
+    /// Maximum content light level (MaxCLL).
+    pub max_content_light_level: u32,
+    /// Maximum frame-average light level (MaxFALL).
+    pub max_frame_average_light_level: u32,
+}
+
+/// Returns the conventional `J:a:b` notation for a chroma subsampling mode.
+const fn chroma_label(chroma_sampling: ChromaSubsampling) -> &'static str {
+    match chroma_sampling {
+        ChromaSubsampling::Yuv420 => "4:2:0",
+        ChromaSubsampling::Yuv422 => "4:2:2",
+        ChromaSubsampling::Yuv444 => "4:4:4",
+        ChromaSubsampling::Monochrome => "4:0:0",
+    }
+}
+
+/// Scaling algorithm used when a backend resizes frames or resamples chroma.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Resizer {
+    /// Nearest neighbor. Fastest, but blocky.
+    Point,
+    /// Bilinear interpolation.
+    Bilinear,
+    /// Bicubic interpolation, a good balance of speed and sharpness.
+    #[default]
+    Bicubic,
+    /// Lanczos resampling. Sharpest, with some ringing.
+    Lanczos,
+    /// Spline resampling (`Spline36` in VapourSynth).
+    Spline,
+}
+
+/// A decoding backend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Backend {
+    /// The built-in Y4M parser.
+    Y4m,
+    /// The built-in headerless planar YUV reader.
+    RawYuv,
+    /// FFmpeg, via `ffmpeg-the-third`.
+    Ffmpeg,
+    /// FFMS2 (FFmpegSource2).
+    Ffms2,
+    /// A VapourSynth script.
+    Vapoursynth,
+}
+
+impl Backend {
+    /// Returns `true` if support for this backend was compiled in.
+    #[inline]
+    #[must_use]
+    pub const fn is_available(self) -> bool {
+        match self {
+            Self::Y4m | Self::RawYuv => true,
+            Self::Ffmpeg => cfg!(feature = "ffmpeg"),
+            Self::Ffms2 => cfg!(feature = "ffms2"),
+            Self::Vapoursynth => cfg!(feature = "vapoursynth"),
+        }
+    }
+
+    /// Picks a backend from the file extension alone: Y4M for `.y4m`/`.yuv`, VapourSynth
+    /// for `.vpy` scripts if available.
+    fn from_extension(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        if Y4M_EXTENSIONS.contains(&ext.as_str()) {
+            Some(Self::Y4m)
+        } else if ext == "vpy" && Self::Vapoursynth.is_available() {
+            Some(Self::Vapoursynth)
+        } else {
+            None
+        }
+    }
+}
+
+impl TryFrom<&Path> for Backend {
+    type Error = DecoderError;
+
+    /// Picks the first backend [`Decoder::from_file`] would try for `path`, based on its
+    /// extension and the compiled features. The file contents are not inspected.
+    ///
+    /// Returns [`DecoderError::NoDecoder`] if no suitable backend is available.
+    #[inline]
+    fn try_from(path: &Path) -> Result<Self, Self::Error> {
+        Self::from_extension(path)
+            .or_else(|| {
+                DEFAULT_BACKEND_ORDER
+                    .into_iter()
+                    .find(|backend| backend.is_available())
+            })
+            .ok_or(DecoderError::NoDecoder)
+    }
+}
+
+impl FromStr for Backend {
+    type Err = DecoderError;
+
+    /// Parses a backend name as printed by [`Display`](fmt::Display), ignoring case.
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "y4m" => Ok(Self::Y4m),
+            "rawyuv" => Ok(Self::RawYuv),
+            "ffmpeg" => Ok(Self::Ffmpeg),
+            "ffms2" => Ok(Self::Ffms2),
+            "vapoursynth" => Ok(Self::Vapoursynth),
+            _ => Err(DecoderError::GenericDecodeError {
+                cause: format!("unknown decoder backend `{s}`"),
+                source: None,
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Backend {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Y4m => "y4m",
+            Self::RawYuv => "rawyuv",
+            Self::Ffmpeg => "ffmpeg",
+            Self::Ffms2 => "ffms2",
+            Self::Vapoursynth => "vapoursynth",
+        })
+    }
+}
+
+/// Identifies the source being decoded, e.g. for logging.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SourceInfo {
+    /// The container format (e.g. `matroska,webm`), if known.
+    pub container: Option<String>,
+    /// The video codec (e.g. `h264`), if known.
+    pub codec: Option<String>,
+    /// The backend decoding the source.
+    pub backend: Backend,
+}
+
+/// Picture-level flags of a decoded frame.
+///
+/// Backends that do not know a frame's picture type (Y4M, raw YUV, and FFMS2) only mark
+/// the first frame as a keyframe, and never report scene changes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct FrameFlags {
+    /// The frame can be decoded without reference to other frames.
+    pub is_keyframe: bool,
+    /// The frame starts a new scene, as flagged by an upstream scene change detector.
+    pub is_scene_change: bool,
+}
+
+/// Presentation timestamp of a decoded frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameTimestamp {
+    /// Presentation timestamp, in units of `time_base`.
+    pub pts: i64,
+    /// Duration of one `pts` tick, in seconds.
+    pub time_base: Rational32,
+}
+
+/// What the backend reported about a decoded frame beyond its pixels, kept with frames
+/// buffered by the lookahead or the frame cache so that reading them again reports the
+/// same values.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct FrameMeta {
+    /// The container's timestamp, if the backend reads one.
+    pub(crate) timestamp: Option<FrameTimestamp>,
+    /// The picture flags, if the backend knows them.
+    pub(crate) flags: Option<FrameFlags>,
+}
+
+impl FrameTimestamp {
+    /// Returns the timestamp in seconds.
+    #[inline]
+    #[must_use]
+    pub fn as_secs_f64(&self) -> f64 {
+        self.pts as f64 * f64::from(*self.time_base.numer()) / f64::from(*self.time_base.denom())
+    }
 }
 
 /// A set of possible configuration flags that are generic across all decoders.
@@ -93,6 +786,36 @@ pub struct VideoDetails {
 pub struct DecoderConfig {
     /// If `true`, the decoder will only fetch the luma planes from the video.
     pub luma_only: bool,
+    /// Padding allocated around the luma plane of every returned frame.
+    pub padding: Padding,
+}
+
+/// Padding in luma samples around each edge of the frames returned by a [`Decoder`].
+///
+/// Chroma planes are padded by the same amounts divided by their subsampling ratio.
+/// Defaults to [`LUMA_PADDING`] on every side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Padding {
+    /// Samples left of the picture.
+    pub left: usize,
+    /// Rows above the picture.
+    pub top: usize,
+    /// Samples right of the picture.
+    pub right: usize,
+    /// Rows below the picture.
+    pub bottom: usize,
+}
+
+impl Default for Padding {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            left: LUMA_PADDING,
+            top: LUMA_PADDING,
+            right: LUMA_PADDING,
+            bottom: LUMA_PADDING,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -106,6 +829,15 @@ impl Default for VideoDetails {
             chroma_sampling: ChromaSubsampling::Yuv420,
             frame_rate: Rational32::new(30, 1),
             total_frames: None,
+            field_order: FieldOrder::Progressive,
+            chroma_location: ChromaLocation::Left,
+            color_range: ColorRange::Limited,
+            sample_aspect_ratio: Rational32::new(1, 1),
+            hdr_metadata: None,
+            rotation: 0,
+            color_primaries: ColorPrimaries::Unspecified,
+            transfer_characteristics: TransferCharacteristics::Unspecified,
+            matrix_coefficients: MatrixCoefficients::Unspecified,
         }
     }
 }
@@ -118,134 +850,239 @@ pub struct Decoder {
     video_details: VideoDetails,
     frames_read: usize,
     config: DecoderConfig,
+    /// Crop applied in Rust after decoding, along with the uncropped source details
+    crop: Option<(Crop, VideoDetails)>,
+    /// Sum of all crops made with `set_crop`, whichever backend applied them
+    total_crop: Option<Crop>,
+    normalized: Option<NormalizedOutput>,
+    /// Rotation applied by `set_auto_rotate`, along with the unrotated details
+    auto_rotation: Option<(i32, VideoDetails)>,
+    lookahead: Option<Lookahead>,
+    /// Frames kept by `enable_frame_cache`, cleared whenever the output format changes
+    frame_cache: Option<FrameCache>,
+    /// Index at which the backend reported the end of the stream. Reads at or past it
+    /// fail without touching the backend again.
+    eof_at: Option<usize>,
+    /// Where the decoder was opened from, for [`try_reopen`](Self::try_reopen) and
+    /// seeking in [`from_bytes`](Self::from_bytes) buffers
+    source: Option<Source>,
+}
+
+/// Output format requested with [`Decoder::set_normalized_output`].
+#[derive(Debug, Clone, Copy)]
+struct NormalizedOutput {
+    width: usize,
+    height: usize,
+    bit_depth: u8,
+    chroma_sampling: ChromaSubsampling,
+    /// Details of the frames converted in Rust, or `None` if the backend converts them
+    input: Option<VideoDetails>,
+}
+
+/// Where a [`Decoder`] was opened from, kept so that the source can be opened again.
+enum Source {
+    File {
+        path: PathBuf,
+        backend: Backend,
+    },
+    RawYuv {
+        path: PathBuf,
+        details: VideoDetails,
+    },
+    #[cfg(feature = "vapoursynth")]
+    Script {
+        script: String,
+        variables: HashMap<VariableName, String>,
+    },
+    #[cfg(feature = "ffmpeg")]
+    Url(String),
+    Bytes(Rc<[u8]>),
+    #[cfg(feature = "memmap")]
+    Mapped(Rc<mmap::MappedFile>),
+}
+
+impl Source {
+    /// Returns a new reader over the whole Y4M stream, for sources held in memory.
+    fn y4m_reader(&self) -> Option<Box<dyn ReadSeek>> {
+        match self {
+            Self::Bytes(data) => Some(Box::new(Cursor::new(Rc::clone(data)))),
+            #[cfg(feature = "memmap")]
+            Self::Mapped(file) => Some(Box::new(mmap::MappedReader::new(Rc::clone(file)))),
+            _ => None,
+        }
+    }
 }
 
 impl Decoder {
     /// Creates a new decoder from a file path, auto-selecting the backend.
     ///
-    /// Priority: Y4M → FFMS2 → FFmpeg → VapourSynth.
+    /// Priority: Y4M → FFMS2 → FFmpeg → VapourSynth. Backends after the first are tried as
+    /// described in [`from_file_with_fallback`](Self::from_file_with_fallback), so that
+    /// e.g. a codec missing from FFMS2 can still be decoded by FFmpeg.
+    ///
+    /// Y4M is detected by its `YUV4MPEG2` signature as well as by extension, so misnamed
+    /// Y4M files still use the Y4M parser.
     ///
     /// # Errors
     ///
-    /// Returns [`DecoderError::FileReadError`] if the file cannot be opened,
-    /// [`DecoderError::NoDecoder`] if no backend is available for the format.
+    /// Returns [`DecoderError::Io`] if the file cannot be opened,
+    /// [`DecoderError::NoDecoder`] if no backend is available for the format, and
+    /// otherwise the error of the last backend tried.
     #[inline]
-    #[expect(clippy::allow_attributes)]
-    #[allow(
-        unreachable_code,
-        reason = "some branches are unreachable with some combinations of features"
-    )]
     pub fn from_file<P: AsRef<Path>>(input: P) -> Result<Decoder, DecoderError> {
         // A raw y4m parser is going to be the fastest with the least overhead,
         // so we should use it if we have a y4m file.
-        let ext = input
-            .as_ref()
-            .extension()
-            .and_then(|ext| ext.to_str())
-            .map(|ext| ext.to_ascii_lowercase());
-        if let Some(ext) = ext.as_deref() {
-            if Y4M_EXTENSIONS.contains(&ext) {
-                let reader =
-                    BufReader::new(File::open(input).map_err(|e| DecoderError::FileReadError {
-                        cause: e.to_string(),
-                    })?);
-                let decoder = DecoderImpl::Y4m(
-                    y4m::decode(Box::new(reader) as Box<dyn Read>).map_err(|e| match e {
-                        y4m::Error::EOF => DecoderError::EndOfFile,
-                        _ => DecoderError::GenericDecodeError {
-                            cause: e.to_string(),
-                        },
-                    })?,
-                );
-                let video_details = decoder.video_details()?;
-                return Ok(Decoder {
-                    decoder,
-                    video_details,
-                    frames_read: 0,
-                    config: DecoderConfig::default(),
-                });
-            }
-
-            #[cfg(feature = "vapoursynth")]
-            if ext == "vpy" {
-                // Decode vapoursynth script file input
-                let decoder = DecoderImpl::Vapoursynth(VapoursynthDecoder::from_file(
-                    input,
-                    HashMap::new(),
-                    None,
-                )?);
-                let video_details = decoder.video_details()?;
-                return Ok(Decoder {
-                    decoder,
-                    video_details,
-                    frames_read: 0,
-                    config: DecoderConfig::default(),
-                });
-            }
+        if let Some(backend) = Backend::from_extension(input.as_ref()) {
+            return Decoder::from_file_with_backend(input, backend);
         }
 
-        // Ffms2 is the fastest and most reliable, use it if available.
-        #[cfg(feature = "ffms2")]
-        {
-            let decoder = DecoderImpl::Ffms2(Ffms2Decoder::new(input, None)?);
-            let video_details = decoder.video_details()?;
-            return Ok(Decoder {
-                decoder,
-                video_details,
-                frames_read: 0,
-                config: DecoderConfig::default(),
+        // Sniff the content in case the extension is missing or misleading. The peeked
+        // bytes stay in the reader's buffer, so the Y4M parser still sees the header.
+        let mut reader = BufReader::new(File::open(input.as_ref())?);
+        if reader.fill_buf()?.starts_with(Y4M_MAGIC) {
+            let mut decoder = Decoder::from_seekable_reader(reader)?;
+            decoder.source = Some(Source::File {
+                path: input.as_ref().to_path_buf(),
+                backend: Backend::Y4m,
             });
+            return Ok(decoder);
+        }
+        drop(reader);
+
+        Decoder::from_file_with_fallback(input, &DEFAULT_BACKEND_ORDER)
+    }
+
+    /// Creates a new decoder from a file path, trying each of `backends` in order.
+    ///
+    /// Backends that are not compiled in are skipped. When a backend cannot handle the
+    /// input, i.e. fails with [`DecoderError::UnsupportedFormat`] or
+    /// [`DecoderError::NoVideoStream`], the next one is tried; any other error is returned
+    /// immediately. [`source_info`](Self::source_info) reports the backend that succeeded.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecoderError::NoDecoder`] if none of `backends` is available, otherwise
+    /// the error of the last backend tried.
+    #[inline]
+    pub fn from_file_with_fallback<P: AsRef<Path>>(
+        input: P,
+        backends: &[Backend],
+    ) -> Result<Decoder, DecoderError> {
+        let mut result = Err(DecoderError::NoDecoder);
+        for &backend in backends.iter().filter(|backend| backend.is_available()) {
+            result = Decoder::from_file_with_backend(input.as_ref(), backend);
+            if !matches!(
+                result,
+                Err(DecoderError::UnsupportedFormat { .. } | DecoderError::NoVideoStream)
+            ) {
+                break;
+            }
         }
+        result
+    }
+
+    /// Creates a new decoder from a file path using the given backend.
+    ///
+    /// With [`Backend::Vapoursynth`], `.vpy` files are evaluated as scripts and other files
+    /// are loaded with `ffms2.Source`. [`Backend::RawYuv`] needs the frame layout, so use
+    /// [`from_raw_yuv`](Self::from_raw_yuv) for it instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecoderError::NoDecoder`] if the backend is not compiled in,
+    /// [`DecoderError::UnsupportedDecoder`] for [`Backend::RawYuv`], and otherwise any
+    /// error from opening the file with the backend.
+    #[inline]
+    #[expect(clippy::allow_attributes)]
+    #[allow(
+        unreachable_code,
+        reason = "every backend returns early when none are compiled in"
+    )]
+    pub fn from_file_with_backend<P: AsRef<Path>>(
+        input: P,
+        backend: Backend,
+    ) -> Result<Decoder, DecoderError> {
+        let path = input.as_ref();
+        let mut decoder = match backend {
+            Backend::Y4m => Decoder::from_seekable_reader(BufReader::new(File::open(path)?))?,
+            Backend::RawYuv => return Err(DecoderError::UnsupportedDecoder),
+            #[cfg(feature = "ffms2")]
+            Backend::Ffms2 => {
+                Decoder::from_decoder_impl(DecoderImpl::Ffms2(Ffms2Decoder::new(path, None)?))?
+            }
+            #[cfg(feature = "ffmpeg")]
+            Backend::Ffmpeg => {
+                Decoder::from_decoder_impl(DecoderImpl::Ffmpeg(FfmpegDecoder::new(path)?))?
+            }
+            #[cfg(feature = "vapoursynth")]
+            Backend::Vapoursynth => {
+                Decoder::from_decoder_impl(DecoderImpl::Vapoursynth(open_vapoursynth_file(path)?))?
+            }
+            #[cfg(not(all(feature = "ffms2", feature = "ffmpeg", feature = "vapoursynth")))]
+            _ => return Err(DecoderError::NoDecoder),
+        };
+        decoder.source = Some(Source::File {
+            path: path.to_path_buf(),
+            backend,
+        });
+        Ok(decoder)
+    }
 
-        // Ffmpeg is considerably faster at decoding, so we should prefer it over Vapoursynth
-        // for general use cases.
+    /// Creates a new decoder for a network stream, such as an HTTP(S) or RTSP URL.
+    ///
+    /// The URL is opened by FFmpeg directly, without treating it as a path. Reads that
+    /// stall for more than [`URL_TIMEOUT`] fail instead of blocking forever; use
+    /// [`FfmpegDecoder::new_url`] with [`from_decoder_impl`](Self::from_decoder_impl) to
+    /// pick another timeout. Transient read failures are retried with the default
+    /// `RetryPolicy` of the FFmpeg backend.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecoderError::NoDecoder`] if the `ffmpeg` feature is disabled,
+    /// [`DecoderError::FileReadError`] if the stream cannot be opened or times out, and
+    /// otherwise the same errors as [`FfmpegDecoder::new`].
+    #[inline]
+    pub fn from_url(url: &str) -> Result<Decoder, DecoderError> {
         #[cfg(feature = "ffmpeg")]
         {
-            let decoder = DecoderImpl::Ffmpeg(FfmpegDecoder::new(input)?);
-            let video_details = decoder.video_details()?;
-            return Ok(Decoder {
-                decoder,
-                video_details,
-                frames_read: 0,
-                config: DecoderConfig::default(),
-            });
+            let mut decoder = Decoder::from_decoder_impl(DecoderImpl::Ffmpeg(
+                FfmpegDecoder::new_url(url, URL_TIMEOUT)?,
+            ))?;
+            decoder.source = Some(Source::Url(url.to_string()));
+            Ok(decoder)
         }
-
-        #[cfg(feature = "vapoursynth")]
+        #[cfg(not(feature = "ffmpeg"))]
         {
-            // Build a vapoursynth script and use that
-            use crate::util::escape_python_string;
-
-            let script = format!(
-                r#"
-import vapoursynth as vs
-core = vs.core
-clip = core.ffms2.Source("{}")
-clip.set_output()
-"#,
-                escape_python_string(
-                    &std::path::absolute(input)
-                        .map_err(|e| DecoderError::FileReadError {
-                            cause: e.to_string()
-                        })?
-                        .to_string_lossy()
-                )
-            );
-            let decoder = DecoderImpl::Vapoursynth(VapoursynthDecoder::from_script(
-                &script,
-                HashMap::new(),
-                None,
-            )?);
-            let video_details = decoder.video_details()?;
-            return Ok(Decoder {
-                decoder,
-                video_details,
-                frames_read: 0,
-                config: DecoderConfig::default(),
-            });
+            let _ = url;
+            Err(DecoderError::NoDecoder)
         }
+    }
 
-        Err(DecoderError::NoDecoder)
+    /// Creates a new FFMS2 decoder for a file, reporting indexing progress.
+    ///
+    /// Indexing large files can take a while; `progress` is called periodically with the
+    /// number of bytes indexed so far and the total size of the file, e.g. to drive a
+    /// progress bar. It is not called when an existing `.ffindex` file is reused.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`from_file_with_backend`](Self::from_file_with_backend) with
+    /// [`Backend::Ffms2`].
+    #[inline]
+    #[cfg(feature = "ffms2")]
+    pub fn from_file_with_index_progress<P: AsRef<Path>, F: FnMut(u64, u64)>(
+        input: P,
+        progress: F,
+    ) -> Result<Decoder, DecoderError> {
+        let path = input.as_ref();
+        let mut decoder = Decoder::from_decoder_impl(DecoderImpl::Ffms2(
+            Ffms2Decoder::new_with_progress(path, None, progress)?,
+        ))?;
+        decoder.source = Some(Source::File {
+            path: path.to_path_buf(),
+            backend: Backend::Ffms2,
+        });
+        Ok(decoder)
     }
 
     /// Creates a new decoder from a VapourSynth script string.
@@ -261,17 +1098,15 @@ clip.set_output()
     #[cfg(feature = "vapoursynth")]
     pub fn from_script(
         script: &str,
-        variables: HashMap<VariableName, VariableValue>,
+        variables: HashMap<VariableName, String>,
     ) -> Result<Decoder, DecoderError> {
-        let dec = VapoursynthDecoder::from_script(script, variables, None)?;
-        let decoder = DecoderImpl::Vapoursynth(dec);
-        let video_details = decoder.video_details()?;
-        Ok(Decoder {
-            decoder,
-            video_details,
-            frames_read: 0,
-            config: DecoderConfig::default(),
-        })
+        let dec = VapoursynthDecoder::from_script_with_variables(script, variables.clone())?;
+        let mut decoder = Decoder::from_decoder_impl(DecoderImpl::Vapoursynth(dec))?;
+        decoder.source = Some(Source::Script {
+            script: script.to_string(),
+            variables,
+        });
+        Ok(decoder)
     }
 
     /// Creates a decoder that reads Y4M data from stdin.
@@ -279,29 +1114,162 @@ clip.set_output()
     /// # Errors
     ///
     /// Returns [`DecoderError::EndOfFile`] if stdin is empty,
-    /// [`DecoderError::GenericDecodeError`] if the Y4M header is invalid.
+    /// [`DecoderError::InvalidHeader`] if the Y4M header is invalid.
     #[inline]
     pub fn from_stdin() -> Result<Decoder, DecoderError> {
         // We can only support y4m for this
-        let reader = BufReader::new(stdin());
-        let decoder = DecoderImpl::Y4m(y4m::decode(Box::new(reader) as Box<dyn Read>).map_err(
-            |e| match e {
-                y4m::Error::EOF => DecoderError::EndOfFile,
-                _ => DecoderError::GenericDecodeError {
-                    cause: e.to_string(),
-                },
-            },
-        )?);
-        let video_details: VideoDetails = decoder.video_details()?;
-        Ok(Decoder {
-            decoder,
-            video_details,
-            frames_read: 0,
-            config: DecoderConfig::default(),
-        })
+        Decoder::from_reader(BufReader::new(stdin()))
     }
 
-    /// Creates a decoder from a specific [`DecoderImpl`] variant, bypassing auto-detection.
+    /// Creates a decoder that reads Y4M data from an in-memory buffer.
+    ///
+    /// Unlike [`from_reader`](Self::from_reader), the decoder keeps the whole buffer, which
+    /// lets [`seek_to_frame`](Self::seek_to_frame) jump to any frame.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecoderError::EndOfFile`] if `data` is empty,
+    /// [`DecoderError::InvalidHeader`] if the Y4M header is invalid.
+    #[inline]
+    pub fn from_bytes(data: Vec<u8>) -> Result<Decoder, DecoderError> {
+        Decoder::from_in_memory_y4m(Source::Bytes(data.into()))
+    }
+
+    /// Creates a decoder that reads a Y4M file through a memory map.
+    ///
+    /// This avoids copying through a read buffer and, like [`from_bytes`](Self::from_bytes),
+    /// lets [`seek_to_frame`](Self::seek_to_frame) jump to any frame. If the file is
+    /// truncated while mapped, reads fail with [`DecoderError::Io`] rather than crashing.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecoderError::Io`] if the file cannot be opened or mapped,
+    /// [`DecoderError::EndOfFile`] if it is empty,
+    /// [`DecoderError::InvalidHeader`] if the Y4M header is invalid.
+    #[inline]
+    #[cfg(feature = "memmap")]
+    pub fn from_file_mmap<P: AsRef<Path>>(input: P) -> Result<Decoder, DecoderError> {
+        Decoder::from_in_memory_y4m(Source::Mapped(Rc::new(mmap::MappedFile::open(
+            input.as_ref(),
+        )?)))
+    }
+
+    fn from_in_memory_y4m(source: Source) -> Result<Decoder, DecoderError> {
+        let reader = source
+            .y4m_reader()
+            .ok_or(DecoderError::UnsupportedDecoder)?;
+        let mut decoder =
+            Decoder::from_decoder_impl(DecoderImpl::Y4mSeekable(SeekableY4mDecoder::new(reader)?))?;
+        decoder.source = Some(source);
+        Ok(decoder)
+    }
+
+    /// Creates a decoder that reads Y4M data from a seekable reader, such as a file or an
+    /// in-memory [`Cursor`].
+    ///
+    /// Unlike [`from_reader`](Self::from_reader), this lets
+    /// [`seek_to_frame`](Self::seek_to_frame) jump to any frame by repositioning the
+    /// reader. The reader is used as-is; wrap unbuffered sources in a [`BufReader`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecoderError::EndOfFile`] if the reader is empty,
+    /// [`DecoderError::InvalidHeader`] if the Y4M header is invalid, and
+    /// [`DecoderError::Io`] if the reader fails.
+    #[inline]
+    pub fn from_seekable_reader<R: Read + Seek + 'static>(
+        reader: R,
+    ) -> Result<Decoder, DecoderError> {
+        Decoder::from_decoder_impl(DecoderImpl::Y4mSeekable(SeekableY4mDecoder::new(
+            Box::new(reader),
+        )?))
+    }
+
+    /// Creates a decoder that reads Y4M data from any reader, such as an in-memory
+    /// buffer, a socket, or a decompressor.
+    ///
+    /// Decoders created this way can only seek forward; use
+    /// [`from_seekable_reader`](Self::from_seekable_reader) for readers that implement
+    /// [`Seek`].
+    ///
+    /// The reader is used as-is; wrap unbuffered sources in a [`BufReader`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecoderError::EndOfFile`] if the reader is empty,
+    /// [`DecoderError::InvalidHeader`] if the Y4M header is invalid.
+    #[inline]
+    pub fn from_reader<R: Read + 'static>(reader: R) -> Result<Decoder, DecoderError> {
+        Decoder::from_decoder_impl(DecoderImpl::Y4m(helpers::y4m::open(Box::new(reader))?))
+    }
+
+    /// Creates a decoder for a headerless planar YUV file.
+    ///
+    /// Raw files carry no metadata, so the frame layout is taken from `details`.
+    /// The frame count is derived from the file size. See [`RawYuvDecoder`] for the
+    /// expected plane layout.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecoderError::Io`] if the file cannot be opened,
+    /// [`DecoderError::GenericDecodeError`] if `details` describes a zero-sized frame.
+    #[inline]
+    pub fn from_raw_yuv<P: AsRef<Path>>(
+        input: P,
+        details: VideoDetails,
+    ) -> Result<Decoder, DecoderError> {
+        let mut decoder = Decoder::from_decoder_impl(DecoderImpl::RawYuv(RawYuvDecoder::new(
+            input.as_ref(),
+            details,
+        )?))?;
+        decoder.source = Some(Source::RawYuv {
+            path: input.as_ref().to_path_buf(),
+            details,
+        });
+        Ok(decoder)
+    }
+
+    /// Reads the video details of a file as cheaply as each backend allows, without keeping
+    /// a decoder open.
+    ///
+    /// Y4M only parses the stream header. FFmpeg reads the stream parameters without
+    /// decoding threads or frames. VapourSynth evaluates the script (which for non-`.vpy`
+    /// inputs still indexes the file through FFMS2) but does not render any frame, so
+    /// chroma siting, sample aspect ratio, and HDR metadata keep their defaults. FFMS2 must
+    /// index the file, so probing it costs the same as opening it.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`from_file`](Self::from_file).
+    #[inline]
+    pub fn probe<P: AsRef<Path>>(input: P) -> Result<VideoDetails, DecoderError> {
+        let input = input.as_ref();
+        let backend = match Backend::from_extension(input) {
+            Some(backend) => backend,
+            None => {
+                let mut reader = BufReader::new(File::open(input)?);
+                if reader.fill_buf()?.starts_with(Y4M_MAGIC) {
+                    return helpers::y4m::get_video_details(&helpers::y4m::open(Box::new(reader))?);
+                }
+                Backend::try_from(input)?
+            }
+        };
+
+        match backend {
+            Backend::Y4m => helpers::y4m::get_video_details(&helpers::y4m::open(Box::new(
+                BufReader::new(File::open(input)?),
+            ))?),
+            #[cfg(feature = "ffmpeg")]
+            Backend::Ffmpeg => FfmpegDecoder::probe(input),
+            #[cfg(feature = "vapoursynth")]
+            Backend::Vapoursynth => open_vapoursynth_file(input)?.probe_video_details(),
+            _ => {
+                Decoder::from_file_with_backend(input, backend).map(|decoder| decoder.video_details)
+            }
+        }
+    }
+
+    /// Creates a decoder from a specific [`DecoderImpl`] variant, bypassing auto-detection.
     ///
     /// Prefer [`from_file`](Self::from_file), `from_script`, or
     /// [`from_stdin`](Self::from_stdin) unless you need direct backend control.
@@ -310,16 +1278,90 @@ clip.set_output()
     ///
     /// Returns [`DecoderError`] if video metadata cannot be extracted from the implementation.
     #[inline]
-    pub fn from_decoder_impl(decoder_impl: DecoderImpl) -> Result<Decoder, DecoderError> {
+    pub fn from_decoder_impl(mut decoder_impl: DecoderImpl) -> Result<Decoder, DecoderError> {
         let video_details = decoder_impl.video_details()?;
         Ok(Decoder {
             decoder: decoder_impl,
             video_details,
             frames_read: 0,
             config: DecoderConfig::default(),
+            crop: None,
+            total_crop: None,
+            normalized: None,
+            auto_rotation: None,
+            lookahead: None,
+            frame_cache: None,
+            eof_at: None,
+            source: None,
         })
     }
 
+    /// Opens the same source again as an independent decoder positioned at frame 0.
+    ///
+    /// Works for decoders created from a file, a URL, a script, raw YUV, or
+    /// [`from_bytes`](Self::from_bytes). The [`DecoderConfig`], crops made with
+    /// [`set_crop`](Self::set_crop), metadata patched with
+    /// [`override_video_details`](Self::override_video_details), and the lookahead window
+    /// and frame cache sizes are carried over; settings made directly on the backend, such
+    /// as through [`decoder_impl_mut`](Self::decoder_impl_mut), are not.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecoderError::UnsupportedDecoder`] for decoders reading from stdin, a
+    /// reader, or a [`DecoderImpl`], otherwise the same errors as the original constructor.
+    #[inline]
+    pub fn try_reopen(&self) -> Result<Decoder, DecoderError> {
+        let mut decoder = match self.source.as_ref() {
+            Some(Source::File { path, backend }) => {
+                Decoder::from_file_with_backend(path, *backend)?
+            }
+            Some(Source::RawYuv { path, details }) => Decoder::from_raw_yuv(path, *details)?,
+            #[cfg(feature = "ffmpeg")]
+            Some(Source::Url(url)) => Decoder::from_url(url)?,
+            #[cfg(feature = "vapoursynth")]
+            Some(Source::Script { script, variables }) => {
+                Decoder::from_script(script, variables.clone())?
+            }
+            Some(Source::Bytes(data)) => {
+                Decoder::from_in_memory_y4m(Source::Bytes(Rc::clone(data)))?
+            }
+            #[cfg(feature = "memmap")]
+            Some(Source::Mapped(file)) => {
+                Decoder::from_in_memory_y4m(Source::Mapped(Rc::clone(file)))?
+            }
+            None => return Err(DecoderError::UnsupportedDecoder),
+        };
+        decoder.config = self.config;
+        if let Some(crop) = self.total_crop {
+            decoder.set_crop(crop.left, crop.top, crop.right, crop.bottom)?;
+        }
+        if let Some(normalized) = self.normalized {
+            decoder.set_normalized_output(
+                normalized.width,
+                normalized.height,
+                normalized.bit_depth,
+                normalized.chroma_sampling,
+            )?;
+        }
+        if self.auto_rotation.is_some() {
+            decoder.set_auto_rotate(true)?;
+        }
+        decoder.video_details = VideoDetails {
+            width: decoder.video_details.width,
+            height: decoder.video_details.height,
+            bit_depth: decoder.video_details.bit_depth,
+            chroma_sampling: decoder.video_details.chroma_sampling,
+            ..self.video_details
+        };
+        if let Some(lookahead) = &self.lookahead {
+            decoder.enable_lookahead(lookahead.capacity());
+        }
+        if let Some(frame_cache) = &self.frame_cache {
+            decoder.enable_frame_cache(frame_cache.capacity());
+        }
+        Ok(decoder)
+    }
+
     /// Returns the video metadata detected during initialization.
     #[inline]
     #[must_use]
@@ -327,195 +1369,2629 @@ clip.set_output()
         &self.video_details
     }
 
+    /// Corrects mis-detected metadata, such as a wrong frame rate in a Y4M header, by
+    /// letting `f` patch the video details.
+    ///
+    /// Only metadata can be changed: the width, height, bit depth, and chroma subsampling
+    /// describe the decoded frames and must stay as they are, since frames are laid out
+    /// from them. Use [`set_crop`](Self::set_crop) or
+    /// [`set_normalized_output`](Self::set_normalized_output) to change those instead.
+    /// The details are left untouched if the override is rejected.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecoderError::GenericDecodeError`] if `f` changes the frame layout or
+    /// sets a frame rate that is not positive.
+    #[inline]
+    pub fn override_video_details(
+        &mut self,
+        f: impl FnOnce(&mut VideoDetails),
+    ) -> Result<(), DecoderError> {
+        let mut details = self.video_details;
+        f(&mut details);
+        let current = &self.video_details;
+        if (details.width, details.height) != (current.width, current.height)
+            || details.bit_depth != current.bit_depth
+            || details.chroma_sampling != current.chroma_sampling
+        {
+            return Err(DecoderError::GenericDecodeError {
+                cause: "the frame layout of decoded video cannot be overridden".to_string(),
+                source: None,
+            });
+        }
+        if *details.frame_rate.numer() <= 0 || *details.frame_rate.denom() <= 0 {
+            return Err(DecoderError::GenericDecodeError {
+                cause: format!("invalid frame rate {}", details.frame_rate),
+                source: None,
+            });
+        }
+        self.video_details = details;
+        self.invalidate_buffered_frames();
+        Ok(())
+    }
+
+    /// Returns the index of the frame the next read will return.
+    ///
+    /// This starts at 0 and counts frames read or skipped on every backend. After
+    /// [`seek_to_frame(n)`](Self::seek_to_frame) it is `n`, and after a read that seeks
+    /// first, such as [`seek_video_frame_at`](Self::seek_video_frame_at), it is one past
+    /// the returned frame.
+    #[inline]
+    #[must_use]
+    pub const fn current_frame_index(&self) -> usize {
+        self.frames_read
+    }
+
+    /// Returns the length of the video, e.g. for display in media tools.
+    ///
+    /// FFmpeg uses the duration reported by the container when it has one, which is more
+    /// accurate for variable frame rate video. Otherwise this is
+    /// [`VideoDetails::duration`], so it is `None` if the frame count is unknown.
+    #[inline]
+    #[must_use]
+    pub fn duration(&self) -> Option<Duration> {
+        #[cfg(feature = "ffmpeg")]
+        if let DecoderImpl::Ffmpeg(dec) = &self.decoder
+            && let Some(duration) = dec.container_duration()
+        {
+            return Some(duration);
+        }
+        self.video_details.duration()
+    }
+
+    /// Returns `true` if the source has an audio track, e.g. to decide whether a muxer
+    /// needs to copy audio from it.
+    ///
+    /// This only inspects the container and never decodes audio. FFmpeg and FFMS2 report
+    /// the tracks of the input file. Y4M and raw YUV carry no audio, and VapourSynth
+    /// always reports `false`, since the VSScript API does not expose audio outputs.
+    #[inline]
+    #[must_use]
+    pub fn has_audio(&self) -> bool {
+        match &self.decoder {
+            #[cfg(feature = "ffmpeg")]
+            DecoderImpl::Ffmpeg(dec) => dec.has_audio(),
+            #[cfg(feature = "ffms2")]
+            DecoderImpl::Ffms2(dec) => dec.has_audio(),
+            #[cfg(feature = "vapoursynth")]
+            DecoderImpl::Vapoursynth(_) => false,
+            DecoderImpl::Y4m(_) | DecoderImpl::Y4mSeekable(_) | DecoderImpl::RawYuv(_) => false,
+        }
+    }
+
+    /// Returns the number of bytes allocated for each frame returned by
+    /// [`read_video_frame`](Self::read_video_frame).
+    ///
+    /// This accounts for the padding around every plane set by
+    /// [`set_padding`](Self::set_padding), which dominates for small resolutions, and for
+    /// [`set_luma_only`](Self::set_luma_only). It excludes the few
+    /// bytes of alignment `v_frame` may add to each row and any frames buffered by the
+    /// backend or the lookahead.
+    #[inline]
+    #[must_use]
+    pub fn estimated_memory_per_frame(&self) -> usize {
+        let details = &self.video_details;
+        let padding = self.config.padding;
+        let padded_area = |width: usize, height: usize, x: usize, y: usize| {
+            (width + (padding.left + padding.right) / x)
+                * (height + (padding.top + padding.bottom) / y)
+        };
+        let mut samples = padded_area(details.width, details.height, 1, 1);
+        if !self.config.luma_only
+            && let Some((x, y)) = details.chroma_sampling.subsample_ratio()
+        {
+            let (width, height) = details.plane_dimensions(1);
+            samples += 2 * padded_area(width, height, x.get() as usize, y.get() as usize);
+        }
+        samples * details.bytes_per_sample()
+    }
+
+    /// Sets the padding allocated around each edge of every subsequent frame, in luma
+    /// samples.
+    ///
+    /// Defaults to [`LUMA_PADDING`] on every side, which suits encoders such as rav1e that
+    /// read past the frame edges. Consumers that only look at the picture, such as metric
+    /// tools, can pass `0` to save memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecoderError::UnsupportedFormat`] if an amount is not a multiple of the
+    /// chroma subsampling (e.g. odd for 4:2:0).
+    #[inline]
+    pub fn set_padding(
+        &mut self,
+        left: usize,
+        top: usize,
+        right: usize,
+        bottom: usize,
+    ) -> Result<(), DecoderError> {
+        let padding = Padding {
+            left,
+            top,
+            right,
+            bottom,
+        };
+        check_padding(&self.video_details, padding)?;
+        self.config.padding = padding;
+        self.invalidate_buffered_frames();
+        Ok(())
+    }
+
     /// Sets the decoder to only fetch the luma planes from the video.
     /// This may improve performance for applications that do not need chroma data.
     #[inline]
     pub fn set_luma_only(&mut self, enabled: bool) {
         self.config.luma_only = enabled;
+        self.invalidate_buffered_frames();
     }
 
-    /// Decodes and returns the next video frame.
+    /// Crops the given number of pixels from each edge of every subsequent frame, e.g. to
+    /// remove letterboxing.
     ///
-    /// `T` must match the video's bit depth: `u8` for 8-bit, `u16` for 10–16 bit.
+    /// VapourSynth crops with `std.Crop`; other backends crop in Rust while copying the
+    /// decoded frame. Video details are updated to the cropped size. Crops are cumulative:
+    /// calling this again crops the already-cropped output further.
     ///
     /// # Errors
     ///
-    /// Returns [`DecoderError::EndOfFile`] at end of stream,
-    /// [`DecoderError::GenericDecodeError`] on corrupted data.
-    ///
-    /// Each frame contains uncompressed pixel data; avoid holding frames longer than needed.
+    /// Returns [`DecoderError::UnsupportedFormat`] if an amount is not a multiple of the
+    /// chroma subsampling (e.g. odd for 4:2:0), [`DecoderError::GenericDecodeError`] if
+    /// nothing would be left of the frame, [`DecoderError::UnsupportedDecoder`] if frames
+    /// are already normalized in Rust by [`set_normalized_output`](Self::set_normalized_output)
+    /// or rotated by [`set_auto_rotate`](Self::set_auto_rotate).
     #[inline]
-    pub fn read_video_frame<T: Pixel>(&mut self) -> Result<Frame<T>, DecoderError> {
-        let result = self.decoder.read_video_frame(
-            &self.video_details,
-            #[cfg(any(feature = "ffmpeg", feature = "vapoursynth", feature = "ffms2"))]
-            self.frames_read,
-            self.config.luma_only,
-        );
-        if result.is_ok() {
-            self.frames_read += 1;
+    pub fn set_crop(
+        &mut self,
+        left: usize,
+        top: usize,
+        right: usize,
+        bottom: usize,
+    ) -> Result<(), DecoderError> {
+        if self.normalized_in_rust() || self.auto_rotation.is_some() {
+            return Err(DecoderError::UnsupportedDecoder);
         }
-        result
+        self.invalidate_buffered_frames();
+        let crop = Crop {
+            left,
+            top,
+            right,
+            bottom,
+        };
+        let cropped = cropped_details(&self.video_details, crop)?;
+        let total_crop = self.total_crop.map_or(crop, |total| Crop {
+            left: total.left + left,
+            top: total.top + top,
+            right: total.right + right,
+            bottom: total.bottom + bottom,
+        });
+
+        #[cfg(feature = "vapoursynth")]
+        if let DecoderImpl::Vapoursynth(dec) = &mut self.decoder {
+            dec.set_crop(left, top, right, bottom)?;
+            self.video_details = dec.get_video_details()?;
+            self.total_crop = Some(total_crop);
+            return Ok(());
+        }
+
+        self.crop = Some(match self.crop {
+            Some((previous, source_details)) => (
+                Crop {
+                    left: previous.left + left,
+                    top: previous.top + top,
+                    right: previous.right + right,
+                    bottom: previous.bottom + bottom,
+                },
+                source_details,
+            ),
+            None => (crop, self.video_details),
+        });
+        self.total_crop = Some(total_crop);
+        self.video_details = cropped;
+        Ok(())
     }
 
-    /// Decodes and returns a specific frame by index.
+    /// Converts every subsequent frame to the given resolution, bit depth, and chroma
+    /// subsampling, e.g. to feed sources of any format to code that only handles 8-bit
+    /// 4:2:0.
     ///
-    /// Not all backends support seeking. `T` must match the video's bit depth.
+    /// FFMS2, FFmpeg, and VapourSynth convert natively with a bicubic resizer; call their
+    /// `set_output_format` directly to pick another [`Resizer`]. Y4M and raw
+    /// YUV sources, and any backend after a [`set_crop`](Self::set_crop) applied in Rust,
+    /// are converted in Rust instead: planes are resized bilinearly, which also resamples
+    /// chroma, and samples are rescaled to the new bit depth. Video details are updated to
+    /// the normalized format, so frames must be read with the matching pixel type. Once
+    /// frames are converted in Rust, they can no longer be cropped.
     ///
     /// # Errors
     ///
-    /// Returns [`DecoderError::UnsupportedDecoder`] if the backend cannot seek,
-    /// [`DecoderError::EndOfFile`] past the last frame.
+    /// Returns [`DecoderError::UnsupportedFormat`] if the format is not supported by the
+    /// backend, or for the Rust conversion if a dimension is zero or the bit depth is not
+    /// between 1 and 16 or the [padding](Self::set_padding) is not a multiple of the new
+    /// chroma subsampling, [`DecoderError::UnsupportedDecoder`] if frames are already rotated
+    /// by [`set_auto_rotate`](Self::set_auto_rotate), and otherwise any error from the
+    /// backend.
     #[inline]
-    #[cfg(feature = "vapoursynth")]
-    pub fn get_video_frame<T: Pixel>(
+    pub fn set_normalized_output(
         &mut self,
-        frame_index: usize,
-    ) -> Result<Frame<T>, DecoderError> {
-        self.decoder.get_video_frame(
-            #[cfg(feature = "vapoursynth")]
-            &self.video_details,
-            #[cfg(feature = "vapoursynth")]
-            frame_index,
-            self.config.luma_only,
-        )
+        width: usize,
+        height: usize,
+        bit_depth: u8,
+        chroma_sampling: ChromaSubsampling,
+    ) -> Result<(), DecoderError> {
+        if self.auto_rotation.is_some() {
+            return Err(DecoderError::UnsupportedDecoder);
+        }
+        self.invalidate_buffered_frames();
+        check_padding(
+            &VideoDetails {
+                chroma_sampling,
+                ..self.video_details
+            },
+            self.config.padding,
+        )?;
+        let mut normalized = NormalizedOutput {
+            width,
+            height,
+            bit_depth,
+            chroma_sampling,
+            input: None,
+        };
+        if self.crop.is_none() && !self.normalized_in_rust() {
+            match &mut self.decoder {
+                #[cfg(feature = "ffms2")]
+                DecoderImpl::Ffms2(dec) => {
+                    dec.set_output_format(
+                        width,
+                        height,
+                        bit_depth,
+                        chroma_sampling,
+                        Resizer::Bicubic,
+                    )?;
+                    self.video_details = dec.video_details;
+                    self.normalized = Some(normalized);
+                    return Ok(());
+                }
+                #[cfg(feature = "ffmpeg")]
+                DecoderImpl::Ffmpeg(dec) => {
+                    dec.set_output_format(
+                        width,
+                        height,
+                        bit_depth,
+                        chroma_sampling,
+                        Resizer::Bicubic,
+                    )?;
+                    self.video_details = dec.video_details;
+                    self.normalized = Some(normalized);
+                    return Ok(());
+                }
+                #[cfg(feature = "vapoursynth")]
+                DecoderImpl::Vapoursynth(dec) => {
+                    dec.set_output_format(
+                        width,
+                        height,
+                        bit_depth,
+                        chroma_sampling,
+                        Resizer::Bicubic,
+                    )?;
+                    self.video_details = dec.get_video_details()?;
+                    self.normalized = Some(normalized);
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+
+        if width == 0 || height == 0 || !(1..=16).contains(&bit_depth) {
+            return Err(DecoderError::UnsupportedFormat {
+                fmt: format!("{width}x{height} {bit_depth}-bit output"),
+            });
+        }
+        normalized.input = Some(
+            self.normalized
+                .and_then(|normalized| normalized.input)
+                .unwrap_or(self.video_details),
+        );
+        self.video_details = VideoDetails {
+            width,
+            height,
+            bit_depth: bit_depth as usize,
+            chroma_sampling,
+            ..self.video_details
+        };
+        self.normalized = Some(normalized);
+        Ok(())
     }
 
-    /// Seeks to the given frame index, skipping intermediate frames.
+    /// Rotates every subsequent frame upright according to [`VideoDetails::rotation`], e.g.
+    /// for phone recordings that are stored sideways.
+    ///
+    /// Frames are rotated in Rust after decoding. For quarter turns, the width and height
+    /// in the video details are swapped and the sample aspect ratio is inverted; the
+    /// reported rotation becomes 0. Set up [`set_crop`](Self::set_crop) and
+    /// [`set_normalized_output`](Self::set_normalized_output) before enabling this, as they
+    /// cannot be changed while frames are rotated. Disabled by default.
     ///
     /// # Errors
     ///
-    /// Returns [`DecoderError::UnsupportedDecoder`] if the backend does not support seeking,
-    /// [`DecoderError::EndOfFile`] if the index is past the last frame.
+    /// Returns [`DecoderError::UnsupportedFormat`] for a quarter turn of 4:2:2 video,
+    /// whose chroma would be subsampled vertically once rotated.
     #[inline]
-    #[cfg(any(feature = "vapoursynth", feature = "ffms2"))]
-    pub fn seek_to_frame(&mut self, frame_index: usize) -> Result<(), DecoderError> {
-        match &self.decoder {
-            #[cfg(feature = "vapoursynth")]
-            DecoderImpl::Vapoursynth(_) => {
-                if self
-                    .video_details
-                    .total_frames
-                    .is_some_and(|total_frames| frame_index >= total_frames)
-                {
-                    return Err(DecoderError::EndOfFile);
-                }
-                self.frames_read = frame_index;
-                Ok(())
+    pub fn set_auto_rotate(&mut self, enabled: bool) -> Result<(), DecoderError> {
+        self.invalidate_buffered_frames();
+        match (enabled, self.auto_rotation) {
+            (true, None) if self.video_details.rotation != 0 => {
+                let rotation = self.video_details.rotation;
+                let rotated = rotated_details(&self.video_details, rotation)?;
+                self.auto_rotation = Some((rotation, self.video_details));
+                self.video_details = rotated;
             }
-            #[cfg(feature = "ffms2")]
-            DecoderImpl::Ffms2(_) => {
-                if self
-                    .video_details
-                    .total_frames
-                    .is_some_and(|total_frames| frame_index >= total_frames)
-                {
-                    return Err(DecoderError::EndOfFile);
-                }
-                self.frames_read = frame_index;
-                Ok(())
+            (false, Some((_, unrotated))) => {
+                self.auto_rotation = None;
+                self.video_details = unrotated;
             }
-            _ => Err(DecoderError::UnsupportedDecoder),
+            _ => {}
         }
+        Ok(())
     }
 
-    /// Returns a mutable reference to the underlying FFmpeg decoder, or `None` otherwise.
+    /// Returns `true` if [`set_normalized_output`](Self::set_normalized_output) converts
+    /// frames in Rust rather than in the backend.
+    fn normalized_in_rust(&self) -> bool {
+        self.normalized
+            .is_some_and(|normalized| normalized.input.is_some())
+    }
+
+    /// Decodes and returns the next video frame.
+    ///
+    /// `T` must match the video's bit depth: `u8` for 8-bit, `u16` for 10–16 bit.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecoderError::PixelTypeMismatch`] if `T` does not match
+    /// [`VideoDetails::bytes_per_sample`], [`DecoderError::EndOfFile`] at end of stream,
+    /// [`DecoderError::GenericDecodeError`] on corrupted data. Once the end of the stream
+    /// is reached, later reads keep returning [`DecoderError::EndOfFile`] without touching
+    /// the backend, until seeking back.
+    ///
+    /// Each frame contains uncompressed pixel data; avoid holding frames longer than needed.
     #[inline]
-    #[cfg(feature = "ffmpeg")]
-    pub fn get_ffmpeg_impl(&mut self) -> Option<&mut FfmpegDecoder> {
-        match &mut self.decoder {
-            DecoderImpl::Ffmpeg(dec) => Some(dec),
-            _ => None,
+    pub fn read_video_frame<T: Pixel>(&mut self) -> Result<Frame<T>, DecoderError> {
+        self.read_video_frame_and_meta().map(|(frame, _)| frame)
+    }
+
+    /// Same as [`read_video_frame`](Self::read_video_frame), also returning what the
+    /// backend reported about the frame when it was decoded.
+    fn read_video_frame_and_meta<T: Pixel>(
+        &mut self,
+    ) -> Result<(Frame<T>, FrameMeta), DecoderError> {
+        check_pixel_type::<T>(&self.video_details)?;
+        if let Some(lookahead) = &self.lookahead
+            && let Some(buffered) = lookahead.get(self.frames_read)?
+        {
+            self.frames_read += 1;
+            return Ok(buffered);
+        }
+        if self.supports_seeking()
+            && let Some(frame_cache) = &mut self.frame_cache
+            && let Some(cached) = frame_cache.get(self.frames_read)?
+        {
+            self.frames_read += 1;
+            return Ok(cached);
         }
+        self.check_end_of_stream()?;
+
+        let details = self
+            .auto_rotation
+            .map_or(self.video_details, |(_, unrotated)| unrotated);
+        let result = match self.normalized.and_then(|normalized| normalized.input) {
+            Some(input) if input.bit_depth > 8 => self
+                .decode_frame::<u16>(&input)
+                .and_then(|frame| convert_frame(&frame, &input, &details, self.config)),
+            Some(input) => self
+                .decode_frame::<u8>(&input)
+                .and_then(|frame| convert_frame(&frame, &input, &details, self.config)),
+            None => self.decode_frame(&details),
+        };
+        let result = match self.auto_rotation {
+            Some((rotation, _)) => result
+                .and_then(|frame| rotate_frame(&frame, &self.video_details, rotation, self.config)),
+            None => result,
+        };
+        let meta = self.backend_frame_meta();
+        if let Ok(frame) = &result {
+            if let Some(lookahead) = &mut self.lookahead {
+                lookahead.push(self.frames_read, frame.clone(), meta);
+            }
+            if let Some(frame_cache) = &mut self.frame_cache {
+                frame_cache.insert(self.frames_read, frame.clone(), meta);
+            }
+            self.frames_read += 1;
+        }
+        self.track_end_of_stream(result.map(|frame| (frame, meta)))
     }
 
-    /// Returns a mutable reference to the underlying FFMS2 decoder, or `None` otherwise.
-    #[inline]
-    #[cfg(feature = "ffms2")]
-    pub fn get_ffms2_impl(&mut self) -> Option<&mut Ffms2Decoder> {
-        match &mut self.decoder {
-            DecoderImpl::Ffms2(dec) => Some(dec),
-            _ => None,
+    /// Returns what the backend reported about the frame it decoded last.
+    fn backend_frame_meta(&self) -> FrameMeta {
+        match &self.decoder {
+            #[cfg(feature = "ffmpeg")]
+            DecoderImpl::Ffmpeg(dec) => FrameMeta {
+                timestamp: dec.last_timestamp(),
+                flags: Some(dec.last_frame_flags()),
+            },
+            #[cfg(feature = "vapoursynth")]
+            DecoderImpl::Vapoursynth(dec) => FrameMeta {
+                timestamp: None,
+                flags: Some(dec.last_frame_flags()),
+            },
+            _ => FrameMeta::default(),
         }
     }
 
-    /// Returns a mutable reference to the underlying VapourSynth decoder, or `None` otherwise.
-    #[inline]
-    #[cfg(feature = "vapoursynth")]
-    pub fn get_vapoursynth_impl(&mut self) -> Option<&mut VapoursynthDecoder> {
-        match &mut self.decoder {
-            DecoderImpl::Vapoursynth(dec) => Some(dec),
-            _ => None,
+    /// Returns [`DecoderError::EndOfFile`] if the backend already reported the end of the
+    /// stream at or before the current frame.
+    fn check_end_of_stream(&self) -> Result<(), DecoderError> {
+        if self.eof_at.is_some_and(|eof_at| self.frames_read >= eof_at) {
+            return Err(DecoderError::EndOfFile);
         }
+        Ok(())
     }
-}
 
-/// Internal enum representing the active decoder backend.
-///
-/// The variant is selected automatically during [`Decoder`] initialization.
-pub enum DecoderImpl {
-    /// Y4M format parser (always available).
-    Y4m(Y4mDecoder<Box<dyn Read>>),
+    /// Remembers where the backend reported the end of the stream, if `result` says so.
+    fn track_end_of_stream<R>(
+        &mut self,
+        result: Result<R, DecoderError>,
+    ) -> Result<R, DecoderError> {
+        if result.as_ref().is_err_and(DecoderError::is_eof) {
+            self.eof_at = Some(self.frames_read);
+        }
+        result
+    }
 
-    /// VapourSynth-based decoder (requires `vapoursynth` feature).
-    #[cfg(feature = "vapoursynth")]
-    Vapoursynth(VapoursynthDecoder),
+    /// Decodes the next frame from the backend and applies any crop made in Rust, producing
+    /// a frame described by `details`.
+    fn decode_frame<T: Pixel>(&mut self, details: &VideoDetails) -> Result<Frame<T>, DecoderError> {
+        self.sync_backend_position()?;
+        let source_details = self
+            .crop
+            .as_ref()
+            .map_or(details, |(_, source_details)| source_details);
+        self.decoder
+            .read_video_frame(
+                source_details,
+                #[cfg(any(feature = "vapoursynth", feature = "ffms2"))]
+                self.frames_read,
+                self.config,
+            )
+            .and_then(|frame| match self.crop {
+                Some((crop, _)) => crop_frame(&frame, details, crop, self.config),
+                None => Ok(frame),
+            })
+    }
 
-    /// FFmpeg-based decoder (requires `ffmpeg` feature).
-    #[cfg(feature = "ffmpeg")]
-    Ffmpeg(FfmpegDecoder),
+    /// Decodes the next video frame into a caller-owned frame, so that one allocation can
+    /// be reused across a whole clip.
+    ///
+    /// `frame` must have the dimensions given by [`get_video_details`](Self::get_video_details).
+    /// Planes missing from `frame` are skipped, so a frame built without chroma planes only
+    /// receives luma. When cropping with [`set_crop`](Self::set_crop) outside of
+    /// VapourSynth, the uncropped frame is still decoded into a temporary allocation,
+    /// and with [`enable_lookahead`](Self::enable_lookahead), a conversion made in Rust
+    /// by [`set_normalized_output`](Self::set_normalized_output), or
+    /// [`set_auto_rotate`](Self::set_auto_rotate) frames are copied in.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecoderError::GenericDecodeError`] if the planes of `frame` do not match
+    /// the video details, otherwise the same errors as
+    /// [`read_video_frame`](Self::read_video_frame).
+    #[inline]
+    pub fn read_video_frame_into<T: Pixel>(
+        &mut self,
+        frame: &mut Frame<T>,
+    ) -> Result<(), DecoderError> {
+        check_pixel_type::<T>(&self.video_details)?;
+        check_frame_geometry(frame, &self.video_details)?;
+        if self.lookahead.is_some() || self.normalized_in_rust() || self.auto_rotation.is_some() {
+            let luma_only = frame.u_plane.is_none();
+            *frame = self.read_video_frame()?;
+            if luma_only {
+                frame.u_plane = None;
+                frame.v_plane = None;
+            }
+            return Ok(());
+        }
+        self.check_end_of_stream()?;
+        self.sync_backend_position()?;
+        let result = match &self.crop {
+            Some((crop, source_details)) => self
+                .decoder
+                .read_video_frame::<T>(
+                    source_details,
+                    #[cfg(any(feature = "vapoursynth", feature = "ffms2"))]
+                    self.frames_read,
+                    DecoderConfig {
+                        luma_only: frame.u_plane.is_none(),
+                        ..self.config
+                    },
+                )
+                .map(|source| {
+                    crop_frame_into(&source, *crop, source_details.chroma_sampling, frame);
+                }),
+            None => self.decoder.read_video_frame_into(
+                #[cfg(any(feature = "vapoursynth", feature = "ffms2"))]
+                self.frames_read,
+                frame,
+            ),
+        };
+        self.track_end_of_stream(result)?;
+        self.frames_read += 1;
+        Ok(())
+    }
 
-    /// FFMS2-based decoder (requires `ffms2` feature).
-    #[cfg(feature = "ffms2")]
-    Ffms2(Ffms2Decoder),
-}
+    /// Decodes all remaining frames into memory.
+    ///
+    /// The vector is pre-sized from [`VideoDetails::total_frames`] when it is known. Every
+    /// frame is fully uncompressed, so this is only suitable for short clips: one minute of
+    /// 1080p 4:2:0 8-bit video at 24 fps already takes about 4.5 GB.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`read_video_frame`](Self::read_video_frame), except that reaching the end
+    /// of the stream is not an error. Frames decoded before an error are discarded.
+    #[inline]
+    pub fn read_all_frames<T: Pixel>(&mut self) -> Result<Vec<Frame<T>>, DecoderError> {
+        let mut frames = Vec::with_capacity(
+            self.video_details
+                .total_frames
+                .map_or(0, |total| total.saturating_sub(self.frames_read)),
+        );
+        loop {
+            match self.read_video_frame() {
+                Ok(frame) => frames.push(frame),
+                Err(DecoderError::EndOfFile) => return Ok(frames),
+                Err(e) => return Err(e),
+            }
+        }
+    }
 
-impl DecoderImpl {
-    pub(crate) fn video_details(&self) -> Result<VideoDetails, DecoderError> {
-        match self {
-            Self::Y4m(dec) => Ok(helpers::y4m::get_video_details(dec)),
-            #[cfg(feature = "vapoursynth")]
-            Self::Vapoursynth(dec) => dec.get_video_details(),
+    /// Decodes the next video frame and converts it to an 8-bit RGB image.
+    ///
+    /// Intended for previews and thumbnails. Colors are converted as in
+    /// [`read_rgb24`](Self::read_rgb24).
+    ///
+    /// # Errors
+    ///
+    /// Same as [`read_video_frame`](Self::read_video_frame).
+    #[inline]
+    #[cfg(feature = "image")]
+    pub fn read_image(&mut self) -> Result<image::RgbImage, DecoderError> {
+        if self.video_details.bit_depth > 8 {
+            let frame = self.read_video_frame::<u16>()?;
+            Ok(preview::frame_to_rgb(&frame, &self.video_details))
+        } else {
+            let frame = self.read_video_frame::<u8>()?;
+            Ok(preview::frame_to_rgb(&frame, &self.video_details))
+        }
+    }
+
+    /// Decodes the next video frame along with its presentation timestamp.
+    ///
+    /// FFmpeg reports the container's timestamp in the stream time base when packets keep
+    /// their timestamps, i.e. with `FfmpegDecoder::set_sequential_decoding` or
+    /// `FfmpegDecoder::set_strict_frame_rate`. Other backends, and FFmpeg streams without
+    /// timestamps, assume a constant frame rate: the PTS is the frame index and the time
+    /// base is the duration of one frame.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecoderError::GenericDecodeError`] if the frame has no timestamp and the
+    /// frame rate is zero, so none can be derived; the frame is still consumed. Otherwise
+    /// the same errors as [`read_video_frame`](Self::read_video_frame).
+    #[inline]
+    pub fn read_video_frame_with_pts<T: Pixel>(
+        &mut self,
+    ) -> Result<(Frame<T>, FrameTimestamp), DecoderError> {
+        let frame_index = self.frames_read;
+        let (frame, meta) = self.read_video_frame_and_meta::<T>()?;
+
+        let timestamp = match meta.timestamp {
+            Some(timestamp) => timestamp,
+            None if *self.video_details.frame_rate.numer() == 0 => {
+                return Err(DecoderError::GenericDecodeError {
+                    cause: "cannot derive a timestamp from a zero frame rate".to_string(),
+                    source: None,
+                });
+            }
+            None => FrameTimestamp {
+                pts: frame_index as i64,
+                time_base: self.video_details.frame_rate.recip(),
+            },
+        };
+        Ok((frame, timestamp))
+    }
+
+    /// Decodes the next video frame along with its keyframe and scene change flags.
+    ///
+    /// FFmpeg reports whether the decoded picture is a keyframe. VapourSynth reads the
+    /// `_PictType` and `_SceneChangePrev` frame properties. See [`FrameFlags`] for the
+    /// other backends.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`read_video_frame`](Self::read_video_frame).
+    #[inline]
+    pub fn read_video_frame_with_flags<T: Pixel>(
+        &mut self,
+    ) -> Result<(Frame<T>, FrameFlags), DecoderError> {
+        let frame_index = self.frames_read;
+        let (frame, meta) = self.read_video_frame_and_meta::<T>()?;
+
+        let flags = meta.flags.unwrap_or(FrameFlags {
+            is_keyframe: frame_index == 0,
+            is_scene_change: false,
+        });
+        Ok((frame, flags))
+    }
+
+    /// Decodes up to `count` frames in one call, which amortizes the per-call overhead for
+    /// low-resolution, high-frame-rate video.
+    ///
+    /// With VapourSynth, all frames are requested up front, so the script filters them in
+    /// parallel. Fewer than `count` frames are returned, without an error, if the stream
+    /// ends first.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`read_video_frame`](Self::read_video_frame), except for
+    /// [`DecoderError::EndOfFile`]. Frames decoded before an error are dropped, but still
+    /// counted as read.
+    #[inline]
+    pub fn read_video_frames<T: Pixel>(
+        &mut self,
+        count: usize,
+    ) -> Result<Vec<Frame<T>>, DecoderError> {
+        check_pixel_type::<T>(&self.video_details)?;
+        #[cfg(feature = "vapoursynth")]
+        if let DecoderImpl::Vapoursynth(dec) = &mut self.decoder {
+            dec.request_frames(self.frames_read, count)?;
+        }
+        let remaining = self
+            .video_details
+            .total_frames
+            .map_or(count, |total_frames| {
+                count.min(total_frames.saturating_sub(self.frames_read))
+            });
+        let mut frames = Vec::with_capacity(remaining);
+        while frames.len() < count {
+            match self.read_video_frame() {
+                Ok(frame) => frames.push(frame),
+                Err(DecoderError::EndOfFile) => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(frames)
+    }
+
+    /// Decodes the next video frame as an 8-bit frame, whatever the source's bit depth, e.g.
+    /// for previews of high bit depth video.
+    ///
+    /// 8-bit sources are read as is. Deeper sources are decoded natively, and each sample
+    /// is right-shifted by `bit_depth - 8` with rounding. Unlike
+    /// [`set_normalized_output`](Self::set_normalized_output), this only reduces the bit
+    /// depth, without resampling, and leaves [`get_video_details`](Self::get_video_details)
+    /// unchanged.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`read_video_frame`](Self::read_video_frame).
+    #[inline]
+    pub fn read_video_frame_as8(&mut self) -> Result<Frame<u8>, DecoderError> {
+        if self.video_details.bit_depth <= 8 {
+            return self.read_video_frame();
+        }
+        let frame = self.read_video_frame::<u16>()?;
+        reduce_to_8bit(&frame, &self.video_details, self.config)
+    }
+
+    /// Advances past the next `count` frames without returning them.
+    ///
+    /// This avoids building a [`Frame`] for frames the caller would throw away.
+    /// Seekable backends (VapourSynth, FFMS2, and seekable Y4M sources) and raw YUV files
+    /// jump directly; Y4M streams and FFmpeg still read through the skipped frames, but
+    /// without copying them.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecoderError::EndOfFile`] if the stream ends before `count` frames were
+    /// skipped. The decoder is then left at the end of the stream, so that
+    /// [`current_frame_index`](Self::current_frame_index) counts the frames that were
+    /// skipped, and later reads return [`DecoderError::EndOfFile`] as well.
+    #[inline]
+    pub fn skip_frames(&mut self, mut count: usize) -> Result<(), DecoderError> {
+        if let Some(lookahead) = &self.lookahead {
+            while count > 0 && lookahead.contains(self.frames_read) {
+                self.frames_read += 1;
+                count -= 1;
+            }
+            if count == 0 {
+                return Ok(());
+            }
+        }
+        let result = self
+            .check_end_of_stream()
+            .and_then(|()| self.skip_decoded_frames(count));
+        let result = self.track_end_of_stream(result);
+        // Skipped frames are never buffered, so the window restarts after them
+        if let Some(lookahead) = &mut self.lookahead {
+            lookahead.reset(self.frames_read);
+        }
+        result
+    }
+
+    fn skip_decoded_frames(&mut self, count: usize) -> Result<(), DecoderError> {
+        self.sync_backend_position()?;
+        let target = self.frames_read.saturating_add(count);
+        // Backends that jump stop at the last frame, like those reading through the frames
+        let end = self
+            .video_details
+            .total_frames
+            .map_or(target, |total_frames| {
+                target.min(total_frames.max(self.frames_read))
+            });
+        match &mut self.decoder {
+            DecoderImpl::Y4m(dec) => {
+                for _ in 0..count {
+                    helpers::y4m::skip_video_frame(dec)?;
+                    self.frames_read += 1;
+                }
+            }
+            DecoderImpl::Y4mSeekable(dec) => {
+                let result = dec.seek_to_frame(target);
+                self.frames_read = dec.position();
+                // Skipping up to the end of the stream is not an error, only skipping past it
+                if self.frames_read < target {
+                    result?;
+                }
+            }
+            DecoderImpl::RawYuv(dec) => {
+                dec.skip_frames(end - self.frames_read)?;
+                self.frames_read = end;
+            }
             #[cfg(feature = "ffmpeg")]
-            Self::Ffmpeg(dec) => Ok(dec.video_details),
+            DecoderImpl::Ffmpeg(dec) => {
+                for _ in 0..count {
+                    dec.skip_frame()?;
+                    self.frames_read += 1;
+                }
+            }
+            #[cfg(feature = "vapoursynth")]
+            DecoderImpl::Vapoursynth(_) => self.frames_read = end,
             #[cfg(feature = "ffms2")]
-            Self::Ffms2(dec) => Ok(dec.video_details),
+            DecoderImpl::Ffms2(_) => self.frames_read = end,
         }
+        if end < target {
+            return Err(DecoderError::EndOfFile);
+        }
+        Ok(())
     }
 
-    pub(crate) fn read_video_frame<T: Pixel>(
+    /// Decodes and returns a specific frame by index.
+    ///
+    /// Not all backends support seeking. `T` must match the video's bit depth.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecoderError::PixelTypeMismatch`] if `T` does not match
+    /// [`VideoDetails::bytes_per_sample`], [`DecoderError::UnsupportedDecoder`] if the
+    /// backend cannot seek, [`DecoderError::EndOfFile`] past the last frame.
+    #[inline]
+    #[cfg(feature = "vapoursynth")]
+    pub fn get_video_frame<T: Pixel>(
         &mut self,
-        cfg: &VideoDetails,
-        #[cfg(any(feature = "ffmpeg", feature = "vapoursynth", feature = "ffms2"))]
         frame_index: usize,
-        luma_only: bool,
     ) -> Result<Frame<T>, DecoderError> {
-        match self {
-            Self::Y4m(dec) => {
-                helpers::y4m::read_video_frame::<Box<dyn Read>, T>(dec, cfg, luma_only)
-            }
+        check_pixel_type::<T>(&self.video_details)?;
+        self.decoder.get_video_frame(
             #[cfg(feature = "vapoursynth")]
-            Self::Vapoursynth(dec) => dec.read_video_frame::<T>(cfg, frame_index, luma_only),
-            #[cfg(feature = "ffmpeg")]
-            Self::Ffmpeg(dec) => dec.read_video_frame::<T>(frame_index, luma_only),
-            #[cfg(feature = "ffms2")]
-            Self::Ffms2(dec) => dec.read_video_frame::<T>(frame_index, luma_only),
+            &self.video_details,
+            #[cfg(feature = "vapoursynth")]
+            frame_index,
+            self.config,
+        )
+    }
+
+    /// Returns the VapourSynth core version and loaded plugins.
+    ///
+    /// See [`VapoursynthDecoder::core_info`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecoderError::UnsupportedDecoder`] if the backend is not VapourSynth,
+    /// [`DecoderError::VapoursynthInternalError`] if the core cannot be accessed.
+    #[inline]
+    #[cfg(feature = "vapoursynth")]
+    pub fn vapoursynth_core_info(&self) -> Result<CoreInfo, DecoderError> {
+        match &self.decoder {
+            DecoderImpl::Vapoursynth(dec) => dec.core_info(),
+            _ => Err(DecoderError::UnsupportedDecoder),
         }
     }
 
+    /// Decodes the next video frame along with all of its VapourSynth frame properties.
+    ///
+    /// This exposes per-frame metadata set by the script, such as `_Matrix`,
+    /// `_SceneChangeNext`, or values written by analysis filters. Properties holding clips,
+    /// frames, or functions are left out; see [`PropValue`].
+    ///
+    /// The frame is always rendered by the script, never taken from the
+    /// [`enable_frame_cache`](Self::enable_frame_cache) cache, since the cache does not
+    /// keep properties.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecoderError::UnsupportedDecoder`] if the backend is not VapourSynth, or
+    /// if a crop or conversion made in Rust, auto-rotation, or lookahead is enabled, since
+    /// the properties describe the frame as the script output it. Otherwise the same errors
+    /// as [`read_video_frame`](Self::read_video_frame).
+    #[inline]
     #[cfg(feature = "vapoursynth")]
-    pub(crate) fn get_video_frame<T: Pixel>(
+    pub fn read_video_frame_with_props<T: Pixel>(
         &mut self,
-        cfg: &VideoDetails,
-        frame_index: usize,
-        luma_only: bool,
+    ) -> Result<(Frame<T>, HashMap<String, PropValue>), DecoderError> {
+        if self.crop.is_some()
+            || self.normalized_in_rust()
+            || self.auto_rotation.is_some()
+            || self.lookahead.is_some()
+        {
+            return Err(DecoderError::UnsupportedDecoder);
+        }
+        check_pixel_type::<T>(&self.video_details)?;
+        self.check_end_of_stream()?;
+        let DecoderImpl::Vapoursynth(dec) = &mut self.decoder else {
+            return Err(DecoderError::UnsupportedDecoder);
+        };
+        let result =
+            dec.read_video_frame_with_props(&self.video_details, self.frames_read, self.config);
+        let frame_and_props = self.track_end_of_stream(result)?;
+        self.frames_read += 1;
+        Ok(frame_and_props)
+    }
+
+    /// Seeks to the frame shown at `seconds` into the video and decodes it.
+    ///
+    /// The frame index is `seconds * frame_rate`, rounded to the nearest frame, and clamped
+    /// to the frames in the video. `seconds` is a float, so the product is computed in
+    /// `f64`: the presentation time of a frame lands on that frame even at NTSC rates such
+    /// as 24000/1001, but a time within rounding error of the midpoint between two frames
+    /// may land on either.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`seek_to_frame`](Self::seek_to_frame) and
+    /// [`read_video_frame`](Self::read_video_frame); in particular
+    /// [`DecoderError::UnsupportedDecoder`] for backends that cannot seek.
+    #[inline]
+    pub fn seek_video_frame_at<T: Pixel>(
+        &mut self,
+        seconds: f64,
     ) -> Result<Frame<T>, DecoderError> {
-        match self {
+        let frame_index = frame_index_at(
+            seconds,
+            self.video_details.frame_rate,
+            self.video_details.total_frames,
+        );
+        self.seek_to_frame(frame_index)?;
+        self.read_video_frame()
+    }
+
+    /// Keeps the last `frames` decoded frames, so that
+    /// [`seek_to_frame`](Self::seek_to_frame) can jump back within them on any backend,
+    /// including Y4M read from stdin.
+    ///
+    /// The window holds `frames` full copies of the decoded frame, i.e. roughly
+    /// `frames` times [`VideoDetails::frame_size_bytes`] of memory at all times, so keep it
+    /// small for high resolutions. Passing `0` disables the lookahead and frees the window.
+    ///
+    /// Changing the output format, e.g. with [`set_crop`](Self::set_crop), empties the
+    /// window. On backends that cannot seek, frames that were buffered but not read yet
+    /// after seeking back are skipped, since they cannot be decoded again.
+    #[inline]
+    pub fn enable_lookahead(&mut self, frames: usize) {
+        self.lookahead = (frames > 0).then(|| Lookahead::new(frames, self.frames_read));
+    }
+
+    /// Caches up to `frames` decoded frames by index, so that seeking back to frames that
+    /// were already read returns them without decoding them again.
+    ///
+    /// When the cache is full, the least recently read frame is evicted. Cached frames are
+    /// only served on backends that can seek anywhere: VapourSynth, FFMS2, and seekable Y4M
+    /// sources. The cache is cleared whenever the output changes, i.e. by crops,
+    /// normalization, rotation, padding, luma-only output, overridden video details, and any
+    /// mutable access to the backend such as registering a VapourSynth node modifier.
+    /// Passing `0` disables the cache and frees the cached frames.
+    ///
+    /// Each cached frame takes about
+    /// [`estimated_memory_per_frame`](Self::estimated_memory_per_frame) bytes; use
+    /// [`enable_frame_cache_bytes`](Self::enable_frame_cache_bytes) to size the cache by a
+    /// memory budget instead.
+    #[inline]
+    pub fn enable_frame_cache(&mut self, frames: usize) {
+        self.frame_cache = (frames > 0).then(|| FrameCache::new(frames));
+    }
+
+    /// Same as [`enable_frame_cache`](Self::enable_frame_cache), but caches as many frames
+    /// as fit in `bytes`, counting
+    /// [`estimated_memory_per_frame`](Self::estimated_memory_per_frame) per frame, padding
+    /// included. The number of frames is fixed when this is called, so set the padding and
+    /// output format first.
+    #[inline]
+    pub fn enable_frame_cache_bytes(&mut self, bytes: usize) {
+        let frames = bytes
+            .checked_div(self.estimated_memory_per_frame())
+            .unwrap_or(0);
+        self.enable_frame_cache(frames);
+    }
+
+    /// Drops the frames kept by the frame cache and the lookahead, e.g. because the output
+    /// format changed.
+    ///
+    /// Seekable backends decode the frames again from the current position. Other backends
+    /// cannot go back, so reading resumes after the last frame the lookahead buffered.
+    fn invalidate_buffered_frames(&mut self) {
+        if let Some(frame_cache) = &mut self.frame_cache {
+            frame_cache.clear();
+        }
+        let supports_seeking = self.supports_seeking();
+        if let Some(lookahead) = &mut self.lookahead {
+            if !supports_seeking {
+                self.frames_read = lookahead.head();
+            }
+            lookahead.reset(self.frames_read);
+        }
+    }
+
+    /// Moves a seekable Y4M stream to the next frame to read, after frames were served
+    /// from the frame cache without reading them from the stream.
+    fn sync_backend_position(&mut self) -> Result<(), DecoderError> {
+        if let DecoderImpl::Y4mSeekable(dec) = &mut self.decoder
+            && dec.position() != self.frames_read
+        {
+            dec.seek_to_frame(self.frames_read)?;
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if the backend can seek to any frame, so that
+    /// [`seek_to_frame`](Self::seek_to_frame) and
+    /// [`seek_video_frame_at`](Self::seek_video_frame_at) succeed for every index in the
+    /// video.
+    ///
+    /// This is the case for VapourSynth, FFMS2, and seekable Y4M sources. Other backends
+    /// can still seek within the window kept by
+    /// [`enable_lookahead`](Self::enable_lookahead).
+    #[inline]
+    #[must_use]
+    pub const fn supports_seeking(&self) -> bool {
+        match self.decoder {
+            DecoderImpl::Y4mSeekable(_) => true,
+            #[cfg(feature = "vapoursynth")]
+            DecoderImpl::Vapoursynth(_) => true,
+            #[cfg(feature = "ffms2")]
+            DecoderImpl::Ffms2(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Seeks to the given frame index, skipping intermediate frames.
+    ///
+    /// VapourSynth, FFMS2, and Y4M files or other seekable Y4M sources, such as
+    /// [`from_bytes`](Self::from_bytes) and
+    /// [`from_seekable_reader`](Self::from_seekable_reader), can seek anywhere. Seeking within the window kept by
+    /// [`enable_lookahead`](Self::enable_lookahead) works for every backend and reuses the
+    /// buffered frames.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecoderError::UnsupportedDecoder`] if the backend does not support seeking,
+    /// as reported by [`supports_seeking`](Self::supports_seeking), and the index is outside
+    /// the lookahead window,
+    /// [`DecoderError::EndOfFile`] if the index is past the last frame.
+    #[inline]
+    pub fn seek_to_frame(&mut self, frame_index: usize) -> Result<(), DecoderError> {
+        if self
+            .lookahead
+            .as_ref()
+            .is_some_and(|lookahead| lookahead.can_seek_to(frame_index))
+        {
+            self.frames_read = frame_index;
+            return Ok(());
+        }
+        match &mut self.decoder {
+            DecoderImpl::Y4mSeekable(dec) => {
+                dec.seek_to_frame(frame_index)?;
+                self.frames_read = frame_index;
+                if let Some(lookahead) = &mut self.lookahead {
+                    lookahead.reset(frame_index);
+                }
+                Ok(())
+            }
             #[cfg(feature = "vapoursynth")]
-            Self::Vapoursynth(dec) => dec.read_video_frame::<T>(cfg, frame_index, luma_only),
+            DecoderImpl::Vapoursynth(_) => {
+                if self
+                    .video_details
+                    .total_frames
+                    .is_some_and(|total_frames| frame_index >= total_frames)
+                {
+                    return Err(DecoderError::EndOfFile);
+                }
+                self.frames_read = frame_index;
+                if let Some(lookahead) = &mut self.lookahead {
+                    lookahead.reset(frame_index);
+                }
+                Ok(())
+            }
             #[cfg(feature = "ffms2")]
-            Self::Ffms2(dec) => dec.read_video_frame::<T>(frame_index, luma_only),
+            DecoderImpl::Ffms2(_) => {
+                if self
+                    .video_details
+                    .total_frames
+                    .is_some_and(|total_frames| frame_index >= total_frames)
+                {
+                    return Err(DecoderError::EndOfFile);
+                }
+                self.frames_read = frame_index;
+                if let Some(lookahead) = &mut self.lookahead {
+                    lookahead.reset(frame_index);
+                }
+                Ok(())
+            }
             _ => Err(DecoderError::UnsupportedDecoder),
         }
     }
+
+    /// Seeks to the keyframe at or before `frame_index` and decodes it, returning the
+    /// keyframe's index along with the frame.
+    ///
+    /// Unlike [`seek_to_frame`](Self::seek_to_frame), this never decodes frames between the
+    /// keyframe and `frame_index`, so it is much faster for GOP-aligned processing. Reading
+    /// continues from the frame after the keyframe. FFMS2 looks the keyframe up in its
+    /// index; FFmpeg seeks the container and derives the index from the keyframe's
+    /// timestamp, which is only exact for constant frame rate streams.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecoderError::UnsupportedDecoder`] for backends other than FFmpeg and
+    /// FFMS2, [`DecoderError::EndOfFile`] if the index is past the last frame, and
+    /// otherwise the same errors as [`read_video_frame`](Self::read_video_frame).
+    #[inline]
+    pub fn seek_to_keyframe<T: Pixel>(
+        &mut self,
+        frame_index: usize,
+    ) -> Result<(usize, Frame<T>), DecoderError> {
+        if self
+            .video_details
+            .total_frames
+            .is_some_and(|total_frames| frame_index >= total_frames)
+        {
+            return Err(DecoderError::EndOfFile);
+        }
+        let keyframe_index = match &mut self.decoder {
+            #[cfg(feature = "ffmpeg")]
+            DecoderImpl::Ffmpeg(dec) => dec.seek_to_keyframe(frame_index)?,
+            #[cfg(feature = "ffms2")]
+            DecoderImpl::Ffms2(dec) => dec.keyframe_at_or_before(frame_index),
+            _ => return Err(DecoderError::UnsupportedDecoder),
+        };
+        self.frames_read = keyframe_index;
+        if let Some(lookahead) = &mut self.lookahead {
+            lookahead.reset(keyframe_index);
+        }
+        let frame = self.read_video_frame()?;
+        Ok((keyframe_index, frame))
+    }
+
+    /// Identifies the backend, container, and codec of the source.
+    ///
+    /// Container and codec names are reported by FFmpeg. Y4M and raw YUV sources report
+    /// `rawvideo`. FFMS2 and VapourSynth do not expose what they are decoding, so only the
+    /// backend is set for them.
+    #[inline]
+    #[must_use]
+    pub fn source_info(&self) -> SourceInfo {
+        match &self.decoder {
+            DecoderImpl::Y4m(_) | DecoderImpl::Y4mSeekable(_) => SourceInfo {
+                container: Some("yuv4mpegpipe".to_string()),
+                codec: Some("rawvideo".to_string()),
+                backend: Backend::Y4m,
+            },
+            DecoderImpl::RawYuv(_) => SourceInfo {
+                container: None,
+                codec: Some("rawvideo".to_string()),
+                backend: Backend::RawYuv,
+            },
+            #[cfg(feature = "ffmpeg")]
+            DecoderImpl::Ffmpeg(dec) => dec.source_info(),
+            #[cfg(feature = "ffms2")]
+            DecoderImpl::Ffms2(_) => SourceInfo {
+                container: None,
+                codec: None,
+                backend: Backend::Ffms2,
+            },
+            #[cfg(feature = "vapoursynth")]
+            DecoderImpl::Vapoursynth(_) => SourceInfo {
+                container: None,
+                codec: None,
+                backend: Backend::Vapoursynth,
+            },
+        }
+    }
+
+    /// Returns a mutable reference to the underlying FFmpeg decoder, or `None` otherwise.
+    #[inline]
+    #[cfg(feature = "ffmpeg")]
+    pub fn get_ffmpeg_impl(&mut self) -> Option<&mut FfmpegDecoder> {
+        self.invalidate_buffered_frames();
+        match &mut self.decoder {
+            DecoderImpl::Ffmpeg(dec) => Some(dec),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the underlying FFMS2 decoder, or `None` otherwise.
+    #[inline]
+    #[cfg(feature = "ffms2")]
+    pub fn get_ffms2_impl(&mut self) -> Option<&mut Ffms2Decoder> {
+        self.invalidate_buffered_frames();
+        match &mut self.decoder {
+            DecoderImpl::Ffms2(dec) => Some(dec),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the underlying VapourSynth decoder, or `None` otherwise.
+    #[inline]
+    #[cfg(feature = "vapoursynth")]
+    pub fn get_vapoursynth_impl(&mut self) -> Option<&mut VapoursynthDecoder> {
+        self.invalidate_buffered_frames();
+        match &mut self.decoder {
+            DecoderImpl::Vapoursynth(dec) => Some(dec),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the underlying backend, whichever it is.
+    ///
+    /// Video details are cached when the decoder is created, so changes made through the
+    /// backend that alter the output format are not reflected by
+    /// [`get_video_details`](Self::get_video_details). Frames kept by
+    /// [`enable_frame_cache`](Self::enable_frame_cache) and
+    /// [`enable_lookahead`](Self::enable_lookahead) are dropped.
+    #[inline]
+    pub fn decoder_impl_mut(&mut self) -> &mut DecoderImpl {
+        self.invalidate_buffered_frames();
+        &mut self.decoder
+    }
+
+    /// Consumes the decoder and returns the underlying backend.
+    ///
+    /// This is the inverse of [`from_decoder_impl`](Self::from_decoder_impl). Any crop or
+    /// lookahead configured on the `Decoder` is dropped.
+    #[inline]
+    #[must_use]
+    pub fn into_decoder_impl(self) -> DecoderImpl {
+        self.decoder
+    }
+}
+
+/// Returns the index of the frame shown at `seconds`, clamped to `[0, total_frames)`.
+fn frame_index_at(seconds: f64, frame_rate: Rational32, total_frames: Option<usize>) -> usize {
+    let frame = (seconds * f64::from(*frame_rate.numer()) / f64::from(*frame_rate.denom())).round();
+    // Negative and NaN times saturate to the first frame
+    let frame_index = frame as usize;
+    total_frames.map_or(frame_index, |total_frames| {
+        frame_index.min(total_frames.saturating_sub(1))
+    })
+}
+
+/// Opens a `.vpy` script, or loads any other file through `ffms2.Source`.
+#[cfg(feature = "vapoursynth")]
+fn open_vapoursynth_file(input: &Path) -> Result<VapoursynthDecoder, DecoderError> {
+    use crate::util::escape_python_string;
+
+    if input
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("vpy"))
+    {
+        // Decode vapoursynth script file input
+        return VapoursynthDecoder::from_file(input, HashMap::new(), None);
+    }
+
+    // Build a vapoursynth script and use that
+    let script = format!(
+        r#"
+import vapoursynth as vs
+core = vs.core
+clip = core.ffms2.Source("{}")
+clip.set_output()
+"#,
+        escape_python_string(&std::path::absolute(input)?.to_string_lossy())
+    );
+    VapoursynthDecoder::from_script(&script, HashMap::new(), None)
+}
+
+/// Internal enum representing the active decoder backend.
+///
+/// The variant is selected automatically during [`Decoder`] initialization.
+pub enum DecoderImpl {
+    /// Y4M format parser (always available).
+    Y4m(Y4mDecoder<Box<dyn Read>>),
+
+    /// Y4M format parser over a seekable stream, used for files and in-memory data
+    /// (always available).
+    Y4mSeekable(SeekableY4mDecoder),
+
+    /// Headerless planar YUV reader (always available).
+    RawYuv(RawYuvDecoder),
+
+    /// VapourSynth-based decoder (requires `vapoursynth` feature).
+    #[cfg(feature = "vapoursynth")]
+    Vapoursynth(VapoursynthDecoder),
+
+    /// FFmpeg-based decoder (requires `ffmpeg` feature).
+    #[cfg(feature = "ffmpeg")]
+    Ffmpeg(FfmpegDecoder),
+
+    /// FFMS2-based decoder (requires `ffms2` feature).
+    #[cfg(feature = "ffms2")]
+    Ffms2(Ffms2Decoder),
+}
+
+impl DecoderImpl {
+    pub(crate) fn video_details(&mut self) -> Result<VideoDetails, DecoderError> {
+        match self {
+            Self::Y4m(dec) => helpers::y4m::get_video_details(dec),
+            Self::Y4mSeekable(dec) => dec.video_details(),
+            Self::RawYuv(dec) => Ok(dec.video_details),
+            #[cfg(feature = "vapoursynth")]
+            Self::Vapoursynth(dec) => dec.get_video_details(),
+            #[cfg(feature = "ffmpeg")]
+            Self::Ffmpeg(dec) => Ok(dec.video_details),
+            #[cfg(feature = "ffms2")]
+            Self::Ffms2(dec) => Ok(dec.video_details),
+        }
+    }
+
+    pub(crate) fn read_video_frame<T: Pixel>(
+        &mut self,
+        cfg: &VideoDetails,
+        #[cfg(any(feature = "vapoursynth", feature = "ffms2"))] frame_index: usize,
+        config: DecoderConfig,
+    ) -> Result<Frame<T>, DecoderError> {
+        let mut frame: Frame<T> = new_padded_frame(cfg, config)?;
+        self.read_video_frame_into(
+            #[cfg(any(feature = "vapoursynth", feature = "ffms2"))]
+            frame_index,
+            &mut frame,
+        )?;
+        Ok(frame)
+    }
+
+    pub(crate) fn read_video_frame_into<T: Pixel>(
+        &mut self,
+        #[cfg(any(feature = "vapoursynth", feature = "ffms2"))] frame_index: usize,
+        frame: &mut Frame<T>,
+    ) -> Result<(), DecoderError> {
+        match self {
+            Self::Y4m(dec) => helpers::y4m::read_video_frame::<Box<dyn Read>, T>(dec, frame),
+            Self::Y4mSeekable(dec) => dec.read_video_frame::<T>(frame),
+            Self::RawYuv(dec) => dec.read_video_frame::<T>(frame),
+            #[cfg(feature = "vapoursynth")]
+            Self::Vapoursynth(dec) => dec.read_video_frame::<T>(frame_index, frame),
+            #[cfg(feature = "ffmpeg")]
+            Self::Ffmpeg(dec) => dec.read_video_frame::<T>(frame),
+            #[cfg(feature = "ffms2")]
+            Self::Ffms2(dec) => dec.read_video_frame::<T>(frame_index, frame),
+        }
+    }
+
+    #[cfg(feature = "vapoursynth")]
+    pub(crate) fn get_video_frame<T: Pixel>(
+        &mut self,
+        cfg: &VideoDetails,
+        frame_index: usize,
+        config: DecoderConfig,
+    ) -> Result<Frame<T>, DecoderError> {
+        match self {
+            #[cfg(feature = "vapoursynth")]
+            Self::Vapoursynth(_) => (),
+            #[cfg(feature = "ffms2")]
+            Self::Ffms2(_) => (),
+            _ => return Err(DecoderError::UnsupportedDecoder),
+        }
+        let mut frame: Frame<T> = new_padded_frame(cfg, config)?;
+        self.read_video_frame_into(frame_index, &mut frame)?;
+        Ok(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::hash::{BuildHasher, RandomState};
+
+    use super::*;
+    use crate::test_util::{temp_path, y4m_fixture};
+
+    #[test]
+    fn video_details_compare_frame_rate_by_value() {
+        let reduced = VideoDetails::default();
+        let unreduced = VideoDetails {
+            frame_rate: Rational32::new_raw(60, 2),
+            ..VideoDetails::default()
+        };
+
+        assert_eq!(reduced, unreduced);
+        let hasher = RandomState::new();
+        assert_eq!(hasher.hash_one(reduced), hasher.hash_one(unreduced));
+    }
+
+    #[test]
+    fn maps_h273_code_points() {
+        assert_eq!(ColorPrimaries::from_h273(9), ColorPrimaries::Bt2020);
+        assert_eq!(
+            TransferCharacteristics::from_h273(16),
+            TransferCharacteristics::Smpte2084
+        );
+        assert_eq!(
+            MatrixCoefficients::from_h273(0),
+            MatrixCoefficients::Identity
+        );
+        for reserved in [-1, 3, 23] {
+            assert_eq!(
+                ColorPrimaries::from_h273(reserved),
+                ColorPrimaries::Unspecified
+            );
+            assert_eq!(
+                TransferCharacteristics::from_h273(reserved),
+                TransferCharacteristics::Unspecified
+            );
+            assert_eq!(
+                MatrixCoefficients::from_h273(reserved),
+                MatrixCoefficients::Unspecified
+            );
+        }
+        assert_eq!(ColorPrimaries::Ebu3213 as u8, 22);
+    }
+
+    #[test]
+    fn video_details_display_includes_frame_count() {
+        let details = VideoDetails {
+            total_frames: Some(112),
+            ..VideoDetails::default()
+        };
+
+        assert_eq!(
+            details.to_string(),
+            "640x480 8-bit 4:2:0 @ 30 fps (112 frames)"
+        );
+    }
+
+    #[test]
+    fn video_details_display_reduces_frame_rate() {
+        let details = VideoDetails {
+            bit_depth: 10,
+            chroma_sampling: ChromaSubsampling::Yuv444,
+            frame_rate: Rational32::new_raw(60_000, 2002),
+            ..VideoDetails::default()
+        };
+
+        assert_eq!(details.to_string(), "640x480 10-bit 4:4:4 @ 30000/1001 fps");
+    }
+
+    #[test]
+    fn video_details_duration_is_exact() {
+        let details = VideoDetails {
+            frame_rate: Rational32::new(30_000, 1001),
+            total_frames: Some(30_000),
+            ..VideoDetails::default()
+        };
+        assert_eq!(details.duration(), Some(Duration::from_secs(1001)));
+
+        let unknown = VideoDetails {
+            total_frames: None,
+            ..details
+        };
+        assert_eq!(unknown.duration(), None);
+        let broken = VideoDetails {
+            frame_rate: Rational32::new_raw(0, 0),
+            ..details
+        };
+        assert_eq!(broken.duration(), None);
+    }
+
+    #[test]
+    fn video_details_frame_rate_accessors() {
+        let details = VideoDetails {
+            frame_rate: Rational32::new_raw(60_000, 2002),
+            ..VideoDetails::default()
+        };
+        assert_eq!(
+            details.frame_rate_reduced(),
+            Some(Rational32::new(30_000, 1001))
+        );
+        assert!((details.frame_rate_f64() - 29.97).abs() < 0.001);
+
+        let broken = VideoDetails {
+            frame_rate: Rational32::new_raw(0, 0),
+            ..VideoDetails::default()
+        };
+        assert_eq!(broken.frame_rate_reduced(), None);
+        assert!(broken.frame_rate_f64() == 0.0);
+        assert!(broken.to_string().contains("@ 0/0 fps"));
+    }
+
+    #[test]
+    fn from_reader_decodes_in_memory_y4m() {
+        let data = y4m_fixture(&[16]);
+
+        let mut decoder =
+            Decoder::from_reader(std::io::Cursor::new(data)).expect("valid Y4M header");
+        let details = *decoder.get_video_details();
+        assert_eq!((details.width, details.height), (4, 2));
+        assert_eq!(details.frame_rate, Rational32::new(25, 1));
+
+        assert!(decoder.read_video_frame::<u8>().is_ok());
+        assert!(
+            decoder
+                .read_video_frame::<u8>()
+                .expect_err("only one frame")
+                .is_eof()
+        );
+    }
+
+    #[test]
+    fn plane_dimensions_follow_subsampling() {
+        let details = VideoDetails {
+            width: 641,
+            height: 481,
+            ..VideoDetails::default()
+        };
+        assert_eq!(details.plane_dimensions(0), (641, 481));
+        assert_eq!(details.plane_dimensions(1), (321, 241));
+        assert_eq!(details.plane_dimensions(2), (321, 241));
+        assert_eq!(details.plane_dimensions(3), (0, 0));
+
+        let details = VideoDetails {
+            chroma_sampling: ChromaSubsampling::Yuv422,
+            ..VideoDetails::default()
+        };
+        assert_eq!(details.plane_dimensions(1), (320, 480));
+
+        let details = VideoDetails {
+            chroma_sampling: ChromaSubsampling::Monochrome,
+            ..VideoDetails::default()
+        };
+        assert_eq!(details.plane_dimensions(1), (0, 0));
+    }
+
+    #[test]
+    fn builds_black_frames() {
+        let details = VideoDetails {
+            width: 4,
+            height: 2,
+            ..VideoDetails::default()
+        };
+        let frame = details.new_black_frame::<u8>().expect("8-bit frame");
+        assert!(frame.y_plane.rows().flatten().all(|&sample| sample == 16));
+        let u_plane = frame.u_plane.as_ref().expect("4:2:0 has chroma");
+        assert!(u_plane.rows().flatten().all(|&sample| sample == 128));
+
+        let details = VideoDetails {
+            bit_depth: 10,
+            color_range: ColorRange::Full,
+            ..details
+        };
+        let frame = details.new_black_frame::<u16>().expect("10-bit frame");
+        assert!(frame.y_plane.rows().flatten().all(|&sample| sample == 0));
+        let v_plane = frame.v_plane.as_ref().expect("4:2:0 has chroma");
+        assert!(v_plane.rows().flatten().all(|&sample| sample == 512));
+        assert!(details.new_black_frame::<u8>().is_err());
+
+        for bit_depth in [0, 24] {
+            let details = VideoDetails {
+                bit_depth,
+                ..details
+            };
+            assert!(matches!(
+                details.new_black_frame::<u16>(),
+                Err(DecoderError::UnsupportedFormat { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn frame_size_bytes_accounts_for_subsampling_and_bit_depth() {
+        let luma = 640 * 480;
+        for (chroma_sampling, chroma) in [
+            (ChromaSubsampling::Yuv420, luma / 4),
+            (ChromaSubsampling::Yuv422, luma / 2),
+            (ChromaSubsampling::Yuv444, luma),
+            (ChromaSubsampling::Monochrome, 0),
+        ] {
+            for (bit_depth, bytes) in [(8, 1), (10, 2), (12, 2)] {
+                let details = VideoDetails {
+                    bit_depth,
+                    chroma_sampling,
+                    ..VideoDetails::default()
+                };
+                assert_eq!(details.frame_size_bytes(), (luma + 2 * chroma) * bytes);
+            }
+        }
+    }
+
+    #[test]
+    fn set_padding_changes_frame_allocation() {
+        let data = y4m_fixture(&[16]);
+        let mut decoder = Decoder::from_bytes(data).expect("valid Y4M header");
+
+        assert!(matches!(
+            decoder.set_padding(1, 0, 0, 0),
+            Err(DecoderError::UnsupportedFormat { .. })
+        ));
+        decoder.set_padding(0, 0, 0, 0).expect("no padding");
+        assert_eq!(
+            decoder.estimated_memory_per_frame(),
+            decoder.get_video_details().frame_size_bytes()
+        );
+        let frame = decoder.read_video_frame::<u8>().expect("one frame");
+        assert!(frame.y_plane.rows().flatten().all(|&sample| sample == 16));
+    }
+
+    #[test]
+    fn estimated_memory_includes_padding() {
+        let data = y4m_fixture(&[16]);
+        let mut decoder = Decoder::from_bytes(data).expect("valid Y4M header");
+
+        let luma = (4 + 2 * LUMA_PADDING) * (2 + 2 * LUMA_PADDING);
+        let chroma = (2 + LUMA_PADDING) * (1 + LUMA_PADDING);
+        assert_eq!(decoder.estimated_memory_per_frame(), luma + 2 * chroma);
+        decoder.set_luma_only(true);
+        assert_eq!(decoder.estimated_memory_per_frame(), luma);
+    }
+
+    #[test]
+    fn from_file_detects_y4m_without_extension() {
+        let data = y4m_fixture(&[16]);
+        let path = temp_path("sniff-y4m.dat");
+        std::fs::write(&path, data).expect("write test file");
+
+        let decoder = Decoder::from_file(&path);
+        std::fs::remove_file(&path).expect("remove test file");
+
+        let details = *decoder.expect("Y4M signature detected").get_video_details();
+        assert_eq!((details.width, details.height), (4, 2));
+    }
+
+    #[test]
+    fn fallback_skips_unavailable_backends() {
+        let data = y4m_fixture(&[16]);
+        let path = temp_path("fallback.y4m");
+        std::fs::write(&path, data).expect("write test file");
+
+        let decoder = Decoder::from_file_with_fallback(&path, &[Backend::Y4m]);
+        let empty = Decoder::from_file_with_fallback(&path, &[]);
+        std::fs::remove_file(&path).expect("remove test file");
+
+        let decoder = decoder.expect("Y4M backend is always available");
+        assert_eq!(decoder.source_info().backend, Backend::Y4m);
+        assert!(matches!(empty, Err(DecoderError::NoDecoder)));
+    }
+
+    #[test]
+    #[ignore = "opens a network connection"]
+    fn unreachable_url_fails_to_open() {
+        // Nothing listens on the discard port, so the connection is refused immediately
+        let result = Decoder::from_url("http://127.0.0.1:9/clip.mkv");
+        if cfg!(feature = "ffmpeg") {
+            assert!(matches!(result, Err(DecoderError::FileReadError { .. })));
+        } else {
+            assert!(matches!(result, Err(DecoderError::NoDecoder)));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "ffmpeg")]
+    fn decodes_url_served_over_http() {
+        use std::io::{BufRead, BufReader, Write};
+
+        let data = y4m_fixture(&[16, 32, 48]);
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("loopback listener");
+        let port = listener.local_addr().expect("bound address").port();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { continue };
+                let mut request = BufReader::new(&stream);
+                let mut line = String::new();
+                while request.read_line(&mut line).is_ok_and(|read| read > 2) {
+                    line.clear();
+                }
+                let header = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    data.len()
+                );
+                let _ = stream
+                    .write_all(header.as_bytes())
+                    .and_then(|()| stream.write_all(&data));
+            }
+        });
+
+        let mut decoder =
+            Decoder::from_url(&format!("http://127.0.0.1:{port}/clip.y4m")).expect("stream opens");
+        decoder
+            .get_ffmpeg_impl()
+            .expect("URLs are decoded with FFmpeg")
+            .set_retry_policy(RetryPolicy {
+                max_retries: 1,
+                ..RetryPolicy::default()
+            });
+        for value in [16, 32, 48] {
+            let frame = decoder.read_video_frame::<u8>().expect("streamed frame");
+            assert!(
+                frame
+                    .y_plane
+                    .rows()
+                    .flatten()
+                    .all(|&sample| sample == value)
+            );
+        }
+        assert!(
+            decoder
+                .read_video_frame::<u8>()
+                .expect_err("only three frames")
+                .is_eof()
+        );
+    }
+
+    #[test]
+    fn y4m_source_info_reports_rawvideo() {
+        let data = y4m_fixture(&[16]);
+
+        let decoder = Decoder::from_reader(std::io::Cursor::new(data)).expect("valid Y4M header");
+        let info = decoder.source_info();
+        assert_eq!(info.backend, Backend::Y4m);
+        assert_eq!(info.codec.as_deref(), Some("rawvideo"));
+    }
+
+    #[test]
+    fn backend_round_trips_through_display() {
+        for backend in [
+            Backend::Y4m,
+            Backend::RawYuv,
+            Backend::Ffmpeg,
+            Backend::Ffms2,
+            Backend::Vapoursynth,
+        ] {
+            assert_eq!(backend.to_string().parse::<Backend>().ok(), Some(backend));
+        }
+        assert_eq!("FFMS2".parse::<Backend>().ok(), Some(Backend::Ffms2));
+        assert!("dgdecnv".parse::<Backend>().is_err());
+    }
+
+    #[test]
+    fn backend_from_y4m_extension() {
+        assert_eq!(
+            Backend::try_from(Path::new("clip.Y4M")).ok(),
+            Some(Backend::Y4m)
+        );
+    }
+
+    #[test]
+    fn read_video_frame_into_reuses_frame() {
+        let data = y4m_fixture(&[16, 32]);
+        let mut decoder =
+            Decoder::from_reader(std::io::Cursor::new(data)).expect("valid Y4M header");
+        let mut frame = decoder.read_video_frame::<u8>().expect("first frame");
+
+        decoder
+            .read_video_frame_into(&mut frame)
+            .expect("second frame");
+        assert!(frame.y_plane.rows().flatten().all(|&sample| sample == 32));
+        assert!(
+            decoder
+                .read_video_frame_into(&mut frame)
+                .expect_err("only two frames")
+                .is_eof()
+        );
+    }
+
+    #[test]
+    fn read_video_frame_into_rejects_mismatched_frame() {
+        let data = y4m_fixture(&[16]);
+        let mut decoder =
+            Decoder::from_reader(std::io::Cursor::new(data)).expect("valid Y4M header");
+        let mut frame = new_padded_frame::<u8>(&VideoDetails::default(), DecoderConfig::default())
+            .expect("640x480");
+
+        assert!(matches!(
+            decoder.read_video_frame_into(&mut frame),
+            Err(DecoderError::GenericDecodeError { .. })
+        ));
+    }
+
+    #[test]
+    fn frame_cache_budget_counts_padding() {
+        let mut decoder = Decoder::from_bytes(y4m_fixture(&[16])).expect("valid Y4M header");
+        let frame_size = decoder.estimated_memory_per_frame();
+        assert!(frame_size > decoder.get_video_details().frame_size_bytes());
+
+        decoder.enable_frame_cache_bytes(3 * frame_size - 1);
+        let frame_cache = decoder.frame_cache.as_ref().expect("cache is enabled");
+        assert_eq!(frame_cache.capacity(), 2);
+        decoder.enable_frame_cache_bytes(frame_size - 1);
+        assert!(decoder.frame_cache.is_none());
+    }
+
+    #[test]
+    fn frame_cache_serves_repeated_seeks() {
+        let data = y4m_fixture(&[16, 32, 48]);
+        let mut decoder = Decoder::from_bytes(data).expect("valid Y4M header");
+        decoder.enable_frame_cache(2);
+        for _ in 0..2 {
+            decoder.read_video_frame::<u8>().expect("frame");
+        }
+
+        decoder.seek_to_frame(0).expect("seekable source");
+        let frame = decoder.read_video_frame::<u8>().expect("cached frame");
+        assert!(frame.y_plane.rows().flatten().all(|&sample| sample == 16));
+        decoder.skip_frames(1).expect("frame 1");
+        // The stream fell behind while frames came from the cache
+        let frame = decoder.read_video_frame::<u8>().expect("decoded frame");
+        assert!(frame.y_plane.rows().flatten().all(|&sample| sample == 48));
+
+        let frame_cache = decoder.frame_cache.as_mut().expect("cache is enabled");
+        assert!(frame_cache.get::<u8>(2).expect("same pixel type").is_some());
+        decoder.set_luma_only(true);
+        let frame_cache = decoder.frame_cache.as_mut().expect("cache is enabled");
+        assert!(frame_cache.get::<u8>(2).expect("same pixel type").is_none());
+    }
+
+    #[test]
+    fn read_video_frames_stops_at_end_of_stream() {
+        let data = y4m_fixture(&[16, 32, 48]);
+        let mut decoder =
+            Decoder::from_reader(std::io::Cursor::new(data)).expect("valid Y4M header");
+
+        let frames = decoder.read_video_frames::<u8>(2).expect("two frames");
+        assert_eq!(frames.len(), 2);
+        let frames = decoder.read_video_frames::<u8>(2).expect("last frame");
+        assert_eq!(frames.len(), 1);
+        assert!(
+            frames[0]
+                .y_plane
+                .rows()
+                .flatten()
+                .all(|&sample| sample == 48)
+        );
+        assert!(
+            decoder
+                .read_video_frames::<u8>(2)
+                .expect("end of stream")
+                .is_empty()
+        );
+        assert_eq!(decoder.current_frame_index(), 3);
+    }
+
+    #[test]
+    fn end_of_stream_is_sticky_until_seeking_back() {
+        let data = y4m_fixture(&[16]);
+        let mut decoder = Decoder::from_bytes(data).expect("valid Y4M header");
+        decoder.read_video_frame::<u8>().expect("only frame");
+
+        for _ in 0..2 {
+            assert!(
+                decoder
+                    .read_video_frame::<u8>()
+                    .expect_err("end of stream")
+                    .is_eof()
+            );
+        }
+        assert_eq!(decoder.eof_at, Some(1));
+        assert!(decoder.skip_frames(1).expect_err("end of stream").is_eof());
+
+        decoder.seek_to_frame(0).expect("seekable source");
+        decoder
+            .read_video_frame::<u8>()
+            .expect("frame after seeking back");
+    }
+
+    #[test]
+    fn read_video_frame_as8_reduces_high_bit_depth() {
+        let mut data = b"YUV4MPEG2 W4 H2 F25:1 Ip A1:1 C420p10 XYSCSS=420P10\nFRAME\n".to_vec();
+        for sample in [1022_u16; 4 * 2].into_iter().chain([513; 2 * 2]) {
+            data.extend_from_slice(&sample.to_le_bytes());
+        }
+        let mut decoder = Decoder::from_bytes(data).expect("valid Y4M header");
+
+        let frame = decoder.read_video_frame_as8().expect("8-bit frame");
+
+        assert_eq!(decoder.get_video_details().bit_depth, 10);
+        assert!(frame.y_plane.rows().flatten().all(|&sample| sample == 255));
+        let u_plane = frame.u_plane.as_ref().expect("4:2:0 has chroma");
+        assert!(u_plane.rows().flatten().all(|&sample| sample == 128));
+    }
+
+    #[test]
+    fn lookahead_seeks_back_within_window() {
+        let data = y4m_fixture(&[16, 32, 48, 64]);
+        let mut decoder =
+            Decoder::from_reader(std::io::Cursor::new(data)).expect("valid Y4M header");
+        decoder.enable_lookahead(2);
+        for _ in 0..3 {
+            decoder.read_video_frame::<u8>().expect("frame");
+        }
+
+        assert!(matches!(
+            decoder.seek_to_frame(0),
+            Err(DecoderError::UnsupportedDecoder)
+        ));
+        decoder.seek_to_frame(1).expect("frame 1 is buffered");
+        let frame = decoder.read_video_frame::<u8>().expect("buffered frame");
+        assert!(frame.y_plane.rows().flatten().all(|&sample| sample == 32));
+        decoder.skip_frames(1).expect("frame 2 is buffered");
+        let frame = decoder.read_video_frame::<u8>().expect("decoded frame");
+        assert!(frame.y_plane.rows().flatten().all(|&sample| sample == 64));
+    }
+
+    #[test]
+    fn output_changes_drop_buffered_frames() {
+        let data = y4m_fixture(&[16, 32, 48]);
+        let mut decoder = Decoder::from_bytes(data.clone()).expect("valid Y4M header");
+        decoder.enable_lookahead(2);
+        decoder.read_video_frame::<u8>().expect("frame");
+        decoder.read_video_frame::<u8>().expect("frame");
+        decoder.seek_to_frame(0).expect("frame 0 is buffered");
+        decoder.set_luma_only(true);
+        let frame = decoder.read_video_frame::<u8>().expect("decoded again");
+        assert!(frame.y_plane.rows().flatten().all(|&sample| sample == 16));
+        assert!(frame.u_plane.is_none());
+
+        // A stream cannot decode frame 1 again, so it is skipped
+        let mut decoder =
+            Decoder::from_reader(std::io::Cursor::new(data)).expect("valid Y4M header");
+        decoder.enable_lookahead(2);
+        decoder.read_video_frame::<u8>().expect("frame");
+        decoder.read_video_frame::<u8>().expect("frame");
+        decoder.seek_to_frame(1).expect("frame 1 is buffered");
+        decoder.set_luma_only(true);
+        assert_eq!(decoder.current_frame_index(), 2);
+        let frame = decoder.read_video_frame::<u8>().expect("next frame");
+        assert!(frame.y_plane.rows().flatten().all(|&sample| sample == 48));
+        assert!(frame.u_plane.is_none());
+    }
+
+    #[test]
+    fn raw_yuv_round_trips_full_range_16_bit() {
+        let details = VideoDetails {
+            width: 4,
+            height: 2,
+            bit_depth: 16,
+            ..VideoDetails::default()
+        };
+        let luma = [0xffff_u16, 0x0100, 0x00ff, 0x8001];
+        let mut data = Vec::new();
+        for sample in luma.iter().cycle().take(4 * 2) {
+            data.extend_from_slice(&sample.to_le_bytes());
+        }
+        for _ in 0..2 * 2 {
+            data.extend_from_slice(&0x8000_u16.to_le_bytes());
+        }
+        let path = temp_path("16-bit.yuv");
+        std::fs::write(&path, data).expect("write test file");
+
+        let decoder = Decoder::from_raw_yuv(&path, details);
+        let frame = decoder
+            .and_then(|mut decoder| decoder.read_video_frame::<u16>())
+            .expect("16-bit frame");
+        std::fs::remove_file(&path).expect("remove test file");
+
+        for row in frame.y_plane.rows() {
+            assert_eq!(row, luma);
+        }
+        let u_plane = frame.u_plane.as_ref().expect("4:2:0 has chroma");
+        assert!(u_plane.rows().flatten().all(|&sample| sample == 0x8000));
+    }
+
+    #[test]
+    fn skipping_past_the_end_stops_at_the_last_frame() {
+        let data = y4m_fixture(&[16, 32]);
+        let path = temp_path("skip-past-end.yuv");
+        std::fs::write(&path, [16; 2 * (4 * 2 + 2 * 2)]).expect("write test file");
+        let details = VideoDetails {
+            width: 4,
+            height: 2,
+            ..VideoDetails::default()
+        };
+        let decoders = [
+            Decoder::from_reader(std::io::Cursor::new(data.clone())),
+            Decoder::from_bytes(data),
+            Decoder::from_raw_yuv(&path, details),
+        ];
+        std::fs::remove_file(&path).expect("remove test file");
+
+        for decoder in decoders {
+            let mut decoder = decoder.expect("valid source");
+            assert!(
+                decoder
+                    .skip_frames(usize::MAX)
+                    .expect_err("only two frames")
+                    .is_eof()
+            );
+            assert_eq!(decoder.current_frame_index(), 2);
+            assert!(
+                decoder
+                    .read_video_frame::<u8>()
+                    .expect_err("at the end")
+                    .is_eof()
+            );
+        }
+    }
+
+    #[test]
+    fn y4m_file_seeks_backward() {
+        let data = y4m_fixture(&[16, 32, 48]);
+        let path = temp_path("seek-backward.y4m");
+        std::fs::write(&path, &data).expect("write test file");
+
+        let mut decoder = Decoder::from_file(&path).expect("valid Y4M file");
+        decoder.skip_frames(3).expect("skip to the end");
+        decoder.seek_to_frame(1).expect("seek backward");
+        let frame = decoder.read_video_frame::<u8>().expect("frame 1");
+        std::fs::remove_file(&path).expect("remove test file");
+
+        assert!(frame.y_plane.rows().flatten().all(|&sample| sample == 32));
+        assert_eq!(decoder.current_frame_index(), 2);
+    }
+
+    #[test]
+    fn reports_seeking_support_by_backend() {
+        let data = y4m_fixture(&[]);
+        let stream =
+            Decoder::from_reader(std::io::Cursor::new(data.clone())).expect("valid Y4M header");
+        let seekable = Decoder::from_bytes(data).expect("valid Y4M header");
+
+        assert!(!stream.supports_seeking());
+        assert!(seekable.supports_seeking());
+    }
+
+    #[test]
+    fn from_bytes_seeks_in_both_directions() {
+        let data = y4m_fixture(&[16, 32, 48]);
+        let mut decoder = Decoder::from_bytes(data).expect("valid Y4M header");
+
+        decoder.seek_to_frame(2).expect("seek forward");
+        let frame = decoder.read_video_frame::<u8>().expect("frame 2");
+        assert!(frame.y_plane.rows().flatten().all(|&sample| sample == 48));
+        decoder.seek_to_frame(0).expect("seek backward");
+        let frame = decoder.read_video_frame::<u8>().expect("frame 0");
+        assert!(frame.y_plane.rows().flatten().all(|&sample| sample == 16));
+        for frame_index in [3, 4] {
+            assert!(
+                decoder
+                    .seek_to_frame(frame_index)
+                    .expect_err("only three frames")
+                    .is_eof()
+            );
+        }
+    }
+
+    #[test]
+    fn buffered_frames_keep_their_timestamps() {
+        let data = y4m_fixture(&[16, 32]);
+        let mut decoder =
+            Decoder::from_reader(std::io::Cursor::new(data)).expect("valid Y4M header");
+        decoder.enable_lookahead(2);
+        decoder.read_video_frame::<u8>().expect("first frame");
+        decoder.read_video_frame::<u8>().expect("second frame");
+        decoder.seek_to_frame(0).expect("frame 0 is buffered");
+
+        let (_, timestamp) = decoder
+            .read_video_frame_with_pts::<u8>()
+            .expect("buffered frame");
+        assert_eq!(timestamp.pts, 0);
+        assert_eq!(timestamp.time_base, Rational32::new(1, 25));
+    }
+
+    #[test]
+    fn zero_frame_rate_has_no_derived_timestamps() {
+        let details = VideoDetails {
+            width: 4,
+            height: 2,
+            frame_rate: Rational32::new(0, 1),
+            ..VideoDetails::default()
+        };
+        let path = temp_path("zero-frame-rate.yuv");
+        let data = [[16; 4 * 2 + 2 * 2], [32; 4 * 2 + 2 * 2]].concat();
+        std::fs::write(&path, data).expect("write test file");
+        let decoder = Decoder::from_raw_yuv(&path, details);
+        std::fs::remove_file(&path).expect("remove test file");
+        let mut decoder = decoder.expect("valid raw layout");
+
+        assert!(matches!(
+            decoder.read_video_frame_with_pts::<u8>(),
+            Err(DecoderError::GenericDecodeError { .. })
+        ));
+        let frame = decoder.read_video_frame::<u8>().expect("second frame");
+        assert!(frame.y_plane.rows().flatten().all(|&sample| sample == 32));
+    }
+
+    #[test]
+    fn y4m_flags_only_first_frame_as_keyframe() {
+        let data = y4m_fixture(&[16, 16]);
+        let mut decoder = Decoder::from_bytes(data).expect("valid Y4M header");
+
+        let (_, flags) = decoder
+            .read_video_frame_with_flags::<u8>()
+            .expect("first frame");
+        assert!(flags.is_keyframe && !flags.is_scene_change);
+        let (_, flags) = decoder
+            .read_video_frame_with_flags::<u8>()
+            .expect("second frame");
+        assert_eq!(flags, FrameFlags::default());
+    }
+
+    #[test]
+    fn cached_frames_keep_their_flags() {
+        let data = y4m_fixture(&[16, 32]);
+        let mut decoder = Decoder::from_bytes(data).expect("valid Y4M header");
+        decoder.enable_frame_cache(2);
+        decoder.read_video_frame::<u8>().expect("first frame");
+        decoder.read_video_frame::<u8>().expect("second frame");
+        decoder.seek_to_frame(0).expect("seekable source");
+
+        let (_, flags) = decoder
+            .read_video_frame_with_flags::<u8>()
+            .expect("cached frame");
+        assert!(flags.is_keyframe);
+    }
+
+    #[test]
+    #[cfg(feature = "vapoursynth")]
+    fn vapoursynth_gray_clip_has_no_chroma_planes() {
+        let script = "import vapoursynth as vs\n\
+            vs.core.std.BlankClip(format=vs.GRAY8, width=4, height=2, length=1, color=[16])\
+            .set_output()";
+        let mut decoder = Decoder::from_script(script, HashMap::new()).expect("valid script");
+        assert_eq!(
+            decoder.get_video_details().chroma_sampling,
+            ChromaSubsampling::Monochrome
+        );
+
+        let frame = decoder.read_video_frame::<u8>().expect("gray frame");
+        assert!(frame.u_plane.is_none() && frame.v_plane.is_none());
+        assert!(frame.y_plane.rows().flatten().all(|&sample| sample == 16));
+    }
+
+    #[test]
+    #[cfg(feature = "vapoursynth")]
+    fn vapoursynth_props_skip_frame_references() {
+        let script = "import vapoursynth as vs\n\
+            clip = vs.core.std.BlankClip(format=vs.GRAY8, width=4, height=2, length=1)\n\
+            clip.std.ClipToProp(mclip=clip, prop='Reference').set_output()";
+        let mut decoder = Decoder::from_script(script, HashMap::new()).expect("valid script");
+
+        let (_, props) = decoder
+            .read_video_frame_with_props::<u8>()
+            .expect("only frame");
+        assert!(!props.contains_key("Reference"));
+        assert!(matches!(props.get("_DurationNum"), Some(PropValue::Int(_))));
+    }
+
+    #[test]
+    #[cfg(feature = "vapoursynth")]
+    fn vapoursynth_random_access_rejects_mismatched_pixel_types() {
+        let script = "import vapoursynth as vs\n\
+            vs.core.std.BlankClip(format=vs.YUV420P10, width=4, height=2, length=1)\
+            .set_output()";
+        let mut decoder = Decoder::from_script(script, HashMap::new()).expect("valid script");
+        assert!(matches!(
+            decoder.get_video_frame::<u8>(0),
+            Err(DecoderError::PixelTypeMismatch { .. })
+        ));
+        decoder.get_video_frame::<u16>(0).expect("10-bit frame");
+    }
+
+    #[test]
+    #[cfg(feature = "vapoursynth")]
+    fn vapoursynth_props_end_like_frames() {
+        let script = "import vapoursynth as vs\n\
+            vs.core.std.BlankClip(format=vs.GRAY8, width=4, height=2, length=1)\
+            .set_output()";
+        let mut decoder = Decoder::from_script(script, HashMap::new()).expect("valid script");
+        assert!(matches!(
+            decoder.read_video_frame_with_props::<u16>(),
+            Err(DecoderError::PixelTypeMismatch { .. })
+        ));
+        decoder.enable_lookahead(1);
+        assert!(matches!(
+            decoder.read_video_frame_with_props::<u8>(),
+            Err(DecoderError::UnsupportedDecoder)
+        ));
+        decoder.enable_lookahead(0);
+
+        decoder
+            .read_video_frame_with_props::<u8>()
+            .expect("only frame");
+        assert!(
+            decoder
+                .read_video_frame_with_props::<u8>()
+                .expect_err("past the end")
+                .is_eof()
+        );
+        assert!(
+            decoder
+                .read_video_frame::<u8>()
+                .expect_err("sticky")
+                .is_eof()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "vapoursynth")]
+    fn vapoursynth_rejects_frames_resized_after_details_were_cached() {
+        let script = "import vapoursynth as vs\n\
+            vs.core.std.BlankClip(format=vs.YUV420P8, width=64, height=64, length=1)\
+            .set_output()";
+        let mut decoder = Decoder::from_script(script, HashMap::new()).expect("valid script");
+        // Cropping on the backend directly leaves the decoder's cached details stale
+        decoder
+            .get_vapoursynth_impl()
+            .expect("scripts use VapourSynth")
+            .set_crop(0, 0, 32, 32)
+            .expect("crop is aligned to the subsampling");
+
+        assert!(matches!(
+            decoder.read_video_frame::<u8>(),
+            Err(DecoderError::VariableResolution)
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "vapoursynth")]
+    fn vapoursynth_frame_timeout_returns_the_awaited_frame() {
+        let script = "import vapoursynth as vs\n\
+            vs.core.std.BlankClip(format=vs.GRAY8, width=4, height=2, length=2, color=[42])\
+            .set_output()";
+        let mut decoder = Decoder::from_script(script, HashMap::new()).expect("valid script");
+        decoder
+            .get_vapoursynth_impl()
+            .expect("scripts use VapourSynth")
+            .set_frame_timeout(Some(std::time::Duration::from_secs(60)));
+
+        let frame = decoder.read_video_frame::<u8>().expect("first frame");
+        assert!(frame.y_plane.rows().flatten().all(|&sample| sample == 42));
+
+        decoder
+            .get_vapoursynth_impl()
+            .expect("scripts use VapourSynth")
+            .set_frame_timeout(None);
+        decoder.read_video_frame::<u8>().expect("second frame");
+
+        decoder
+            .get_vapoursynth_impl()
+            .expect("scripts use VapourSynth")
+            .set_frame_timeout(Some(std::time::Duration::from_secs(60)));
+        assert!(
+            decoder
+                .read_video_frame::<u8>()
+                .expect_err("past the end")
+                .is_eof()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "vapoursynth")]
+    fn vapoursynth_reads_alpha_and_premultiplication() {
+        let script = "import vapoursynth as vs\n\
+            clip = vs.core.std.BlankClip(format=vs.YUV420P8, width=4, height=2, length=1)\n\
+            alpha = vs.core.std.BlankClip(format=vs.GRAY8, width=4, height=2, length=1, color=[200])\n\
+            with_alpha = clip.std.ClipToProp(alpha, prop='_Alpha')\n\
+            (with_alpha.std.SetFrameProps(_AlphaIsPremultiplied=1) + with_alpha + clip)\
+            .set_output()";
+        let mut decoder = Decoder::from_script(script, HashMap::new()).expect("valid script");
+
+        let (_, alpha) = decoder
+            .read_video_frame_with_alpha::<u8>()
+            .expect("premultiplied frame");
+        let alpha = alpha.expect("frame has alpha");
+        assert!(alpha.premultiplied);
+        assert!(alpha.plane.rows().flatten().all(|&sample| sample == 200));
+
+        let (_, alpha) = decoder
+            .read_video_frame_with_alpha::<u8>()
+            .expect("straight frame");
+        assert!(!alpha.expect("frame has alpha").premultiplied);
+
+        let (_, alpha) = decoder
+            .read_video_frame_with_alpha::<u8>()
+            .expect("opaque frame");
+        assert!(alpha.is_none());
+        assert!(
+            decoder
+                .read_video_frame_with_alpha::<u8>()
+                .expect_err("past the end")
+                .is_eof()
+        );
+    }
+
+    #[test]
+    fn y4m_cannot_seek_to_keyframe() {
+        let data = y4m_fixture(&[16]);
+        let mut decoder = Decoder::from_bytes(data).expect("valid Y4M header");
+
+        assert!(matches!(
+            decoder.seek_to_keyframe::<u8>(0),
+            Err(DecoderError::UnsupportedDecoder)
+        ));
+    }
+
+    #[test]
+    fn overrides_metadata_but_not_frame_layout() {
+        let data = y4m_fixture(&[16]);
+        let mut decoder = Decoder::from_bytes(data).expect("valid Y4M header");
+
+        decoder
+            .override_video_details(|details| {
+                details.frame_rate = Rational32::new(24000, 1001);
+                details.color_range = ColorRange::Full;
+            })
+            .expect("metadata can be overridden");
+        assert_eq!(
+            decoder.get_video_details().frame_rate,
+            Rational32::new(24000, 1001)
+        );
+        assert_eq!(decoder.get_video_details().color_range, ColorRange::Full);
+
+        assert!(
+            decoder
+                .override_video_details(|details| details.width = 8)
+                .is_err()
+        );
+        assert!(
+            decoder
+                .override_video_details(|details| details.bit_depth = 10)
+                .is_err()
+        );
+        assert!(
+            decoder
+                .override_video_details(|details| details.frame_rate = Rational32::new(0, 1))
+                .is_err()
+        );
+        assert_eq!(decoder.get_video_details().width, 4);
+        decoder
+            .read_video_frame::<u8>()
+            .expect("frame layout unchanged");
+    }
+
+    #[test]
+    #[cfg(feature = "vapoursynth")]
+    fn vapoursynth_core_info_lists_std_plugin() {
+        let vapoursynth = VapoursynthDecoder::new().expect("VapourSynth environment");
+        let info = vapoursynth.core_info().expect("core is available");
+
+        assert!(info.core_version > 0);
+        let std_plugin = info
+            .plugins
+            .iter()
+            .find(|plugin| plugin.id == "com.vapoursynth.std")
+            .expect("std is built in");
+        assert_eq!(std_plugin.namespace, "std");
+
+        let data = y4m_fixture(&[]);
+        let decoder = Decoder::from_reader(std::io::Cursor::new(data)).expect("valid Y4M header");
+        assert!(matches!(
+            decoder.vapoursynth_core_info(),
+            Err(DecoderError::UnsupportedDecoder)
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "vapoursynth")]
+    fn vapoursynth_empty_environment_decodes_modifier_output() {
+        let empty = VapoursynthDecoder::new().expect("VapourSynth environment");
+        assert!(matches!(
+            Decoder::from_decoder_impl(DecoderImpl::Vapoursynth(empty)),
+            Err(DecoderError::NoVideoStream)
+        ));
+
+        let mut vapoursynth = VapoursynthDecoder::new().expect("VapourSynth environment");
+        vapoursynth
+            .register_node_modifier(Box::new(|core, node| {
+                assert!(node.is_none(), "empty environments have no output");
+                let api = vapoursynth::api::API::get().ok_or(DecoderError::NoDecoder)?;
+                let mut arguments = vapoursynth::map::OwnedMap::new(api);
+                arguments.set_int("length", 2).expect("set length");
+                core.get_plugin_by_namespace("std")
+                    .ok()
+                    .flatten()
+                    .ok_or(DecoderError::NoDecoder)?
+                    .invoke("BlankClip", &arguments)
+                    .map_err(|e| DecoderError::VapoursynthInternalError {
+                        cause: e.to_string(),
+                    })?
+                    .get_video_node("clip")
+                    .map_err(|e| DecoderError::VapoursynthInternalError {
+                        cause: e.to_string(),
+                    })
+            }))
+            .expect("modifier creates a clip");
+        let mut decoder = Decoder::from_decoder_impl(DecoderImpl::Vapoursynth(vapoursynth))
+            .expect("modifier output is decodable");
+
+        assert_eq!(decoder.get_video_details().total_frames, Some(2));
+        decoder.read_video_frame::<u8>().expect("first frame");
+        decoder.read_video_frame::<u8>().expect("second frame");
+        assert!(
+            decoder
+                .read_video_frame::<u8>()
+                .expect_err("end of clip")
+                .is_eof()
+        );
+    }
+
+    #[test]
+    fn into_decoder_impl_returns_backend() {
+        let data = y4m_fixture(&[16]);
+        let mut decoder = Decoder::from_bytes(data).expect("valid Y4M header");
+
+        assert!(matches!(
+            decoder.decoder_impl_mut(),
+            DecoderImpl::Y4mSeekable(_)
+        ));
+        let mut decoder = Decoder::from_decoder_impl(decoder.into_decoder_impl())
+            .expect("backend is still usable");
+        decoder.read_video_frame::<u8>().expect("first frame");
+    }
+
+    #[test]
+    fn probe_reads_y4m_header() {
+        let data = y4m_fixture(&[16]);
+        let path = temp_path("probe.y4m");
+        std::fs::write(&path, data).expect("write test file");
+
+        let details = Decoder::probe(&path);
+        std::fs::remove_file(&path).expect("remove test file");
+
+        let details = details.expect("valid Y4M header");
+        assert_eq!((details.width, details.height), (4, 2));
+        assert_eq!(details.frame_rate, Rational32::new(25, 1));
+    }
+
+    #[test]
+    fn odd_resolution_keeps_last_chroma_column_and_row() {
+        let mut data = b"YUV4MPEG2 W5 H3 F25:1 Ip A1:1 C420jpeg\nFRAME\n".to_vec();
+        data.extend_from_slice(&[16; 5 * 3]);
+        // 3x2 chroma planes, with the rounded-up column and row set apart
+        for edge in [200, 100] {
+            data.extend_from_slice(&[128, 128, edge, edge, edge, edge]);
+        }
+        let mut decoder = Decoder::from_bytes(data).expect("valid Y4M header");
+        assert_eq!(decoder.get_video_details().plane_dimensions(1), (3, 2));
+
+        let frame = decoder.read_video_frame::<u8>().expect("odd-sized frame");
+        let u_rows: Vec<&[u8]> = frame
+            .u_plane
+            .as_ref()
+            .expect("4:2:0 has chroma")
+            .rows()
+            .collect();
+        assert_eq!(u_rows, [[128, 128, 200], [200, 200, 200]]);
+        assert!(frame.y_plane.rows().flatten().all(|&sample| sample == 16));
+    }
+
+    #[test]
+    #[cfg(feature = "vapoursynth")]
+    fn vapoursynth_audio_outputs_are_skipped() {
+        let script = "import vapoursynth as vs\n\
+            vs.core.std.BlankAudio().set_output()";
+        assert!(matches!(
+            Decoder::from_script(script, HashMap::new()),
+            Err(DecoderError::NoVideoStream)
+        ));
+
+        let script = "import vapoursynth as vs\n\
+            vs.core.std.BlankAudio().set_output(0)\n\
+            vs.core.std.BlankClip(length=5).set_output(1)";
+        let decoder = Decoder::from_script(script, HashMap::new()).expect("video output");
+        assert_eq!(decoder.get_video_details().total_frames, Some(5));
+    }
+
+    #[test]
+    #[cfg(feature = "vapoursynth")]
+    fn vapoursynth_script_sees_variables() {
+        let script = "import vapoursynth as vs\n\
+            vs.core.std.BlankClip(length=10)[int(start):].set_output()";
+        let variables = HashMap::from([("start".to_string(), "3".to_string())]);
+        let decoder = Decoder::from_script(script, variables).expect("valid script");
+
+        assert_eq!(decoder.get_video_details().total_frames, Some(7));
+    }
+
+    #[test]
+    #[cfg(feature = "vapoursynth")]
+    fn vapoursynth_script_sees_typed_variables() {
+        let mut vapoursynth = VapoursynthDecoder::new().expect("VapourSynth environment");
+        vapoursynth
+            .set_variable_typed("start", VariableValue::Int(3))
+            .expect("int variable");
+        vapoursynth
+            .set_variable_typed("trim", VariableValue::Bool(true))
+            .expect("bool variable");
+        vapoursynth
+            .get_env()
+            .eval_script(
+                "import vapoursynth as vs\n\
+                 clip = vs.core.std.BlankClip(length=10)\n\
+                 (clip[start:] if trim else clip).set_output()",
+            )
+            .expect("script uses the variables without parsing them");
+        let decoder = Decoder::from_decoder_impl(DecoderImpl::Vapoursynth(vapoursynth))
+            .expect("video output");
+
+        assert_eq!(decoder.get_video_details().total_frames, Some(7));
+    }
+
+    #[test]
+    fn normalizes_y4m_output_in_rust() {
+        let mut data = y4m_fixture(&[64]);
+        let chroma_start = data.len() - 2 * 2;
+        data[chroma_start..].fill(128);
+        let mut decoder = Decoder::from_bytes(data).expect("valid Y4M header");
+
+        decoder
+            .set_normalized_output(2, 2, 10, ChromaSubsampling::Yuv444)
+            .expect("normalize");
+        let details = decoder.get_video_details();
+        assert_eq!(
+            (details.width, details.height, details.bit_depth),
+            (2, 2, 10)
+        );
+        assert!(matches!(
+            decoder.set_crop(0, 0, 1, 0),
+            Err(DecoderError::UnsupportedDecoder)
+        ));
+
+        let frame = decoder.read_video_frame::<u16>().expect("normalized frame");
+        assert!(frame.y_plane.rows().flatten().all(|&sample| sample == 256));
+        let v_plane = frame.v_plane.as_ref().expect("4:4:4 has chroma");
+        assert_eq!(v_plane.rows().flatten().count(), 4);
+        assert!(v_plane.rows().flatten().all(|&sample| sample == 512));
+    }
+
+    #[test]
+    fn current_frame_index_follows_reads_and_seeks() {
+        let data = y4m_fixture(&[16, 32, 48]);
+        let mut decoder = Decoder::from_bytes(data).expect("valid Y4M header");
+        assert_eq!(decoder.current_frame_index(), 0);
+
+        decoder.read_video_frame::<u8>().expect("frame 0");
+        assert_eq!(decoder.current_frame_index(), 1);
+        decoder.seek_to_frame(2).expect("seek forward");
+        assert_eq!(decoder.current_frame_index(), 2);
+        decoder.seek_to_frame(0).expect("seek back");
+        assert_eq!(decoder.current_frame_index(), 0);
+    }
+
+    #[test]
+    fn read_all_frames_reads_remaining_frames() {
+        let data = y4m_fixture(&[16, 32, 48]);
+        let mut decoder = Decoder::from_bytes(data).expect("valid Y4M header");
+
+        decoder.skip_frames(1).expect("skip first frame");
+        let frames = decoder.read_all_frames::<u8>().expect("remaining frames");
+        assert_eq!(frames.len(), 2);
+        assert!(
+            frames[1]
+                .y_plane
+                .rows()
+                .flatten()
+                .all(|&sample| sample == 48)
+        );
+        assert!(
+            decoder
+                .read_all_frames::<u8>()
+                .expect("no frames left")
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn try_reopen_restarts_in_memory_y4m() {
+        let data = y4m_fixture(&[16, 32]);
+        let mut decoder = Decoder::from_bytes(data.clone()).expect("valid Y4M header");
+        decoder.set_crop(0, 0, 2, 0).expect("aligned crop");
+        decoder
+            .override_video_details(|details| details.frame_rate = Rational32::new(30, 1))
+            .expect("metadata only");
+        decoder.read_video_frame::<u8>().expect("first frame");
+
+        let mut reopened = decoder.try_reopen().expect("reopenable source");
+        assert_eq!(reopened.get_video_details().width, 2);
+        assert_eq!(
+            reopened.get_video_details().frame_rate,
+            Rational32::new(30, 1)
+        );
+        let frame = reopened
+            .read_video_frame::<u8>()
+            .expect("first frame again");
+        assert!(frame.y_plane.rows().flatten().all(|&sample| sample == 16));
+
+        let decoder = Decoder::from_reader(std::io::Cursor::new(data)).expect("valid Y4M header");
+        assert!(matches!(
+            decoder.try_reopen(),
+            Err(DecoderError::UnsupportedDecoder)
+        ));
+    }
+
+    #[test]
+    fn missing_file_reports_not_found() {
+        let path = temp_path("missing.y4m");
+
+        match Decoder::from_file(&path) {
+            Err(DecoderError::Io { kind, .. }) => {
+                assert_eq!(kind, std::io::ErrorKind::NotFound);
+            }
+            Err(e) => panic!("unexpected error: {e}"),
+            Ok(_) => panic!("file does not exist"),
+        }
+    }
+
+    #[test]
+    fn frame_index_at_rounds_ntsc_rates() {
+        let ntsc = Rational32::new(24000, 1001);
+        assert_eq!(frame_index_at(5.0, ntsc, None), 120);
+        // Exact presentation times of frames 1 and 1001
+        assert_eq!(frame_index_at(1001.0 / 24000.0, ntsc, None), 1);
+        assert_eq!(frame_index_at(1001.0 * 1001.0 / 24000.0, ntsc, None), 1001);
+        assert_eq!(
+            frame_index_at(60.0, Rational32::new(30000, 1001), None),
+            1798
+        );
+    }
+
+    #[test]
+    fn frame_index_at_clamps_to_video() {
+        let rate = Rational32::new(25, 1);
+        assert_eq!(frame_index_at(-1.0, rate, Some(10)), 0);
+        assert_eq!(frame_index_at(f64::NAN, rate, Some(10)), 0);
+        assert_eq!(frame_index_at(100.0, rate, Some(10)), 9);
+    }
+
+    #[test]
+    #[cfg(feature = "memmap")]
+    fn from_file_mmap_seeks_and_detects_truncation() {
+        let data = y4m_fixture(&[16, 32]);
+        let path = temp_path("mmap.y4m");
+        std::fs::write(&path, &data).expect("write test file");
+
+        let mut decoder = Decoder::from_file_mmap(&path).expect("valid Y4M header");
+        decoder.seek_to_frame(1).expect("seek forward");
+        let frame = decoder.read_video_frame::<u8>().expect("frame 1");
+        assert!(frame.y_plane.rows().flatten().all(|&sample| sample == 32));
+        decoder.seek_to_frame(0).expect("seek backward");
+
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|file| file.set_len(data.len() as u64 - 4))
+            .expect("truncate test file");
+        let result = decoder.read_video_frame::<u8>();
+        std::fs::remove_file(&path).expect("remove test file");
+
+        assert!(matches!(
+            result,
+            Err(DecoderError::Io {
+                kind: std::io::ErrorKind::InvalidData,
+                ..
+            })
+        ));
+    }
 }
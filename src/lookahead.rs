@@ -0,0 +1,83 @@
+use std::any::Any;
+use std::collections::VecDeque;
+
+use v_frame::{frame::Frame, pixel::Pixel};
+
+use crate::FrameMeta;
+use crate::error::DecoderError;
+
+/// Bounded window of recently decoded frames, used to seek within streams that cannot
+/// otherwise seek.
+///
+/// Frames are stored type-erased, since the pixel type is only chosen per read.
+pub(crate) struct Lookahead {
+    capacity: usize,
+    frames: VecDeque<(usize, Box<dyn Any>, FrameMeta)>,
+    /// Index of the next frame the backend will produce
+    head: usize,
+}
+
+impl Lookahead {
+    pub(crate) fn new(capacity: usize, head: usize) -> Self {
+        Self {
+            capacity,
+            frames: VecDeque::with_capacity(capacity),
+            head,
+        }
+    }
+
+    pub(crate) const fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the index of the next frame the backend will produce.
+    pub(crate) const fn head(&self) -> usize {
+        self.head
+    }
+
+    /// Returns `true` if reading can resume at `index` without seeking the backend.
+    pub(crate) fn can_seek_to(&self, index: usize) -> bool {
+        index == self.head || self.contains(index)
+    }
+
+    pub(crate) fn contains(&self, index: usize) -> bool {
+        self.frames.iter().any(|&(i, ..)| i == index)
+    }
+
+    /// Drops all buffered frames and restarts the window at `head`.
+    pub(crate) fn reset(&mut self, head: usize) {
+        self.frames.clear();
+        self.head = head;
+    }
+
+    /// Returns a copy of the buffered frame at `index` and what the backend reported
+    /// about it, if there is one.
+    pub(crate) fn get<T: Pixel>(
+        &self,
+        index: usize,
+    ) -> Result<Option<(Frame<T>, FrameMeta)>, DecoderError> {
+        self.frames
+            .iter()
+            .find(|&&(i, ..)| i == index)
+            .map(|(_, frame, meta)| {
+                frame
+                    .downcast_ref::<Frame<T>>()
+                    .cloned()
+                    .map(|frame| (frame, *meta))
+                    .ok_or_else(|| DecoderError::GenericDecodeError {
+                        cause: "buffered frame was decoded with a different pixel type".to_string(),
+                        source: None,
+                    })
+            })
+            .transpose()
+    }
+
+    /// Buffers a freshly decoded frame, evicting the oldest one if the window is full.
+    pub(crate) fn push<T: Pixel>(&mut self, index: usize, frame: Frame<T>, meta: FrameMeta) {
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+        self.frames.push_back((index, Box::new(frame), meta));
+        self.head = self.head.max(index + 1);
+    }
+}
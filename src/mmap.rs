@@ -0,0 +1,70 @@
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::rc::Rc;
+
+use memmap2::Mmap;
+
+/// A file mapped into memory, along with the handle used to detect truncation.
+pub(crate) struct MappedFile {
+    file: File,
+    map: Mmap,
+}
+
+impl MappedFile {
+    pub(crate) fn open(path: &Path) -> io::Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: the mapping is only read through `MappedReader`, which checks that the
+        // file still covers the whole mapping before every read
+        let map = unsafe { Mmap::map(&file)? };
+        Ok(Self { file, map })
+    }
+}
+
+/// Reads a [`MappedFile`] from the start.
+///
+/// Touching a mapped page past the end of a file that was truncated after mapping raises
+/// `SIGBUS`, so each read first checks the file size and fails with
+/// [`io::ErrorKind::InvalidData`] instead; not `UnexpectedEof`, which the Y4M parser
+/// would report as a normal end of stream.
+pub(crate) struct MappedReader {
+    file: Rc<MappedFile>,
+    position: usize,
+}
+
+impl MappedReader {
+    pub(crate) const fn new(file: Rc<MappedFile>) -> Self {
+        Self { file, position: 0 }
+    }
+}
+
+impl Read for MappedReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mapped = &self.file.map[..];
+        if self.file.file.metadata()?.len() < mapped.len() as u64 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "file was truncated while mapped",
+            ));
+        }
+        let remaining = &mapped[self.position.min(mapped.len())..];
+        let count = remaining.len().min(buf.len());
+        buf[..count].copy_from_slice(&remaining[..count]);
+        self.position += count;
+        Ok(count)
+    }
+}
+
+impl Seek for MappedReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => (self.file.map.len() as u64).checked_add_signed(offset),
+            SeekFrom::Current(offset) => (self.position as u64).checked_add_signed(offset),
+        }
+        .and_then(|position| usize::try_from(position).ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid seek position"))?;
+        self.position = position;
+        Ok(position as u64)
+    }
+}
@@ -0,0 +1,177 @@
+use std::{
+    ops::Range,
+    sync::{
+        Arc,
+        mpsc::{self, Receiver, SyncSender},
+    },
+    thread, vec,
+};
+
+use v_frame::pixel::Pixel;
+
+use crate::worker::{FrameResult, send_frames};
+use crate::{Decoder, DecoderError};
+
+/// Number of decoded frames each range may buffer before its worker blocks.
+const RANGE_BUFFER_FRAMES: usize = 4;
+
+impl Decoder {
+    /// Decodes several frame ranges in parallel, yielding their frames in order.
+    ///
+    /// Spawns `workers` threads (at least one), each of which calls `open` to create its own
+    /// decoder, then seeks to the start of each range assigned to it. Ranges are assigned
+    /// round-robin and yielded in the order given, frame by frame.
+    ///
+    /// `open` creates each worker's decoder on its thread, see
+    /// [decoding on other threads](crate#decoding-on-other-threads), so it must build a
+    /// fresh decoder for the same source each time it is called. Only decoders that
+    /// [support seeking](Self::supports_seeking) can decode ranges; others yield
+    /// [`DecoderError::UnsupportedDecoder`].
+    ///
+    /// # Memory
+    ///
+    /// Each range buffers up to a few decoded frames ahead of the consumer, so up to
+    /// `workers` ranges' worth of buffered frames can be alive at once, in addition to
+    /// each backend's own caches. Uncompressed frames are large; keep `workers` modest for
+    /// high resolutions.
+    ///
+    /// Errors from opening, seeking, or decoding are yielded in place of the frames of the
+    /// affected range; the remaining ranges are still decoded.
+    #[inline]
+    #[must_use]
+    pub fn decode_range_parallel<T, F>(
+        open: F,
+        ranges: &[Range<usize>],
+        workers: usize,
+    ) -> ParallelFrames<T>
+    where
+        T: Pixel + Send + 'static,
+        F: Fn() -> Result<Decoder, DecoderError> + Send + Sync + 'static,
+    {
+        let workers = workers.max(1);
+        let open = Arc::new(open);
+
+        let mut receivers = Vec::with_capacity(ranges.len());
+        let mut jobs: Vec<Vec<(Range<usize>, SyncSender<FrameResult<T>>)>> =
+            (0..workers).map(|_| Vec::new()).collect();
+        for (index, range) in ranges.iter().enumerate() {
+            let (tx, rx) = mpsc::sync_channel(RANGE_BUFFER_FRAMES);
+            receivers.push(rx);
+            jobs[index % workers].push((range.clone(), tx));
+        }
+
+        for worker_jobs in jobs.into_iter().filter(|jobs| !jobs.is_empty()) {
+            let open = Arc::clone(&open);
+            thread::spawn(move || {
+                let mut decoder = match open().and_then(|decoder| {
+                    if decoder.supports_seeking() {
+                        Ok(decoder)
+                    } else {
+                        Err(DecoderError::UnsupportedDecoder)
+                    }
+                }) {
+                    Ok(decoder) => decoder,
+                    Err(e) => {
+                        for (_, tx) in worker_jobs {
+                            let _ = tx.send(Err(e.clone()));
+                        }
+                        return;
+                    }
+                };
+                for (range, tx) in worker_jobs {
+                    if let Err(e) = send_range(&mut decoder, range, &tx) {
+                        let _ = tx.send(Err(e));
+                    }
+                }
+            });
+        }
+
+        let mut receivers = receivers.into_iter();
+        ParallelFrames {
+            current: receivers.next(),
+            receivers,
+        }
+    }
+}
+
+/// Decodes a single range and sends its frames, stopping early if the consumer hung up.
+fn send_range<T: Pixel>(
+    decoder: &mut Decoder,
+    range: Range<usize>,
+    tx: &SyncSender<FrameResult<T>>,
+) -> Result<(), DecoderError> {
+    if range.is_empty() {
+        return Ok(());
+    }
+    decoder.seek_to_frame(range.start)?;
+    send_frames(decoder, Some(range.len()), |frame| tx.send(frame).is_ok());
+    Ok(())
+}
+
+/// Iterator over the frames produced by [`Decoder::decode_range_parallel`].
+///
+/// Dropping the iterator stops the workers after their current frame.
+pub struct ParallelFrames<T: Pixel> {
+    current: Option<Receiver<FrameResult<T>>>,
+    receivers: vec::IntoIter<Receiver<FrameResult<T>>>,
+}
+
+impl<T: Pixel> Iterator for ParallelFrames<T> {
+    type Item = FrameResult<T>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.current.as_ref()?.recv() {
+                Ok(item) => return Some(item),
+                // The worker finished this range
+                Err(_) => self.current = self.receivers.next(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::y4m_fixture;
+
+    #[test]
+    fn decodes_ranges_in_the_given_order() {
+        let data = y4m_fixture(&[16, 32, 48, 64]);
+        let frames = Decoder::decode_range_parallel::<u8, _>(
+            move || Decoder::from_bytes(data.clone()),
+            &[2..4, 0..2],
+            2,
+        );
+        let values: Vec<_> = frames
+            .map(|frame| {
+                frame
+                    .expect("decoded frame")
+                    .y_plane
+                    .rows()
+                    .flatten()
+                    .copied()
+                    .next()
+            })
+            .collect();
+
+        assert_eq!(values, [Some(48), Some(64), Some(16), Some(32)]);
+    }
+
+    #[test]
+    fn rejects_decoders_that_cannot_seek() {
+        let data = y4m_fixture(&[16]);
+        let mut frames = Decoder::decode_range_parallel::<u8, _>(
+            move || Decoder::from_reader(std::io::Cursor::new(data.clone())),
+            &[0..1],
+            1,
+        );
+
+        assert!(matches!(
+            frames.next(),
+            Some(Err(DecoderError::UnsupportedDecoder))
+        ));
+        assert!(frames.next().is_none());
+    }
+}
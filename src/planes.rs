@@ -0,0 +1,149 @@
+use v_frame::{frame::Frame, pixel::Pixel, plane::Plane};
+
+use crate::{Decoder, DecoderError};
+
+impl Decoder {
+    /// Decodes the next frame and returns its Y, U, and V planes as contiguous buffers
+    /// holding only the visible samples, e.g. for metric libraries such as SSIM that
+    /// expect tightly packed planes.
+    ///
+    /// Chroma planes have the subsampled dimensions of the video's chroma sampling. For
+    /// monochrome video or with [`set_luma_only`](Self::set_luma_only), the chroma planes
+    /// are empty, with a width and height of 0.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`read_video_frame`](Self::read_video_frame).
+    #[inline]
+    pub fn read_planes<T: Pixel>(&mut self) -> Result<[PackedPlane<T>; 3], DecoderError> {
+        let frame = self.read_video_frame::<T>()?;
+        Ok(frame_planes(&frame))
+    }
+}
+
+/// Copies the visible samples of each plane of `frame`, with an empty plane for each
+/// missing chroma plane.
+fn frame_planes<T: Pixel>(frame: &Frame<T>) -> [PackedPlane<T>; 3] {
+    [
+        PackedPlane::from(&frame.y_plane),
+        frame
+            .u_plane
+            .as_ref()
+            .map_or_else(PackedPlane::empty, PackedPlane::from),
+        frame
+            .v_plane
+            .as_ref()
+            .map_or_else(PackedPlane::empty, PackedPlane::from),
+    ]
+}
+
+/// A copy of the visible samples of one plane, tightly packed without padding, returned
+/// by [`Decoder::read_planes`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackedPlane<T: Pixel> {
+    data: Vec<T>,
+    width: usize,
+    height: usize,
+}
+
+impl<T: Pixel> PackedPlane<T> {
+    const fn empty() -> Self {
+        Self {
+            data: Vec::new(),
+            width: 0,
+            height: 0,
+        }
+    }
+
+    /// Returns the visible samples in row-major order.
+    #[inline]
+    #[must_use]
+    pub fn data(&self) -> &[T] {
+        &self.data
+    }
+
+    /// Returns the width of the plane in samples.
+    #[inline]
+    #[must_use]
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the height of the plane in rows.
+    #[inline]
+    #[must_use]
+    pub const fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the number of samples from the start of one row to the start of the next,
+    /// which always equals the width since rows are tightly packed.
+    #[inline]
+    #[must_use]
+    pub const fn stride(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the visible samples of row `y`, or `None` if it is out of bounds.
+    #[inline]
+    #[must_use]
+    pub fn row(&self, y: usize) -> Option<&[T]> {
+        (y < self.height).then(|| &self.data[y * self.width..(y + 1) * self.width])
+    }
+}
+
+impl<T: Pixel> From<&Plane<T>> for PackedPlane<T> {
+    /// Copies the visible samples of `plane`, dropping its padding.
+    #[inline]
+    fn from(plane: &Plane<T>) -> Self {
+        let width = plane.rows().next().map_or(0, <[T]>::len);
+        let height = plane.rows().count();
+        Self {
+            data: plane.rows().flatten().copied().collect(),
+            width,
+            height,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::y4m_fixture;
+
+    #[test]
+    fn strips_padding_and_subsamples_chroma() {
+        let mut data = y4m_fixture(&[16]);
+        let chroma_start = data.len() - 2 * 2;
+        data[chroma_start..].copy_from_slice(&[128, 129, 130, 131]);
+        let mut decoder =
+            Decoder::from_reader(std::io::Cursor::new(data)).expect("valid Y4M header");
+        decoder.set_padding(2, 2, 2, 2).expect("even padding");
+
+        let [y_plane, u_plane, v_plane] = decoder.read_planes::<u8>().expect("frame");
+
+        assert_eq!(
+            (y_plane.width(), y_plane.height(), y_plane.stride()),
+            (4, 2, 4)
+        );
+        assert_eq!(y_plane.data(), [16; 4 * 2]);
+        assert_eq!((u_plane.width(), u_plane.height()), (2, 1));
+        assert_eq!(u_plane.data(), [128, 129]);
+        assert_eq!(v_plane.row(0), Some(&[130, 131][..]));
+        assert_eq!(v_plane.row(1), None);
+    }
+
+    #[test]
+    fn luma_only_has_empty_chroma() {
+        let data = y4m_fixture(&[16]);
+        let mut decoder =
+            Decoder::from_reader(std::io::Cursor::new(data)).expect("valid Y4M header");
+        decoder.set_luma_only(true);
+
+        let [y_plane, u_plane, _] = decoder.read_planes::<u8>().expect("frame");
+
+        assert_eq!(y_plane.data().len(), 4 * 2);
+        assert_eq!((u_plane.width(), u_plane.height()), (0, 0));
+        assert!(u_plane.data().is_empty());
+    }
+}
@@ -0,0 +1,17 @@
+use image::RgbImage;
+use v_frame::{frame::Frame, pixel::Pixel};
+
+use crate::{VideoDetails, rgb::frame_to_rgb24};
+
+/// Converts a decoded frame to an 8-bit RGB image, as described in [`frame_to_rgb24`].
+pub(crate) fn frame_to_rgb<T: Pixel + Into<u32>>(
+    frame: &Frame<T>,
+    details: &VideoDetails,
+) -> RgbImage {
+    RgbImage::from_raw(
+        details.width as u32,
+        details.height as u32,
+        frame_to_rgb24(frame, details),
+    )
+    .expect("one RGB triple per pixel")
+}
@@ -0,0 +1,127 @@
+use std::mem::size_of;
+
+use v_frame::{frame::Frame, pixel::Pixel, plane::Plane};
+
+use crate::{Decoder, DecoderError, encoder::write_plane};
+
+impl Decoder {
+    /// Decodes the next frame into plain byte buffers, without the [`Pixel`] generic of
+    /// [`read_video_frame`](Self::read_video_frame).
+    ///
+    /// This is meant for FFI consumers and language bindings, which can work with
+    /// `&[u8]` planes directly instead of choosing between `u8` and `u16` frames. Sources
+    /// up to 8 bits use one byte per sample; deeper sources use two, little-endian.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`read_video_frame`](Self::read_video_frame).
+    #[inline]
+    pub fn read_video_frame_raw(&mut self) -> Result<RawFrame, DecoderError> {
+        let bit_depth = self.video_details.bit_depth;
+        if bit_depth > 8 {
+            self.read_video_frame::<u16>()
+                .map(|frame| RawFrame::new(&frame, bit_depth))
+        } else {
+            self.read_video_frame::<u8>()
+                .map(|frame| RawFrame::new(&frame, bit_depth))
+        }
+    }
+}
+
+/// A decoded frame stored as raw bytes, returned by [`Decoder::read_video_frame_raw`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawFrame {
+    /// The Y, U, and V planes. Chroma planes are `None` for luma-only decoding.
+    pub planes: [Option<RawPlane>; 3],
+    /// Significant bits per sample. Samples take two bytes above 8 bits.
+    pub bit_depth: usize,
+}
+
+impl RawFrame {
+    fn new<T: Pixel + Into<u32>>(frame: &Frame<T>, bit_depth: usize) -> Self {
+        Self {
+            planes: [
+                Some(RawPlane::new(&frame.y_plane)),
+                frame.u_plane.as_ref().map(RawPlane::new),
+                frame.v_plane.as_ref().map(RawPlane::new),
+            ],
+            bit_depth,
+        }
+    }
+
+    /// Returns the number of bytes each sample occupies, 1 or 2.
+    #[inline]
+    #[must_use]
+    pub const fn bytes_per_sample(&self) -> usize {
+        if self.bit_depth > 8 { 2 } else { 1 }
+    }
+}
+
+/// One plane of a [`RawFrame`], holding only the visible samples without padding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawPlane {
+    /// Samples in row-major order. Samples above 8 bits are little-endian `u16`s.
+    pub data: Vec<u8>,
+    /// Number of bytes from the start of one row to the start of the next.
+    pub stride: usize,
+    /// Width of the plane in samples.
+    pub width: usize,
+    /// Height of the plane in rows.
+    pub height: usize,
+}
+
+impl RawPlane {
+    fn new<T: Pixel + Into<u32>>(plane: &Plane<T>) -> Self {
+        let mut data = Vec::new();
+        write_plane(plane, size_of::<T>(), &mut data);
+        let width = plane.rows().next().map_or(0, <[T]>::len);
+        let height = plane.rows().count();
+        Self {
+            data,
+            stride: width * size_of::<T>(),
+            width,
+            height,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VideoDetails;
+    use crate::test_util::temp_path;
+
+    #[test]
+    fn reads_high_bit_depth_planes_as_little_endian_bytes() {
+        let details = VideoDetails {
+            width: 4,
+            height: 2,
+            bit_depth: 10,
+            ..VideoDetails::default()
+        };
+        let mut data = Vec::new();
+        for sample in [0x0123_u16; 4 * 2].into_iter().chain([0x0200; 2 * 2]) {
+            data.extend_from_slice(&sample.to_le_bytes());
+        }
+        let source = temp_path("raw-frame.yuv");
+        std::fs::write(&source, &data).expect("write test file");
+
+        let frame = Decoder::from_raw_yuv(&source, details)
+            .and_then(|mut decoder| decoder.read_video_frame_raw())
+            .expect("10-bit frame");
+        std::fs::remove_file(&source).expect("remove test file");
+
+        assert_eq!(frame.bit_depth, 10);
+        assert_eq!(frame.bytes_per_sample(), 2);
+        let [y_plane, u_plane, v_plane] = frame.planes;
+        let y_plane = y_plane.expect("luma plane");
+        assert_eq!((y_plane.width, y_plane.height, y_plane.stride), (4, 2, 8));
+        assert_eq!(y_plane.data[..2], [0x23, 0x01]);
+        let u_plane = u_plane.expect("4:2:0 has chroma");
+        assert_eq!((u_plane.width, u_plane.height, u_plane.stride), (2, 1, 4));
+        assert_eq!(
+            v_plane.expect("4:2:0 has chroma").data,
+            [0x00, 0x02, 0x00, 0x02]
+        );
+    }
+}
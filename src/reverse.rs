@@ -0,0 +1,110 @@
+use std::marker::PhantomData;
+
+use v_frame::{frame::Frame, pixel::Pixel};
+
+use crate::{Decoder, DecoderError};
+
+impl Decoder {
+    /// Returns an iterator over all frames of the video from the last to the first, e.g.
+    /// for backward motion search.
+    ///
+    /// Each frame is reached with [`seek_to_frame`](Self::seek_to_frame) on this decoder,
+    /// so only backends that can seek anywhere are supported: VapourSynth, FFMS2, and Y4M
+    /// files or other seekable Y4M sources, such as [`from_bytes`](Self::from_bytes) and
+    /// [`from_seekable_reader`](Self::from_seekable_reader). If the frame count of
+    /// the video is unknown, it is counted first by skipping through the whole video.
+    ///
+    /// After the iterator is exhausted, the decoder is positioned after the first frame.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecoderError::UnsupportedDecoder`] if the backend cannot seek anywhere,
+    /// and any error from counting the frames. Errors reading individual frames are
+    /// yielded by the iterator.
+    #[inline]
+    pub fn frames_rev<T: Pixel>(&mut self) -> Result<FramesRev<'_, T>, DecoderError> {
+        if !self.supports_seeking() {
+            return Err(DecoderError::UnsupportedDecoder);
+        }
+        let remaining = match self.video_details.total_frames {
+            Some(total_frames) => total_frames,
+            None => {
+                self.seek_to_frame(0)?;
+                match self.skip_frames(usize::MAX) {
+                    Ok(()) | Err(DecoderError::EndOfFile) => self.frames_read,
+                    Err(e) => return Err(e),
+                }
+            }
+        };
+        Ok(FramesRev {
+            decoder: self,
+            remaining,
+            pixel: PhantomData,
+        })
+    }
+}
+
+/// Iterator over the frames of a video in reverse order, created by
+/// [`Decoder::frames_rev`].
+pub struct FramesRev<'a, T> {
+    decoder: &'a mut Decoder,
+    /// Number of frames left to yield, which is also one past the next frame's index
+    remaining: usize,
+    pixel: PhantomData<T>,
+}
+
+impl<T: Pixel> Iterator for FramesRev<'_, T> {
+    type Item = Result<Frame<T>, DecoderError>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.remaining = self.remaining.checked_sub(1)?;
+        Some(
+            self.decoder
+                .seek_to_frame(self.remaining)
+                .and_then(|()| self.decoder.read_video_frame()),
+        )
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T: Pixel> ExactSizeIterator for FramesRev<'_, T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::y4m_fixture;
+
+    #[test]
+    fn yields_in_memory_y4m_frames_backwards() {
+        let data = y4m_fixture(&[16, 32, 48]);
+        let mut decoder = Decoder::from_bytes(data).expect("valid Y4M header");
+
+        let first_luma: Vec<u8> = decoder
+            .frames_rev::<u8>()
+            .expect("in-memory Y4M can seek")
+            .map(|frame| {
+                let frame = frame.expect("frame in range");
+                let luma = frame.y_plane.rows().flatten().next().copied();
+                luma.expect("luma sample")
+            })
+            .collect();
+        assert_eq!(first_luma, [48, 32, 16]);
+    }
+
+    #[test]
+    fn rejects_streams_that_cannot_seek() {
+        let data = y4m_fixture(&[16]);
+        let mut decoder =
+            Decoder::from_reader(std::io::Cursor::new(data)).expect("valid Y4M header");
+
+        assert!(matches!(
+            decoder.frames_rev::<u8>(),
+            Err(DecoderError::UnsupportedDecoder)
+        ));
+    }
+}
@@ -0,0 +1,174 @@
+use v_frame::{frame::Frame, pixel::Pixel};
+
+use crate::{ColorRange, Decoder, DecoderError, MatrixCoefficients, VideoDetails};
+
+impl Decoder {
+    /// Decodes the next video frame and converts it to packed 8-bit RGB, returning the
+    /// pixels along with the width and height.
+    ///
+    /// Pixels are stored row by row as `R, G, B` bytes with no padding. The conversion is
+    /// done in Rust, so it works in every build. It uses the BT.709 or BT.2020 matrix when
+    /// [`VideoDetails::matrix_coefficients`] signals one, and BT.601 otherwise, except that
+    /// unspecified HD video is assumed to be BT.709. Samples are scaled according to
+    /// [`VideoDetails::color_range`], chroma is upsampled by nearest neighbor, and high bit
+    /// depth video is rounded to 8 bits. Intended for previews and visualization rather
+    /// than accurate color.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`read_video_frame`](Self::read_video_frame).
+    #[inline]
+    pub fn read_rgb24(&mut self) -> Result<(Vec<u8>, usize, usize), DecoderError> {
+        let details = self.video_details;
+        let rgb = if details.bit_depth > 8 {
+            frame_to_rgb24(&self.read_video_frame::<u16>()?, &details)
+        } else {
+            frame_to_rgb24(&self.read_video_frame::<u8>()?, &details)
+        };
+        Ok((rgb, details.width, details.height))
+    }
+}
+
+/// Converts a decoded frame to packed 8-bit RGB.
+///
+/// The YCbCr matrix follows [`VideoDetails::matrix_coefficients`]: BT.709 and BT.2020 are
+/// used when signaled, unspecified matrices are assumed to be BT.709 above 576 lines and
+/// BT.601 otherwise, and anything else is treated as BT.601. Samples are scaled according
+/// to [`VideoDetails::color_range`]. Chroma is upsampled by nearest neighbor, and high bit
+/// depth samples are rounded to 8 bits. Frames without chroma planes are converted as
+/// grayscale.
+pub(crate) fn frame_to_rgb24<T: Pixel + Into<u32>>(
+    frame: &Frame<T>,
+    details: &VideoDetails,
+) -> Vec<u8> {
+    let scale = f32::from(1u16 << (details.bit_depth.saturating_sub(8) as u16));
+    let (ss_x, ss_y) = details
+        .chroma_sampling
+        .subsample_ratio()
+        .map_or((1, 1), |(x, y)| (x.get() as usize, y.get() as usize));
+    let (kr, kb) = luma_coefficients(details);
+    let kg = 1.0 - kr - kb;
+    let (luma_offset, luma_gain, chroma_gain) = match details.color_range {
+        ColorRange::Limited => (16.0, 255.0 / 219.0, 255.0 / 224.0),
+        ColorRange::Full => (0.0, 1.0, 1.0),
+    };
+
+    let chroma_rows =
+        frame
+            .u_plane
+            .as_ref()
+            .zip(frame.v_plane.as_ref())
+            .map(|(u_plane, v_plane)| {
+                (
+                    u_plane.rows().collect::<Vec<&[T]>>(),
+                    v_plane.rows().collect::<Vec<&[T]>>(),
+                )
+            });
+    let sample = |value: T| Into::<u32>::into(value) as f32 / scale;
+
+    let mut rgb = Vec::with_capacity(details.width * details.height * 3);
+    for (y, row) in frame.y_plane.rows().enumerate() {
+        for (x, &luma) in row.iter().enumerate() {
+            let luma = (sample(luma) - luma_offset) * luma_gain;
+            let Some((u_rows, v_rows)) = chroma_rows.as_ref() else {
+                let gray = to_u8(luma);
+                rgb.extend_from_slice(&[gray, gray, gray]);
+                continue;
+            };
+            let cb = (sample(u_rows[y / ss_y][x / ss_x]) - 128.0) * chroma_gain;
+            let cr = (sample(v_rows[y / ss_y][x / ss_x]) - 128.0) * chroma_gain;
+
+            let r = (2.0 * (1.0 - kr)).mul_add(cr, luma);
+            let b = (2.0 * (1.0 - kb)).mul_add(cb, luma);
+            let g = (luma - kr * r - kb * b) / kg;
+            rgb.extend_from_slice(&[to_u8(r), to_u8(g), to_u8(b)]);
+        }
+    }
+    rgb
+}
+
+/// Returns the red and blue luma coefficients `(Kr, Kb)` of the video's YCbCr matrix.
+fn luma_coefficients(details: &VideoDetails) -> (f32, f32) {
+    const BT601: (f32, f32) = (0.299, 0.114);
+    const BT709: (f32, f32) = (0.2126, 0.0722);
+    const BT2020: (f32, f32) = (0.2627, 0.0593);
+
+    match details.matrix_coefficients {
+        MatrixCoefficients::Bt709 => BT709,
+        MatrixCoefficients::Bt2020Ncl | MatrixCoefficients::Bt2020Cl => BT2020,
+        MatrixCoefficients::Unspecified if details.height > 576 => BT709,
+        _ => BT601,
+    }
+}
+
+fn to_u8(value: f32) -> u8 {
+    value.round().clamp(0.0, 255.0) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::y4m_fixture;
+
+    /// Builds a one-frame stream with flat `luma` and neutral chroma, with `extra_params`
+    /// appended to the stream header.
+    fn y4m_frame(extra_params: &str, luma: u8) -> Decoder {
+        let mut data = y4m_fixture(&[luma]);
+        let chroma_start = data.len() - 2 * 2;
+        data[chroma_start..].fill(128);
+        let header_end = y4m_fixture(&[]).len() - 1;
+        data.splice(header_end..header_end, extra_params.bytes());
+        Decoder::from_bytes(data).expect("valid Y4M header")
+    }
+
+    #[test]
+    fn converts_limited_range_extremes() {
+        let (rgb, width, height) = y4m_frame("", 235).read_rgb24().expect("frame");
+        assert_eq!((width, height), (4, 2));
+        assert_eq!(rgb.len(), 4 * 2 * 3);
+        assert!(rgb.iter().all(|&value| value == 255));
+
+        let (rgb, ..) = y4m_frame("", 16).read_rgb24().expect("frame");
+        assert!(rgb.iter().all(|&value| value == 0));
+    }
+
+    #[test]
+    fn honors_full_range() {
+        let (rgb, ..) = y4m_frame(" XCOLORRANGE=FULL", 255)
+            .read_rgb24()
+            .expect("frame");
+        assert!(rgb.iter().all(|&value| value == 255));
+    }
+
+    #[test]
+    fn picks_matrix_from_details() {
+        let details = VideoDetails {
+            width: 1,
+            height: 1,
+            chroma_sampling: v_frame::chroma::ChromaSubsampling::Yuv444,
+            ..VideoDetails::default()
+        };
+        let bt709 = VideoDetails {
+            matrix_coefficients: MatrixCoefficients::Bt709,
+            ..details
+        };
+        let mut red = details.new_black_frame::<u8>().expect("black frame");
+        // Pure red in limited range BT.601
+        for (plane, value) in [
+            (Some(&mut red.y_plane), 81),
+            (red.u_plane.as_mut(), 90),
+            (red.v_plane.as_mut(), 240),
+        ] {
+            let plane = plane.expect("4:4:4 has all planes");
+            plane
+                .rows_mut()
+                .flatten()
+                .for_each(|sample| *sample = value);
+        }
+
+        let rgb = frame_to_rgb24(&red, &details);
+        assert!(rgb[0] >= 254 && rgb[1] <= 1 && rgb[2] <= 1);
+        let rgb = frame_to_rgb24(&red, &bt709);
+        assert!(rgb[1] > 1, "BT.709 shifts BT.601 red towards green");
+    }
+}
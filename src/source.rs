@@ -0,0 +1,165 @@
+//! Source filters that load a video file into a VapourSynth [`Node`].
+//!
+//! These are meant to be called from a node modifier passed to
+//! [`VapoursynthDecoder::register_node_modifier`](crate::VapoursynthDecoder::register_node_modifier),
+//! e.g. to decode with a different source plugin than the one a script uses. Each plugin
+//! must be installed in the VapourSynth environment.
+
+use std::path::Path;
+
+use vapoursynth::{api::API, core::CoreRef, map::OwnedMap, node::Node, plugin::Plugin};
+
+use crate::DecoderError;
+
+const LSMASH_ID: &str = "systems.innocent.lsmas";
+const FFMS2_ID: &str = "com.vapoursynth.ffms2";
+const BESTSOURCE_ID: &str = "com.vapoursynth.bestsource";
+const DGDECNV_ID: &str = "com.vapoursynth.dgdecodenv";
+
+/// Loads `input` with L-SMASH Works' `lsmas.LWLibavSource`.
+///
+/// `cache` controls whether L-SMASH Works writes an index file next to the input; `None`
+/// keeps the plugin's default.
+///
+/// # Errors
+///
+/// Returns [`DecoderError::MissingVapoursynthPlugin`] if L-SMASH Works is not installed,
+/// [`DecoderError::VapoursynthInternalError`] if it cannot load the file.
+#[inline]
+pub fn import_lsmash<'core>(
+    core: CoreRef<'core>,
+    input: impl AsRef<Path>,
+    cache: Option<bool>,
+) -> Result<Node<'core>, DecoderError> {
+    invoke_source(
+        core,
+        LSMASH_ID,
+        "LWLibavSource",
+        input.as_ref(),
+        |arguments| match cache {
+            Some(cache) => arguments.set_int("cache", i64::from(cache)),
+            None => Ok(()),
+        },
+    )
+}
+
+/// Loads `input` with `ffms2.Source`.
+///
+/// # Errors
+///
+/// Returns [`DecoderError::MissingVapoursynthPlugin`] if FFMS2 is not installed,
+/// [`DecoderError::VapoursynthInternalError`] if it cannot load the file.
+#[inline]
+pub fn import_ffms2<'core>(
+    core: CoreRef<'core>,
+    input: impl AsRef<Path>,
+) -> Result<Node<'core>, DecoderError> {
+    invoke_source(core, FFMS2_ID, "Source", input.as_ref(), |_| Ok(()))
+}
+
+/// Loads `input` with BestSource's `bs.VideoSource`.
+///
+/// # Errors
+///
+/// Returns [`DecoderError::MissingVapoursynthPlugin`] if BestSource is not installed,
+/// [`DecoderError::VapoursynthInternalError`] if it cannot load the file.
+#[inline]
+pub fn import_bestsource<'core>(
+    core: CoreRef<'core>,
+    input: impl AsRef<Path>,
+) -> Result<Node<'core>, DecoderError> {
+    invoke_source(core, BESTSOURCE_ID, "VideoSource", input.as_ref(), |_| {
+        Ok(())
+    })
+}
+
+/// Loads a DGIndexNV index file (`.dgi`) with DGDecNV's `dgdecodenv.DGSource`, which
+/// decodes on NVIDIA GPUs.
+///
+/// # Errors
+///
+/// Returns [`DecoderError::MissingVapoursynthPlugin`] if DGDecNV is not installed,
+/// [`DecoderError::VapoursynthInternalError`] if it cannot load the index.
+#[inline]
+pub fn import_dgdecnv<'core>(
+    core: CoreRef<'core>,
+    index: impl AsRef<Path>,
+) -> Result<Node<'core>, DecoderError> {
+    invoke_source(core, DGDECNV_ID, "DGSource", index.as_ref(), |_| Ok(()))
+}
+
+/// Looks up a plugin by its identifier.
+fn get_plugin<'core>(core: CoreRef<'core>, id: &str) -> Result<Plugin<'core>, DecoderError> {
+    core.get_plugin_by_id(id)
+        .ok()
+        .flatten()
+        .ok_or_else(|| DecoderError::MissingVapoursynthPlugin {
+            plugin: id.to_string(),
+        })
+}
+
+/// Calls `function` of the plugin `plugin_id` with `input` as its `source` argument,
+/// plus any arguments set by `set_arguments`.
+fn invoke_source<'core>(
+    core: CoreRef<'core>,
+    plugin_id: &str,
+    function: &str,
+    input: &Path,
+    set_arguments: impl FnOnce(&mut OwnedMap<'_>) -> Result<(), vapoursynth::map::Error>,
+) -> Result<Node<'core>, DecoderError> {
+    let plugin = get_plugin(core, plugin_id)?;
+    let api = API::get().ok_or_else(|| DecoderError::VapoursynthInternalError {
+        cause: "failed to get Vapoursynth API instance".to_string(),
+    })?;
+
+    let map_args_error = |e: vapoursynth::map::Error| DecoderError::VapoursynthArgsError {
+        cause: e.to_string(),
+    };
+    let mut arguments = OwnedMap::new(api);
+    arguments
+        .set("source", &input.as_os_str().as_encoded_bytes())
+        .map_err(map_args_error)?;
+    set_arguments(&mut arguments).map_err(map_args_error)?;
+
+    plugin
+        .invoke(function, &arguments)
+        .map_err(|e| DecoderError::VapoursynthInternalError {
+            cause: e.to_string(),
+        })?
+        .get_video_node("clip")
+        .map_err(|e| DecoderError::VapoursynthInternalError {
+            cause: e.to_string(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::Decoder;
+
+    #[test]
+    fn missing_plugin_is_reported() {
+        let script = "import vapoursynth as vs\n\
+            vs.core.std.BlankClip(length=1).set_output()";
+        let mut decoder = Decoder::from_script(script, HashMap::new()).expect("valid script");
+        let vapoursynth = decoder
+            .get_vapoursynth_impl()
+            .expect("scripts use VapourSynth");
+
+        let result = vapoursynth.register_node_modifier(Box::new(|core, _| {
+            invoke_source(
+                core,
+                "org.example.missing",
+                "Source",
+                Path::new("clip.mkv"),
+                |_| Ok(()),
+            )
+        }));
+        assert!(matches!(
+            result,
+            Err(DecoderError::MissingVapoursynthPlugin { plugin }) if plugin == "org.example.missing"
+        ));
+    }
+}
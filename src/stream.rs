@@ -0,0 +1,58 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_core::Stream;
+use tokio::sync::mpsc::{self, Receiver};
+use v_frame::pixel::Pixel;
+
+use crate::worker::{FrameResult, decode_all};
+use crate::{Decoder, DecoderError};
+
+/// Number of decoded frames buffered ahead of the consumer before decoding pauses.
+const STREAM_BUFFER_FRAMES: usize = 4;
+
+impl Decoder {
+    /// Decodes frames on Tokio's blocking thread pool and yields them as an async [`Stream`].
+    ///
+    /// `open` creates the decoder on the blocking thread, see
+    /// [decoding on other threads](crate#decoding-on-other-threads). Frames are passed
+    /// through a small bounded channel; decoding pauses while the channel is full and stops
+    /// once the stream is dropped.
+    ///
+    /// The stream ends after the last frame. Any other error, including one returned by
+    /// `open`, is yielded once and ends the stream.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a Tokio runtime.
+    #[inline]
+    #[must_use]
+    pub fn frame_stream<T, F>(open: F) -> FrameStream<T>
+    where
+        T: Pixel + Send + 'static,
+        F: FnOnce() -> Result<Decoder, DecoderError> + Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel(STREAM_BUFFER_FRAMES);
+        tokio::task::spawn_blocking(move || {
+            decode_all(open, |frame| tx.blocking_send(frame).is_ok());
+        });
+
+        FrameStream { receiver: rx }
+    }
+}
+
+/// Stream of frames produced by [`Decoder::frame_stream`].
+pub struct FrameStream<T: Pixel> {
+    receiver: Receiver<FrameResult<T>>,
+}
+
+impl<T: Pixel> Stream for FrameStream<T> {
+    type Item = FrameResult<T>;
+
+    #[inline]
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
@@ -0,0 +1,131 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(feature = "ffmpeg")]
+use ffmpeg_the_third::{Rational, codec, encoder, format, frame, packet};
+#[cfg(feature = "ffmpeg")]
+use v_frame::frame::Frame;
+
+/// Builds a 4x2 4:2:0 Y4M stream with one frame per entry of `frames`, each frame filled
+/// with that sample value.
+pub(crate) fn y4m_fixture(frames: &[u8]) -> Vec<u8> {
+    let mut data = b"YUV4MPEG2 W4 H2 F25:1 Ip A1:1 C420jpeg\n".to_vec();
+    for &value in frames {
+        data.extend_from_slice(b"FRAME\n");
+        data.extend_from_slice(&[value; 4 * 2 + 2 * 2]);
+    }
+    data
+}
+
+/// Returns a path in the temporary directory that no other test, or concurrent run of
+/// the test suite, uses.
+pub(crate) fn temp_path(name: &str) -> PathBuf {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    std::env::temp_dir().join(format!(
+        "av-decoders-{}-{}-{name}",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+/// Encodes a clip of `frames` frames at 25 fps into a temporary file named `name`, whose
+/// extension picks the muxer, and returns its path.
+///
+/// `configure` adjusts the encoder after its size and format are set, and `fill` draws
+/// each frame, given its index, before it is encoded.
+#[cfg(feature = "ffmpeg")]
+pub(crate) fn write_ffmpeg_clip(
+    name: &str,
+    codec_id: codec::Id,
+    pixel_format: format::Pixel,
+    (width, height): (u32, u32),
+    frames: usize,
+    configure: impl FnOnce(&mut encoder::video::Video),
+    mut fill: impl FnMut(usize, &mut frame::Video),
+) -> PathBuf {
+    ffmpeg_the_third::init().expect("FFmpeg initializes");
+    let path = temp_path(name);
+    let mut output = format::output(&path).expect("muxer for the file extension");
+    let global_header = output
+        .format()
+        .flags()
+        .contains(format::Flags::GLOBAL_HEADER);
+
+    let codec = encoder::find(codec_id).expect("encoder is available");
+    let mut encoder = codec::context::Context::new_with_codec(codec)
+        .encoder()
+        .video()
+        .expect("video encoder");
+    encoder.set_width(width);
+    encoder.set_height(height);
+    encoder.set_format(pixel_format);
+    encoder.set_time_base(Rational::new(1, 25));
+    encoder.set_frame_rate(Some(Rational::new(25, 1)));
+    if global_header {
+        encoder.set_flags(codec::Flags::GLOBAL_HEADER);
+    }
+    configure(&mut encoder);
+    let mut encoder = encoder.open_as(codec).expect("encoder opens");
+    let mut stream = output.add_stream(codec).expect("output stream");
+    stream.set_parameters(&encoder);
+    output.write_header().expect("container header");
+    let stream_time_base = output.stream(0).expect("output stream").time_base();
+
+    let write_packets = |encoder: &mut encoder::Video, output: &mut format::context::Output| {
+        let mut packet = packet::Packet::empty();
+        while encoder.receive_packet(&mut packet).is_ok() {
+            packet.set_stream(0);
+            packet.rescale_ts(Rational::new(1, 25), stream_time_base);
+            packet.write_interleaved(output).expect("packet is written");
+        }
+    };
+    for index in 0..frames {
+        let mut frame = frame::Video::new(pixel_format, width, height);
+        fill(index, &mut frame);
+        frame.set_pts(Some(index as i64));
+        encoder.send_frame(&frame).expect("frame is encoded");
+        write_packets(&mut encoder, &mut output);
+    }
+    encoder.send_eof().expect("encoder flushes");
+    write_packets(&mut encoder, &mut output);
+    output.write_trailer().expect("container trailer");
+    path
+}
+
+/// Writes one 8x4 uncompressed frame to a NUT file, with each plane filled with its entry
+/// of `values`. `pixel_format` must be planar with 16-bit little-endian samples.
+#[cfg(feature = "ffmpeg")]
+pub(crate) fn write_flat_raw_clip(pixel_format: format::Pixel, values: [u16; 3]) -> PathBuf {
+    write_ffmpeg_clip(
+        "flat.nut",
+        codec::Id::RAWVIDEO,
+        pixel_format,
+        (8, 4),
+        1,
+        |_| {},
+        |_, frame| {
+            for (plane, value) in values.into_iter().enumerate() {
+                for sample in frame.data_mut(plane).chunks_exact_mut(2) {
+                    sample.copy_from_slice(&value.to_le_bytes());
+                }
+            }
+        },
+    )
+}
+
+/// Asserts that every sample of each plane of `frame` equals its entry of `values`.
+#[cfg(feature = "ffmpeg")]
+pub(crate) fn assert_flat_frame(frame: &Frame<u16>, values: [u16; 3]) {
+    let planes = [
+        Some(&frame.y_plane),
+        frame.u_plane.as_ref(),
+        frame.v_plane.as_ref(),
+    ];
+    for (index, (plane, value)) in planes.into_iter().zip(values).enumerate() {
+        let plane = plane.expect("frame has chroma planes");
+        assert!(
+            plane.rows().flatten().all(|&sample| sample == value),
+            "plane {index} is not {value:#x}"
+        );
+    }
+}
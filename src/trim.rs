@@ -0,0 +1,165 @@
+use v_frame::{frame::Frame, pixel::Pixel};
+
+use crate::{Decoder, DecoderError, VideoDetails};
+
+impl Decoder {
+    /// Restricts decoding to the frames `[start, end)` of the source.
+    ///
+    /// Frames the decoder already read are not read again, so the range begins at `start`
+    /// or at the [current frame](Self::current_frame_index), whichever is later. The first
+    /// read moves there: seekable backends seek directly, while the others decode and
+    /// discard the frames before it. Reads return [`DecoderError::EndOfFile`] once `end` is
+    /// reached, or at the end of the stream when `end` is `None`. An `end` at or before the
+    /// start of the range yields no frames.
+    #[inline]
+    #[must_use]
+    pub fn with_trim(self, start: usize, end: Option<usize>) -> TrimmedDecoder {
+        let start = start.max(self.frames_read);
+        let end = end.map(|end| end.max(start));
+        let total_frames = match (end, self.video_details.total_frames) {
+            (Some(end), Some(total)) => Some(end.min(total).saturating_sub(start)),
+            (Some(end), None) => Some(end - start),
+            (None, total) => total.map(|total| total.saturating_sub(start)),
+        };
+        TrimmedDecoder {
+            video_details: VideoDetails {
+                total_frames,
+                ..self.video_details
+            },
+            decoder: self,
+            start,
+            end,
+            started: false,
+        }
+    }
+}
+
+/// A [`Decoder`] limited to a range of frames, created by [`Decoder::with_trim`].
+pub struct TrimmedDecoder {
+    decoder: Decoder,
+    video_details: VideoDetails,
+    start: usize,
+    end: Option<usize>,
+    /// Whether the decoder has been moved to `start`
+    started: bool,
+}
+
+impl TrimmedDecoder {
+    /// Returns the video details, with `total_frames` counting only the trimmed range.
+    #[inline]
+    #[must_use]
+    pub const fn get_video_details(&self) -> &VideoDetails {
+        &self.video_details
+    }
+
+    /// Decodes the next frame of the trimmed range.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DecoderError::EndOfFile`] once the end of the range or of the stream is
+    /// reached. Otherwise the same errors as [`Decoder::read_video_frame`].
+    #[inline]
+    pub fn read_video_frame<T: Pixel>(&mut self) -> Result<Frame<T>, DecoderError> {
+        if !self.started {
+            self.move_to_start()?;
+            self.started = true;
+        }
+        if self.end.is_some_and(|end| self.decoder.frames_read >= end) {
+            return Err(DecoderError::EndOfFile);
+        }
+        self.decoder.read_video_frame()
+    }
+
+    /// Seeks to `start`, falling back to skipping forward for backends that cannot seek.
+    ///
+    /// `start` is never before the frame the decoder was at when trimmed, so skipping
+    /// forward always reaches it.
+    fn move_to_start(&mut self) -> Result<(), DecoderError> {
+        match self.decoder.seek_to_frame(self.start) {
+            Err(DecoderError::UnsupportedDecoder) => self
+                .decoder
+                .skip_frames(self.start - self.decoder.frames_read),
+            result => result,
+        }
+    }
+
+    /// Returns the underlying decoder, positioned wherever the last read left it.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> Decoder {
+        self.decoder
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::y4m_fixture;
+
+    fn first_luma(frame: &Frame<u8>) -> u8 {
+        frame
+            .y_plane
+            .rows()
+            .flatten()
+            .copied()
+            .next()
+            .expect("luma")
+    }
+
+    #[test]
+    fn reads_only_the_trimmed_range() {
+        let decoder = Decoder::from_reader(std::io::Cursor::new(y4m_fixture(&[16, 32, 48])))
+            .expect("valid Y4M header");
+        let mut trimmed = decoder.with_trim(1, Some(2));
+        assert_eq!(trimmed.get_video_details().total_frames, Some(1));
+
+        let frame = trimmed.read_video_frame::<u8>().expect("frame 1");
+        assert_eq!(first_luma(&frame), 32);
+        assert!(
+            trimmed
+                .read_video_frame::<u8>()
+                .expect_err("end of range")
+                .is_eof()
+        );
+    }
+
+    #[test]
+    fn open_ended_trim_runs_to_end_of_stream() {
+        let decoder = Decoder::from_bytes(y4m_fixture(&[16, 32, 48])).expect("valid Y4M header");
+        let mut trimmed = decoder.with_trim(2, None);
+
+        let frame = trimmed.read_video_frame::<u8>().expect("frame 2");
+        assert_eq!(first_luma(&frame), 48);
+        assert!(
+            trimmed
+                .read_video_frame::<u8>()
+                .expect_err("end of stream")
+                .is_eof()
+        );
+    }
+
+    #[test]
+    fn trim_starts_after_frames_already_read() {
+        for seekable in [false, true] {
+            let data = y4m_fixture(&[16, 32, 48]);
+            let mut decoder = if seekable {
+                Decoder::from_bytes(data)
+            } else {
+                Decoder::from_reader(std::io::Cursor::new(data))
+            }
+            .expect("valid Y4M header");
+            decoder.read_video_frame::<u8>().expect("frame 0");
+
+            let mut trimmed = decoder.with_trim(0, Some(2));
+            assert_eq!(trimmed.get_video_details().total_frames, Some(1));
+            let frame = trimmed.read_video_frame::<u8>().expect("frame 1");
+            assert_eq!(first_luma(&frame), 32);
+            assert!(
+                trimmed
+                    .read_video_frame::<u8>()
+                    .expect_err("end of range")
+                    .is_eof()
+            );
+        }
+    }
+}
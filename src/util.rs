@@ -1,13 +1,109 @@
+use std::fmt::Write;
+use std::time::Duration;
+
+use num_rational::Rational32;
+
+/// Builds a sample aspect ratio, treating unknown (zero or out-of-range) values as square.
+pub(crate) fn sample_aspect_ratio(num: i64, den: i64) -> Rational32 {
+    match (i32::try_from(num), i32::try_from(den)) {
+        (Ok(num), Ok(den)) if num > 0 && den > 0 => Rational32::new(num, den),
+        _ => Rational32::new(1, 1),
+    }
+}
+
+/// Converts `ticks` of `time_base` seconds each to a [`Duration`], exactly up to the
+/// nanosecond. Returns `None` for a time base that is not positive.
+pub(crate) fn ticks_to_duration(ticks: u64, time_base: Rational32) -> Option<Duration> {
+    const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+    let numer = u128::try_from(*time_base.numer())
+        .ok()
+        .filter(|&numer| numer > 0)?;
+    let denom = u128::try_from(*time_base.denom())
+        .ok()
+        .filter(|&denom| denom > 0)?;
+    let nanos = u128::from(ticks) * numer * NANOS_PER_SEC / denom;
+    Some(Duration::new(
+        u64::try_from(nanos / NANOS_PER_SEC).ok()?,
+        (nanos % NANOS_PER_SEC) as u32,
+    ))
+}
+
+/// Converts a CIE 1931 chromaticity coordinate to a fraction, in the 0.00002 steps used by
+/// SMPTE ST 2086.
+#[cfg_attr(
+    not(any(feature = "vapoursynth", feature = "ffms2")),
+    expect(dead_code)
+)]
+pub(crate) fn chromaticity(value: f64) -> Rational32 {
+    Rational32::new((value * 50_000.0).round() as i32, 50_000)
+}
+
+/// Converts a luminance in cd/m² to a fraction, in the 0.0001 cd/m² steps used by
+/// SMPTE ST 2086.
+#[cfg_attr(
+    not(any(feature = "vapoursynth", feature = "ffms2")),
+    expect(dead_code)
+)]
+pub(crate) fn luminance(value: f64) -> Rational32 {
+    Rational32::new((value * 10_000.0).round() as i32, 10_000)
+}
+
+/// Escapes `s` for use inside a double-quoted Python string literal.
+///
+/// Backslashes (as in Windows paths), quotes, and control characters including NUL are
+/// escaped; other characters, including those outside the Basic Multilingual Plane, are
+/// kept as is, since scripts are evaluated as UTF-8.
 #[cfg_attr(not(feature = "vapoursynth"), expect(dead_code))]
 pub(crate) fn escape_python_string(s: &str) -> String {
-    s.chars()
-        .map(|c| match c {
-            '\\' => r"\\".to_string(),
-            '"' => r#"\""#.to_string(),
-            '\n' => r"\n".to_string(),
-            '\r' => r"\r".to_string(),
-            '\t' => r"\t".to_string(),
-            c => c.to_string(),
-        })
-        .collect()
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => escaped.push_str(r"\\"),
+            '"' => escaped.push_str(r#"\""#),
+            '\n' => escaped.push_str(r"\n"),
+            '\r' => escaped.push_str(r"\r"),
+            '\t' => escaped.push_str(r"\t"),
+            c if c.is_control() => {
+                write!(escaped, "\\u{:04x}", u32::from(c))
+                    .expect("writing to a String cannot fail");
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(all(test, feature = "vapoursynth"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_windows_paths() {
+        assert_eq!(
+            escape_python_string(r"C:\video\new.mkv"),
+            r"C:\\video\\new.mkv"
+        );
+        assert_eq!(
+            escape_python_string(r"\\server\share\clip.mkv"),
+            r"\\\\server\\share\\clip.mkv"
+        );
+    }
+
+    #[test]
+    fn escapes_quotes_and_control_characters() {
+        assert_eq!(
+            escape_python_string("a\"); import os; \"b"),
+            r#"a\"); import os; \"b"#
+        );
+        assert_eq!(escape_python_string("a\0b\x1bc\n"), r"a\u0000b\u001bc\n");
+    }
+
+    #[test]
+    fn keeps_unicode() {
+        assert_eq!(
+            escape_python_string("/videos/日本語/🎬 clip.mkv"),
+            "/videos/日本語/🎬 clip.mkv"
+        );
+    }
 }
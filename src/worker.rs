@@ -0,0 +1,43 @@
+use v_frame::{frame::Frame, pixel::Pixel};
+
+use crate::{Decoder, DecoderError};
+
+/// A decoded frame, or the error that ended decoding, as passed between threads.
+pub(crate) type FrameResult<T> = Result<Frame<T>, DecoderError>;
+
+/// Creates a decoder with `open` and hands every frame to `send`, or the error returned
+/// by `open`.
+pub(crate) fn decode_all<T: Pixel>(
+    open: impl FnOnce() -> Result<Decoder, DecoderError>,
+    mut send: impl FnMut(FrameResult<T>) -> bool,
+) {
+    match open() {
+        Ok(mut decoder) => send_frames(&mut decoder, None, send),
+        Err(e) => {
+            send(Err(e));
+        }
+    }
+}
+
+/// Reads up to `count` frames, or all remaining frames if `count` is `None`, and hands
+/// each one to `send`.
+///
+/// Stops after the first error, or once `send` returns `false` because the receiver hung
+/// up. Reaching the end of the stream is only sent as an error if it comes before
+/// `count` frames were read.
+pub(crate) fn send_frames<T: Pixel>(
+    decoder: &mut Decoder,
+    count: Option<usize>,
+    mut send: impl FnMut(FrameResult<T>) -> bool,
+) {
+    for _ in 0..count.unwrap_or(usize::MAX) {
+        let frame = decoder.read_video_frame::<T>();
+        if count.is_none() && frame.as_ref().is_err_and(DecoderError::is_eof) {
+            break;
+        }
+        let is_err = frame.is_err();
+        if !send(frame) || is_err {
+            break;
+        }
+    }
+}